@@ -0,0 +1,210 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::pattern::PathPattern;
+
+/// Distinct values selected by a wildcard path, counted in both documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueDistribution {
+    pub path: String,
+    pub values: Vec<DistributionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistributionEntry {
+    pub value: Value,
+    pub base_count: usize,
+    pub compare_count: usize,
+}
+
+/// Counts the distinct values selected by `pattern` in the base and compare documents,
+/// most frequent first.
+pub fn value_distribution(pattern: &str, base: &Value, compare: &Value) -> ValueDistribution {
+    let selector = PathPattern::parse(pattern);
+    let mut values: Vec<DistributionEntry> = Vec::new();
+    let mut positions = HashMap::new();
+
+    for (document, is_base) in [(base, true), (compare, false)] {
+        for (_, value) in selector.select(document) {
+            let position = *positions.entry(value.to_string()).or_insert_with(|| {
+                values.push(DistributionEntry {
+                    value: value.clone(),
+                    base_count: 0,
+                    compare_count: 0,
+                });
+                values.len() - 1
+            });
+            if is_base {
+                values[position].base_count += 1;
+            } else {
+                values[position].compare_count += 1;
+            }
+        }
+    }
+
+    // stable sort keeps first-seen order among equally frequent values
+    values.sort_by_key(|entry| std::cmp::Reverse(entry.base_count + entry.compare_count));
+
+    ValueDistribution {
+        path: pattern.to_string(),
+        values,
+    }
+}
+
+/// Summary of how far numeric values drifted between two documents.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeltaStatistics {
+    /// Number of paths holding a number in both documents
+    pub compared: usize,
+    /// How many of them changed at all, regardless of tolerances
+    pub changed: usize,
+    pub max_abs_delta: f64,
+    pub max_abs_delta_path: Option<String>,
+    pub mean_abs_delta: f64,
+    /// Largest change relative to the larger of the two values
+    pub max_rel_delta: f64,
+}
+
+/// Collects delta statistics over pairs of base and compare values at the same path,
+/// ignoring pairs that are not both numbers.
+pub fn delta_statistics<'a>(
+    pairs: impl IntoIterator<Item = (&'a str, &'a Value, &'a Value)>,
+) -> DeltaStatistics {
+    let mut statistics = DeltaStatistics::default();
+    let mut total_abs_delta = 0.0;
+
+    for (path, base, compare) in pairs {
+        let (Some(base), Some(compare)) = (base.as_f64(), compare.as_f64()) else {
+            continue;
+        };
+        let delta = (base - compare).abs();
+        statistics.compared += 1;
+        total_abs_delta += delta;
+        if delta == 0.0 {
+            continue;
+        }
+
+        statistics.changed += 1;
+        if delta > statistics.max_abs_delta {
+            statistics.max_abs_delta = delta;
+            statistics.max_abs_delta_path = Some(path.to_string());
+        }
+        let relative = delta / base.abs().max(compare.abs());
+        statistics.max_rel_delta = statistics.max_rel_delta.max(relative);
+    }
+
+    if statistics.compared > 0 {
+        statistics.mean_abs_delta = total_abs_delta / statistics.compared as f64;
+    }
+    statistics
+}
+
+/// How much the paths matched by `pattern` count towards the weighted similarity score,
+/// e.g. 10 for a payment endpoint and 0.1 for a tooltip; other paths count 1.
+#[derive(Debug, Clone)]
+pub struct PathWeight {
+    pub pattern: PathPattern,
+    pub weight: f64,
+}
+
+/// The weight of `path`: that of the last pattern matching it, or 1.
+pub fn path_weight(weights: &[PathWeight], path: &str) -> f64 {
+    weights
+        .iter()
+        .rev()
+        .find(|weight| weight.pattern.matches(path))
+        .map_or(1.0, |weight| weight.weight)
+}
+
+/// How much each kind of difference counts against the similarity score: 1 counts a path
+/// as unmatched, 0.5 as half matched and 0 leaves it out of the score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SimilarityWeights {
+    /// Base paths missing from the compare document
+    pub missing: f64,
+    /// Compare paths missing from the base document
+    pub extra: f64,
+    /// Paths in both documents whose values differ
+    pub different: f64,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        SimilarityWeights {
+            missing: 1.0,
+            extra: 1.0,
+            different: 1.0,
+        }
+    }
+}
+
+impl SimilarityWeights {
+    /// Parses weights such as `extra=0,different=0.5`; weights not given stay 1.
+    pub fn parse(s: &str) -> Result<SimilarityWeights> {
+        let mut weights = SimilarityWeights::default();
+        for entry in s
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let Some((kind, weight)) = entry.split_once('=') else {
+                bail!("invalid weight {:?}, expected e.g. extra=0.5", entry);
+            };
+            let weight: f64 = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid weight {:?}", entry))?;
+            if !(0.0..=1.0).contains(&weight) {
+                bail!("weight {:?} must be between 0 and 1", entry);
+            }
+            match kind.trim() {
+                "missing" => weights.missing = weight,
+                "extra" => weights.extra = weight,
+                "different" => weights.different = weight,
+                kind => bail!(
+                    "unknown weight {:?}, expected missing, extra or different",
+                    kind
+                ),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+/// The percentage of the union of both documents' paths that matched, with each
+/// unmatched path counted by the weight of its kind. Two empty documents are 100%
+/// similar.
+pub fn similarity(
+    matched: usize,
+    missing: usize,
+    extra: usize,
+    different: usize,
+    weights: &SimilarityWeights,
+) -> f64 {
+    weighted_similarity(
+        matched as f64,
+        missing as f64,
+        extra as f64,
+        different as f64,
+        weights,
+    )
+}
+
+/// Like `similarity`, with the paths of each kind given as the sum of their weights.
+pub fn weighted_similarity(
+    matched: f64,
+    missing: f64,
+    extra: f64,
+    different: f64,
+    weights: &SimilarityWeights,
+) -> f64 {
+    let total =
+        matched + missing * weights.missing + extra * weights.extra + different * weights.different;
+    if total == 0.0 {
+        100.0
+    } else {
+        matched * 100.0 / total
+    }
+}
@@ -0,0 +1,564 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::{capture_diff_slices, Algorithm};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use crate::hash::canonical_hash;
+use crate::json_diff::{child_path, get_value_by_path, parse_path, path_from_parts, PathPart};
+use crate::pattern::PathPattern;
+
+/// How the elements of arrays are paired between the base and compare documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayOrder {
+    /// Elements are paired by index
+    #[default]
+    Index,
+    /// Elements are matched as a multiset, whatever their order
+    Ignore,
+    /// Elements are aligned by a longest common subsequence, so an inserted or deleted
+    /// element doesn't shift the pairing of the elements after it
+    Lcs,
+}
+
+/// The array order for arrays at matching paths, replacing the global one; the last
+/// matching entry wins.
+#[derive(Debug, Clone)]
+pub struct PathArrayOrder {
+    pub pattern: PathPattern,
+    pub order: ArrayOrder,
+}
+
+/// Pairs the elements of the arrays at matching paths by the value of a field, such as
+/// `id`, instead of by index.
+#[derive(Debug, Clone)]
+pub struct ArrayKey {
+    pub pattern: PathPattern,
+    /// Path of the identifying value within each element, e.g. `id` or `meta.uid`
+    pub field: String,
+}
+
+impl ArrayKey {
+    /// Parses `<path>=<field>`, e.g. `items=id` or `orders[*].lines=sku`.
+    pub fn parse(s: &str) -> Result<ArrayKey> {
+        match s.split_once('=') {
+            Some((path, field)) if !field.trim().is_empty() => Ok(ArrayKey {
+                pattern: PathPattern::parse(path.trim()),
+                field: field.trim().to_string(),
+            }),
+            _ => bail!(
+                "invalid array key {:?}, expected <path>=<field> such as items=id",
+                s
+            ),
+        }
+    }
+}
+
+/// How the elements of one array are paired.
+enum Pairing<'a> {
+    Index,
+    Multiset,
+    Key(&'a str),
+    Sequence,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArrayMatchOptions {
+    pub order: ArrayOrder,
+    pub path_orders: Vec<PathArrayOrder>,
+    /// Key fields for the arrays at matching paths, taking precedence over the order;
+    /// the last matching entry wins
+    pub keys: Vec<ArrayKey>,
+}
+
+impl ArrayMatchOptions {
+    fn pairing_at(&self, path: &str) -> Pairing<'_> {
+        if let Some(key) = self.keys.iter().rev().find(|key| key.pattern.matches(path)) {
+            return Pairing::Key(&key.field);
+        }
+        match self.order_at(path) {
+            ArrayOrder::Index => Pairing::Index,
+            ArrayOrder::Ignore => Pairing::Multiset,
+            ArrayOrder::Lcs => Pairing::Sequence,
+        }
+    }
+
+    fn order_at(&self, path: &str) -> ArrayOrder {
+        self.path_orders
+            .iter()
+            .rev()
+            .find(|entry| entry.pattern.matches(path))
+            .map_or(self.order, |entry| entry.order)
+    }
+
+    fn is_index_everywhere(&self) -> bool {
+        self.order == ArrayOrder::Index
+            && self.keys.is_empty()
+            && self
+                .path_orders
+                .iter()
+                .all(|entry| entry.order == ArrayOrder::Index)
+    }
+}
+
+/// An array element without an equal counterpart in the other document, with its index
+/// in its own document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnmatchedElement {
+    pub index: usize,
+    pub value: Value,
+}
+
+/// The elements of an order-insensitive, keyed or LCS-aligned array found in only one of
+/// the documents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrayElementDiff {
+    pub path: String,
+    /// Base elements missing from the compare array (deleted)
+    pub missing: Vec<UnmatchedElement>,
+    /// Compare elements not in the base array (inserted)
+    pub extra: Vec<UnmatchedElement>,
+}
+
+/// How the elements of two arrays line up along their longest common subsequence.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceAlignment {
+    /// Base and compare indices of the elements kept in place: equal ones, and changed
+    /// ones between two equal runs
+    pub kept: Vec<(usize, usize)>,
+    /// Base indices of the deleted elements
+    pub deleted: Vec<usize>,
+    /// Compare indices of the inserted elements
+    pub inserted: Vec<usize>,
+}
+
+/// Pairs of elements that the differing middle of two arrays, between their common start
+/// and end, may have at most to be aligned along a common subsequence. Myers' algorithm
+/// takes time proportional to the number of differences times the lengths, which for
+/// arrays that differ throughout grows with this product.
+pub const MAX_ALIGNED_PAIRS: usize = 100_000_000;
+
+/// Aligns two arrays by their longest common subsequence of equal elements. Between two
+/// equal runs, as many elements as both arrays have there are kept as changed and the
+/// rest reported as deleted or inserted, so an element inserted at the front shows up as
+/// one insertion rather than as every later element being different. Returns `None` for
+/// arrays whose differing middle has more than [`MAX_ALIGNED_PAIRS`] pairs of elements.
+pub fn align_sequences(base: &[Value], compare: &[Value]) -> Option<SequenceAlignment> {
+    let hashes = |arr: &[Value]| arr.iter().map(canonical_hash).collect::<Vec<_>>();
+    sequence_alignment(&hashes(base), &hashes(compare))
+}
+
+fn sequence_alignment(base: &[u64], compare: &[u64]) -> Option<SequenceAlignment> {
+    let common_start = base.iter().zip(compare).take_while(|(a, b)| a == b).count();
+    let common_end = base[common_start..]
+        .iter()
+        .rev()
+        .zip(compare[common_start..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let middle = |len: usize| len - common_start - common_end;
+    if middle(base.len()).saturating_mul(middle(compare.len())) > MAX_ALIGNED_PAIRS {
+        return None;
+    }
+
+    let mut alignment = SequenceAlignment::default();
+    // Myers finds a longest common subsequence in O((N+M)·D) time for D differences and
+    // linear space, where a table of all pairs would grow with N·M
+    for op in capture_diff_slices(Algorithm::Myers, base, compare) {
+        // equal and replaced runs pair up as far as both sides reach
+        let (_, base_range, compare_range) = op.as_tag_tuple();
+        let kept = base_range.len().min(compare_range.len());
+        alignment
+            .kept
+            .extend(base_range.clone().zip(compare_range.clone()));
+        alignment.deleted.extend(base_range.skip(kept));
+        alignment.inserted.extend(compare_range.skip(kept));
+    }
+    Some(alignment)
+}
+
+/// The result of aligning the order-insensitive and keyed arrays of a compare document
+/// with the base document.
+#[derive(Debug, Clone, Default)]
+pub struct ArrayAlignment {
+    pub diffs: Vec<ArrayElementDiff>,
+    /// Paths of the LCS-aligned arrays too different to align, whose elements were
+    /// paired by index instead
+    pub unaligned: Vec<String>,
+    /// For arrays with missing elements, the compare index of every base index
+    index_maps: HashMap<String, Vec<Option<usize>>>,
+}
+
+impl ArrayAlignment {
+    /// The path in the aligned compare document of the value at a base path. Returns
+    /// `None` for the paths the diffs already report: arrays with unmatched elements and
+    /// everything within a missing element.
+    pub fn compare_path(&self, base_path: &str) -> Option<String> {
+        if self.diffs.iter().any(|diff| diff.path == base_path) {
+            return None;
+        }
+        if self.index_maps.is_empty() {
+            return Some(base_path.to_string());
+        }
+
+        let mut base_parts = Vec::new();
+        let mut compare_parts = Vec::new();
+        for part in parse_path(base_path) {
+            let mapped = match (&part, self.index_maps.get(&path_from_parts(&base_parts))) {
+                (PathPart::Index(index), Some(map)) => PathPart::Index(map[*index]?),
+                _ => part.clone(),
+            };
+            base_parts.push(part);
+            compare_parts.push(mapped);
+        }
+        Some(path_from_parts(&compare_parts))
+    }
+
+    /// Adds the diffs and index maps of an alignment of another part of the documents.
+    pub fn extend(&mut self, other: ArrayAlignment) {
+        self.diffs.extend(other.diffs);
+        self.unaligned.extend(other.unaligned);
+        self.index_maps.extend(other.index_maps);
+    }
+}
+
+/// Reorders the order-insensitive, keyed and LCS-aligned arrays of `compare` so the
+/// element paired with a base element sits at the base element's index, followed by the
+/// elements the base array doesn't have. Keyed elements are paired when their key fields
+/// are equal, others when the elements are equal apart from the order of the
+/// order-insensitive and keyed arrays inside them; tolerances don't apply to pairing.
+pub fn align_arrays(
+    compare: &mut Value,
+    base: &Value,
+    options: &ArrayMatchOptions,
+) -> ArrayAlignment {
+    let mut alignment = ArrayAlignment::default();
+    if !options.is_index_everywhere() {
+        align(compare, base, "", options, &mut alignment);
+    }
+    alignment
+}
+
+/// Like [`align_arrays`], for the values at `path` of both documents only.
+pub fn align_arrays_at(
+    compare: &mut Value,
+    base: &Value,
+    path: &str,
+    options: &ArrayMatchOptions,
+) -> ArrayAlignment {
+    let mut alignment = ArrayAlignment::default();
+    if !options.is_index_everywhere() {
+        align(compare, base, path, options, &mut alignment);
+    }
+    alignment
+}
+
+fn align(
+    compare: &mut Value,
+    base: &Value,
+    path: &str,
+    options: &ArrayMatchOptions,
+    alignment: &mut ArrayAlignment,
+) {
+    match (compare, base) {
+        (Value::Object(compare_map), Value::Object(base_map)) => {
+            for (key, base_child) in base_map {
+                if let Some(compare_child) = compare_map.get_mut(key) {
+                    let path = child_path(path, key);
+                    align(compare_child, base_child, &path, options, alignment);
+                }
+            }
+        }
+        (Value::Array(compare_arr), Value::Array(base_arr))
+            if !matches!(options.pairing_at(path), Pairing::Index) =>
+        {
+            let identity = |element: &Value, index: usize| match options.pairing_at(path) {
+                Pairing::Key(field) => get_value_by_path(element, field).map(canonical_hash),
+                _ => Some(unordered_hash(element, &index_path(path, index), options)),
+            };
+            let pairs = if matches!(options.pairing_at(path), Pairing::Sequence) {
+                let hashes = |arr: &[Value]| -> Vec<u64> {
+                    let elements = arr.iter().enumerate();
+                    elements
+                        .map(|(i, element)| unordered_hash(element, &index_path(path, i), options))
+                        .collect()
+                };
+                let Some(sequence) = sequence_alignment(&hashes(base_arr), &hashes(compare_arr))
+                else {
+                    alignment.unaligned.push(path.to_string());
+                    align_by_index(compare_arr, base_arr, path, options, alignment);
+                    return;
+                };
+                let mut pairs = vec![None; base_arr.len()];
+                for (index, compare_index) in sequence.kept {
+                    pairs[index] = Some(compare_index);
+                }
+                pairs
+            } else {
+                pair_by_identity(compare_arr, base_arr, identity)
+            };
+            let (aligned, index_map, extra) = rearrange(std::mem::take(compare_arr), &pairs);
+            *compare_arr = aligned;
+            // paired elements are compared below their base paths, and the arrays inside
+            // them aligned in turn
+            for (index, compare_index) in index_map.iter().enumerate() {
+                if let Some(compare_index) = compare_index {
+                    let path = index_path(path, index);
+                    let element = &mut compare_arr[*compare_index];
+                    align(element, &base_arr[index], &path, options, alignment);
+                }
+            }
+
+            let missing: Vec<UnmatchedElement> = index_map
+                .iter()
+                .enumerate()
+                .filter(|(_, compare_index)| compare_index.is_none())
+                .map(|(index, _)| UnmatchedElement {
+                    index,
+                    value: base_arr[index].clone(),
+                })
+                .collect();
+            if !missing.is_empty() {
+                alignment.index_maps.insert(path.to_string(), index_map);
+            }
+            if !missing.is_empty() || !extra.is_empty() {
+                alignment.diffs.push(ArrayElementDiff {
+                    path: path.to_string(),
+                    missing,
+                    extra,
+                });
+            }
+        }
+        (Value::Array(compare_arr), Value::Array(base_arr)) => {
+            align_by_index(compare_arr, base_arr, path, options, alignment)
+        }
+        _ => {}
+    }
+}
+
+/// Aligns the arrays inside the elements of two arrays paired by index.
+fn align_by_index(
+    compare_arr: &mut [Value],
+    base_arr: &[Value],
+    path: &str,
+    options: &ArrayMatchOptions,
+    alignment: &mut ArrayAlignment,
+) {
+    for (index, (compare_child, base_child)) in compare_arr.iter_mut().zip(base_arr).enumerate() {
+        let path = index_path(path, index);
+        align(compare_child, base_child, &path, options, alignment);
+    }
+}
+
+/// Pairs every base element with the first not yet paired compare element of the same
+/// identity, returning the compare index paired with each base index; elements without
+/// an identity stay unpaired.
+fn pair_by_identity(
+    compare_arr: &[Value],
+    base_arr: &[Value],
+    identity: impl Fn(&Value, usize) -> Option<u64>,
+) -> Vec<Option<usize>> {
+    let mut candidates: HashMap<u64, VecDeque<usize>> = HashMap::new();
+    for (index, element) in compare_arr.iter().enumerate() {
+        if let Some(hash) = identity(element, index) {
+            candidates.entry(hash).or_default().push_back(index);
+        }
+    }
+
+    base_arr
+        .iter()
+        .enumerate()
+        .map(|(index, element)| candidates.get_mut(&identity(element, index)?)?.pop_front())
+        .collect()
+}
+
+/// Moves the compare elements paired with base elements to the front, in base order.
+/// Returns the rearranged elements, the index of each base element's pair among them,
+/// and the unpaired compare elements, which follow the paired ones.
+fn rearrange(
+    compare_arr: Vec<Value>,
+    pairs: &[Option<usize>],
+) -> (Vec<Value>, Vec<Option<usize>>, Vec<UnmatchedElement>) {
+    let mut elements: Vec<Option<Value>> = compare_arr.into_iter().map(Some).collect();
+    let mut aligned = Vec::with_capacity(elements.len());
+    let index_map = pairs
+        .iter()
+        .map(|compare_index| {
+            aligned.push(elements[(*compare_index)?].take()?);
+            Some(aligned.len() - 1)
+        })
+        .collect();
+
+    let extra: Vec<UnmatchedElement> = elements
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, element)| {
+            Some(UnmatchedElement {
+                index,
+                value: element?,
+            })
+        })
+        .collect();
+    aligned.extend(extra.iter().map(|element| element.value.clone()));
+    (aligned, index_map, extra)
+}
+
+/// A hash that is the same for values equal apart from the order of the elements of
+/// their order-insensitive and keyed arrays.
+fn unordered_hash(value: &Value, path: &str, options: &ArrayMatchOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match value {
+        Value::Array(arr) => {
+            let mut hashes: Vec<u64> = arr
+                .iter()
+                .enumerate()
+                .map(|(index, child)| unordered_hash(child, &index_path(path, index), options))
+                .collect();
+            if matches!(
+                options.pairing_at(path),
+                Pairing::Multiset | Pairing::Key(_)
+            ) {
+                hashes.sort_unstable();
+            }
+            ("array", hashes).hash(&mut hasher);
+        }
+        Value::Object(map) => {
+            "object".hash(&mut hasher);
+            for (key, child) in map {
+                (key, unordered_hash(child, &child_path(path, key), options)).hash(&mut hasher);
+            }
+        }
+        _ => return canonical_hash(value),
+    }
+    hasher.finish()
+}
+
+fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(value: Value) -> Vec<Value> {
+        value.as_array().unwrap().clone()
+    }
+
+    fn lcs() -> ArrayMatchOptions {
+        ArrayMatchOptions {
+            order: ArrayOrder::Lcs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn insertion_at_the_front_leaves_later_elements_paired() {
+        let alignment =
+            align_sequences(&values(json!([1, 2, 3])), &values(json!([0, 1, 2, 3]))).unwrap();
+        assert_eq!(alignment.kept, [(0, 1), (1, 2), (2, 3)]);
+        assert!(alignment.deleted.is_empty());
+        assert_eq!(alignment.inserted, [0]);
+    }
+
+    #[test]
+    fn deletion_in_the_middle_is_reported_once() {
+        let alignment =
+            align_sequences(&values(json!([1, 2, 3, 4])), &values(json!([1, 3, 4]))).unwrap();
+        assert_eq!(alignment.kept, [(0, 0), (2, 1), (3, 2)]);
+        assert_eq!(alignment.deleted, [1]);
+        assert!(alignment.inserted.is_empty());
+    }
+
+    #[test]
+    fn changed_elements_between_equal_runs_are_kept() {
+        let alignment = align_sequences(
+            &values(json!(["a", "b", "c", "d"])),
+            &values(json!(["a", "x", "y", "z", "d"])),
+        )
+        .unwrap();
+        assert_eq!(alignment.kept, [(0, 0), (1, 1), (2, 2), (3, 4)]);
+        assert!(alignment.deleted.is_empty());
+        assert_eq!(alignment.inserted, [3]);
+    }
+
+    #[test]
+    fn lcs_arrays_move_inserted_elements_to_the_end() {
+        let base = json!({"steps": [{"id": 1}, {"id": 2}]});
+        let mut compare = json!({"steps": [{"id": 0}, {"id": 1}, {"id": 2}]});
+        let alignment = align_arrays(&mut compare, &base, &lcs());
+        assert_eq!(compare, json!({"steps": [{"id": 1}, {"id": 2}, {"id": 0}]}));
+        assert_eq!(alignment.diffs.len(), 1);
+        assert_eq!(alignment.diffs[0].path, "steps");
+        assert!(alignment.diffs[0].missing.is_empty());
+        assert_eq!(alignment.diffs[0].extra[0].index, 0);
+        assert_eq!(
+            alignment.compare_path("steps[1].id").unwrap(),
+            "steps[1].id"
+        );
+    }
+
+    #[test]
+    fn missing_elements_map_later_base_paths() {
+        let base = json!([1, 2, 3]);
+        let mut compare = json!([1, 3]);
+        let alignment = align_arrays(&mut compare, &base, &lcs());
+        assert_eq!(alignment.diffs[0].missing[0].index, 1);
+        assert_eq!(alignment.compare_path("[2]").unwrap(), "[1]");
+        assert_eq!(alignment.compare_path("[1]"), None);
+    }
+
+    #[test]
+    fn align_arrays_at_pairs_by_the_options_of_its_path() {
+        let options = ArrayMatchOptions {
+            path_orders: vec![PathArrayOrder {
+                pattern: PathPattern::parse("list"),
+                order: ArrayOrder::Ignore,
+            }],
+            ..Default::default()
+        };
+        let mut compare = json!([1, 3, 4]);
+        let mut alignment = align_arrays_at(&mut compare, &json!([3, 1]), "list", &options);
+        assert_eq!(compare, json!([3, 1, 4]));
+        assert_eq!(alignment.diffs[0].path, "list");
+        assert_eq!(alignment.diffs[0].extra[0].index, 2);
+
+        let mut compare = json!([1, 3, 4]);
+        alignment.extend(align_arrays_at(
+            &mut compare,
+            &json!([3, 1]),
+            "other",
+            &options,
+        ));
+        assert_eq!(compare, json!([1, 3, 4]));
+        assert_eq!(alignment.diffs.len(), 1);
+    }
+
+    #[test]
+    fn arrays_too_different_to_align_are_paired_by_index() {
+        let base: Vec<usize> = (0..10_001).collect();
+        let reversed: Vec<usize> = base.iter().rev().copied().collect();
+        assert!(align_sequences(&values(json!(base)), &values(json!(reversed))).is_none());
+
+        let base = json!({"series": base, "other": [1, 2]});
+        let mut compare = json!({"series": reversed, "other": [0, 1, 2]});
+        let alignment = align_arrays(&mut compare, &base, &lcs());
+        assert_eq!(alignment.unaligned, ["series"]);
+        assert_eq!(compare["series"][0], json!(10_000));
+        // the other arrays are still aligned
+        assert_eq!(alignment.diffs.len(), 1);
+        assert_eq!(alignment.diffs[0].path, "other");
+
+        // a long common start and end leave a small middle to align
+        let base: Vec<usize> = (0..20_000).collect();
+        let mut inserted = base.clone();
+        inserted.insert(10_000, 0);
+        let alignment = align_sequences(&values(json!(base)), &values(json!(inserted))).unwrap();
+        assert_eq!(alignment.inserted, [10_000]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Baseline files of accepted differences, so a run on files with known drift only
+//! reports, and fails on, the differences that are new since the baseline was recorded.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::render::Category;
+use crate::{ComparisonResult, PathStyle};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    /// Accepted differences by compare file
+    files: BTreeMap<String, Vec<AcceptedDiff>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AcceptedDiff {
+    category: String,
+    path: String,
+    /// The compare value of a different value or type mismatch, so a value that changes
+    /// again is reported as new
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<Value>,
+}
+
+type Fingerprint = (String, String, Option<String>);
+
+fn fingerprint(category: Category, path: &str, value: Option<&Value>) -> Fingerprint {
+    (
+        category.key().to_string(),
+        path.to_string(),
+        value.map(Value::to_string),
+    )
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Baseline> {
+        let content = fs::read(path).with_context(|| {
+            format!(
+                "Failed to read baseline {:?}; record one with --update-baseline",
+                path
+            )
+        })?;
+        serde_json::from_slice(&content).with_context(|| format!("Invalid baseline {:?}", path))
+    }
+
+    /// The compare files with accepted differences and how many each has.
+    pub fn files(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.files
+            .iter()
+            .map(|(file, diffs)| (file.as_str(), diffs.len()))
+    }
+
+    /// Removes the differences the baseline accepts from a result, returning how many.
+    /// Paths are matched in dotted form, whatever `--path-style` the result and the
+    /// baseline were written in.
+    pub fn suppress(&self, result: &mut ComparisonResult) -> usize {
+        let Some(accepted) = self.files.get(&result.compare_file) else {
+            return 0;
+        };
+        // baselines recorded with `--path-style pointer` before paths were recorded in
+        // dotted form hold JSON Pointers, which a dotted path never starts with unless a
+        // key does
+        let accepted: HashSet<Fingerprint> = accepted
+            .iter()
+            .flat_map(|diff| {
+                let pointer = diff
+                    .path
+                    .starts_with('/')
+                    .then(|| PathStyle::Pointer.to_dotted(&diff.path));
+                [Some(diff.path.clone()), pointer]
+                    .into_iter()
+                    .flatten()
+                    .map(|path| {
+                        (
+                            diff.category.clone(),
+                            path,
+                            diff.value.as_ref().map(Value::to_string),
+                        )
+                    })
+            })
+            .collect();
+        let path_style = result.path_style;
+        result.retain_diffs(|category, path, value| {
+            !accepted.contains(&fingerprint(category, &path_style.to_dotted(path), value))
+        })
+    }
+
+    /// Records every difference of a result as accepted, with its path in dotted form,
+    /// replacing those recorded for the compare file before.
+    pub fn record(&mut self, result: &mut ComparisonResult) {
+        let mut diffs = Vec::new();
+        let path_style = result.path_style;
+        result.retain_diffs(|category, path, value| {
+            diffs.push(AcceptedDiff {
+                category: category.key().to_string(),
+                path: path_style.to_dotted(path),
+                value: value.cloned(),
+            });
+            true
+        });
+        self.files.insert(result.compare_file.clone(), diffs);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write baseline {:?}", path))
+    }
+}
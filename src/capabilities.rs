@@ -0,0 +1,90 @@
+//! `--capabilities`, a JSON description of what this build supports, so tooling that
+//! drives different installed versions can check for a format or rule before using it.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::process::ExitCode;
+
+use crate::render::OutputStyle;
+use crate::{ExportFormat, FailOn, Mode};
+use json_diff_checker::comparators::COMPARATOR_TYPES;
+use json_diff_checker::compare::DiffKind;
+use json_diff_checker::input::InputFormat;
+use json_diff_checker::rules::RULE_KEYS;
+use json_diff_checker::strings::StringNormalization;
+
+#[derive(Serialize)]
+struct Capabilities {
+    version: &'static str,
+    /// The cargo features this build was compiled with
+    features: Vec<&'static str>,
+    /// `--format` values
+    input_formats: Vec<String>,
+    /// Compression detected on inputs and applied to exports by their extension
+    compression: Vec<&'static str>,
+    /// `--mode` values
+    modes: Vec<String>,
+    /// Built-in comparator types of the rules file, and `script` with `--comparator`
+    comparators: Vec<&'static str>,
+    /// `--normalize-strings` values
+    string_normalizations: Vec<String>,
+    /// `--output` values
+    output_formats: Vec<String>,
+    /// `--export-format` values
+    export_formats: Vec<String>,
+    /// `--fail-on` and `--exit-code` kinds
+    fail_on: Vec<String>,
+    /// `--only` kinds
+    only: Vec<String>,
+    /// Top-level keys of a rules file
+    rule_keys: Vec<&'static str>,
+}
+
+/// The names an option accepts for a value enum, as clap spells them.
+fn names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|variant| variant.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+fn features() -> Vec<&'static str> {
+    [
+        ("http", cfg!(feature = "http")),
+        ("arbitrary_precision", cfg!(feature = "arbitrary_precision")),
+        ("rayon", cfg!(feature = "rayon")),
+        ("collation", cfg!(feature = "collation")),
+        ("scripting", cfg!(feature = "scripting")),
+        ("tui", cfg!(feature = "tui")),
+    ]
+    .into_iter()
+    .filter(|&(_, enabled)| enabled)
+    .map(|(feature, _)| feature)
+    .collect()
+}
+
+/// Prints the capabilities of this build as pretty-printed JSON.
+pub fn run() -> Result<ExitCode> {
+    let mut comparators = COMPARATOR_TYPES.to_vec();
+    if cfg!(feature = "scripting") {
+        comparators.push("script");
+    }
+    let capabilities = Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: features(),
+        input_formats: names::<InputFormat>(),
+        compression: vec!["gzip", "zstd"],
+        modes: names::<Mode>(),
+        comparators,
+        string_normalizations: names::<StringNormalization>(),
+        output_formats: names::<OutputStyle>(),
+        export_formats: names::<ExportFormat>(),
+        fail_on: names::<FailOn>(),
+        only: names::<DiffKind>(),
+        rule_keys: RULE_KEYS.to_vec(),
+    };
+    println!("{}", serde_json::to_string_pretty(&capabilities)?);
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,178 @@
+//! Checkpoint files for resumable runs: the result of every compare file is appended as
+//! one JSON line once it's done, and a re-run takes them from the file instead of
+//! comparing those files again.
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::ComparisonResult;
+
+pub struct Checkpoint {
+    path: PathBuf,
+    file: File,
+    /// Results of an earlier run by compare file, in the order they were compared
+    completed: HashMap<String, VecDeque<ComparisonResult>>,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint at `path`, reading the results an earlier run against
+    /// `base_file` left in it.
+    pub fn open(path: &Path, base_file: &str) -> Result<Checkpoint> {
+        let mut completed: HashMap<String, VecDeque<ComparisonResult>> = HashMap::new();
+        if path.exists() {
+            let mut content =
+                fs::read(path).with_context(|| format!("Failed to read checkpoint {:?}", path))?;
+            // a run stopped while writing leaves its last line incomplete, which is
+            // dropped so appending continues on a line of its own
+            if content.last().is_some_and(|byte| *byte != b'\n') {
+                content.truncate(
+                    content
+                        .iter()
+                        .rposition(|byte| *byte == b'\n')
+                        .map_or(0, |end| end + 1),
+                );
+                fs::write(path, &content)?;
+            }
+            let content = String::from_utf8(content)
+                .with_context(|| format!("Invalid checkpoint {:?}", path))?;
+            for line in content.lines().filter(|line| !line.is_empty()) {
+                let result: ComparisonResult = serde_json::from_str(line)
+                    .with_context(|| format!("Invalid checkpoint {:?}", path))?;
+                if result.base_file != base_file {
+                    anyhow::bail!(
+                        "Checkpoint {:?} was written comparing against {}, not {}",
+                        path,
+                        result.base_file,
+                        base_file
+                    );
+                }
+                completed
+                    .entry(result.compare_file.clone())
+                    .or_default()
+                    .push_back(result);
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open checkpoint {:?}", path))?;
+        Ok(Checkpoint {
+            path: path.to_path_buf(),
+            file,
+            completed,
+        })
+    }
+
+    /// Takes the result an earlier run recorded for `compare_file`, if any. A file
+    /// compared several times, e.g. by manifest entries, gets its results in order.
+    pub fn take(&mut self, compare_file: &str) -> Option<ComparisonResult> {
+        self.completed.get_mut(compare_file)?.pop_front()
+    }
+
+    /// Appends the result of a compare file.
+    pub fn record(&mut self, result: &ComparisonResult) -> Result<()> {
+        let mut line = serde_json::to_string(result)?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data())
+            .with_context(|| format!("Failed to write checkpoint {:?}", self.path))
+    }
+
+    /// Removes the checkpoint once the run is complete.
+    pub fn finish(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .with_context(|| format!("Failed to remove checkpoint {:?}", self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(compare_file: &str, total_paths_checked: usize) -> ComparisonResult {
+        serde_json::from_value(json!({
+            "base_file": "base.json",
+            "compare_file": compare_file,
+            "missing_paths": [],
+            "different_values": [],
+            "type_mismatches": [],
+            "statistics": {
+                "total_paths_checked": total_paths_checked,
+                "missing_count": 0,
+                "different_count": 0,
+                "type_mismatch_count": 0,
+                "nullability_change_count": 0,
+                "match_count": total_paths_checked,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "json-diff-checker-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn resumed_runs_take_recorded_results_in_order() {
+        let path = checkpoint_path("resume");
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        assert!(checkpoint.take("a.json").is_none());
+        checkpoint.record(&result("a.json", 1)).unwrap();
+        checkpoint.record(&result("b.json", 2)).unwrap();
+        checkpoint.record(&result("a.json", 3)).unwrap();
+        drop(checkpoint);
+
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        let checked =
+            |result: Option<ComparisonResult>| result.unwrap().statistics.total_paths_checked;
+        assert_eq!(checked(checkpoint.take("a.json")), 1);
+        assert_eq!(checked(checkpoint.take("a.json")), 3);
+        assert!(checkpoint.take("a.json").is_none());
+        assert_eq!(checked(checkpoint.take("b.json")), 2);
+        checkpoint.finish().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn incomplete_last_line_is_dropped() {
+        let path = checkpoint_path("incomplete");
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        checkpoint.record(&result("a.json", 1)).unwrap();
+        drop(checkpoint);
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"base_file\": \"base.js");
+        fs::write(&path, content).unwrap();
+
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        checkpoint.record(&result("b.json", 2)).unwrap();
+        drop(checkpoint);
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        assert!(checkpoint.take("a.json").is_some());
+        assert!(checkpoint.take("b.json").is_some());
+        checkpoint.finish().unwrap();
+    }
+
+    #[test]
+    fn checkpoint_of_another_base_file_is_refused() {
+        let path = checkpoint_path("other-base");
+        let mut checkpoint = Checkpoint::open(&path, "base.json").unwrap();
+        checkpoint.record(&result("a.json", 1)).unwrap();
+        drop(checkpoint);
+        let error = Checkpoint::open(&path, "other.json").err().unwrap();
+        assert!(error.to_string().contains("comparing against base.json"));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Which differences between strings a collation tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollationStrength {
+    /// Base letters only, so case, accents and variants such as German `ß` and `ss`
+    /// are ignored
+    #[default]
+    Primary,
+    /// Base letters and accents; case is ignored
+    Secondary,
+    /// Base letters, accents and case
+    Tertiary,
+}
+
+/// Compares strings by the collation rules of a locale, so strings the locale sorts as
+/// equivalent at the given strength are equal.
+#[derive(Clone)]
+pub struct Collation {
+    locale: String,
+    strength: CollationStrength,
+    #[cfg(feature = "collation")]
+    collator: std::sync::Arc<icu_collator::Collator>,
+}
+
+impl Collation {
+    /// Creates the collation of a BCP 47 locale such as `de` or `sv-SE`. Fails when the
+    /// locale can't be parsed, or without the `collation` feature.
+    #[cfg(feature = "collation")]
+    pub fn new(locale: &str, strength: CollationStrength) -> Result<Collation> {
+        use icu_collator::{Collator, CollatorOptions, Strength};
+        use icu_locid::Locale;
+
+        let parsed: Locale = locale
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid collation locale {:?}: {}", locale, e))?;
+        let mut options = CollatorOptions::new();
+        options.strength = Some(match strength {
+            CollationStrength::Primary => Strength::Primary,
+            CollationStrength::Secondary => Strength::Secondary,
+            CollationStrength::Tertiary => Strength::Tertiary,
+        });
+        let collator = Collator::try_new(&(&parsed).into(), options)
+            .map_err(|e| anyhow::anyhow!("No collation data for locale {:?}: {}", locale, e))?;
+        Ok(Collation {
+            locale: locale.to_string(),
+            strength,
+            collator: std::sync::Arc::new(collator),
+        })
+    }
+
+    #[cfg(not(feature = "collation"))]
+    pub fn new(locale: &str, _strength: CollationStrength) -> Result<Collation> {
+        anyhow::bail!(
+            "Collation for locale {:?} needs ICU, which this build leaves out; rebuild with --features collation",
+            locale
+        )
+    }
+
+    pub fn equal(&self, a: &str, b: &str) -> bool {
+        #[cfg(feature = "collation")]
+        return self.collator.compare(a, b) == std::cmp::Ordering::Equal;
+        #[cfg(not(feature = "collation"))]
+        return a == b;
+    }
+}
+
+impl fmt::Debug for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collation")
+            .field("locale", &self.locale)
+            .field("strength", &self.strength)
+            .finish()
+    }
+}
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::json_diff::Decimal;
+
+/// E.164 numbers have at most 15 digits after the `+`.
+const E164_MAX_DIGITS: usize = 15;
+
+/// The `type` of each built-in comparator.
+pub const COMPARATOR_TYPES: &[&str] = &["phone", "email", "money"];
+
+/// A built-in comparison for values that are written in several equivalent ways.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Comparator {
+    /// Phone numbers, equal when they normalize to the same E.164 number, so
+    /// `(555) 123-4567`, `555.123.4567` and `+1 555 123 4567` are equal with a default
+    /// country code of 1
+    Phone {
+        /// Country calling code for numbers written without one, e.g. `44`; a leading
+        /// trunk `0` is dropped from them
+        #[serde(default)]
+        default_country_code: Option<u16>,
+    },
+    /// Email addresses, equal when they match with the domain lowercased
+    Email {
+        /// Also ignore a `+tag` in the local part, as in `jane+news@example.com`
+        #[serde(default)]
+        strip_plus_tags: bool,
+    },
+    /// Monetary amounts, compared exactly as decimals: numbers, decimal strings and
+    /// objects holding an amount in minor units with its scale, so `10.5`, `"10.50"` and
+    /// `{"amount": 1050, "scale": 2}` are equal
+    Money {
+        /// Field holding the amount in objects, a number or decimal string
+        #[serde(default = "default_amount_field")]
+        amount_field: String,
+        /// Field holding the number of decimal places of the amount in objects; the
+        /// amount is taken as is when the field is absent
+        #[serde(default = "default_scale_field")]
+        scale_field: String,
+        /// Field holding the currency in objects, e.g. `"EUR"`, which must also match
+        /// when both values have one
+        #[serde(default)]
+        currency_field: Option<String>,
+    },
+}
+
+fn default_amount_field() -> String {
+    "amount".to_string()
+}
+
+fn default_scale_field() -> String {
+    "scale".to_string()
+}
+
+impl Comparator {
+    /// The comparator's `type` in a rules file, e.g. `phone`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Comparator::Phone { .. } => "phone",
+            Comparator::Email { .. } => "email",
+            Comparator::Money { .. } => "money",
+        }
+    }
+
+    /// Compares two values, returning `None` when either can't be read by the
+    /// comparator so the default comparison applies.
+    pub fn equal(&self, a: &Value, b: &Value) -> Option<bool> {
+        match self {
+            Comparator::Phone {
+                default_country_code,
+            } => {
+                let normalize = |value: &Value| match value {
+                    Value::String(s) => normalize_phone(s, *default_country_code),
+                    Value::Number(n) if n.is_u64() => {
+                        normalize_phone(&n.to_string(), *default_country_code)
+                    }
+                    _ => None,
+                };
+                Some(normalize(a)? == normalize(b)?)
+            }
+            Comparator::Email { strip_plus_tags } => {
+                let (a, b) = (a.as_str()?, b.as_str()?);
+                Some(normalize_email(a, *strip_plus_tags)? == normalize_email(b, *strip_plus_tags)?)
+            }
+            Comparator::Money {
+                amount_field,
+                scale_field,
+                currency_field,
+            } => {
+                let read = |value| read_money(value, amount_field, scale_field, currency_field);
+                let ((amount_a, currency_a), (amount_b, currency_b)) = (read(a)?, read(b)?);
+                Some(
+                    amount_a == amount_b
+                        && match (currency_a, currency_b) {
+                            (Some(a), Some(b)) => a == b,
+                            _ => true,
+                        },
+                )
+            }
+        }
+    }
+}
+
+/// Reads a monetary amount and, for objects, its currency.
+fn read_money<'a>(
+    value: &'a Value,
+    amount_field: &str,
+    scale_field: &str,
+    currency_field: &Option<String>,
+) -> Option<(Decimal, Option<&'a str>)> {
+    let decimal = |value: &Value| match value {
+        Value::Number(n) => Some(Decimal::from_number(n)),
+        Value::String(s) => Decimal::parse(s.trim()),
+        _ => None,
+    };
+    match value {
+        Value::Object(map) => {
+            let amount = decimal(map.get(amount_field)?)?;
+            let amount = match map.get(scale_field) {
+                Some(scale) => amount.scaled(scale.as_i64()?),
+                None => amount,
+            };
+            let currency = currency_field
+                .as_ref()
+                .and_then(|field| map.get(field))
+                .and_then(Value::as_str);
+            Some((amount, currency))
+        }
+        value => Some((decimal(value)?, None)),
+    }
+}
+
+/// Normalizes a phone number to E.164, e.g. `+15551234567`, keeping an extension as
+/// `;ext=123`. Numbers without a country code keep only their digits unless a default
+/// country code is given. Returns `None` for strings that aren't phone numbers.
+pub fn normalize_phone(s: &str, default_country_code: Option<u16>) -> Option<String> {
+    let s = s.trim();
+    let s = s.strip_prefix("tel:").unwrap_or(s);
+    // everything from the first letter on, as in "x123" or "ext. 123", is an extension
+    let (number, extension) = match s.find(|ch: char| ch.is_alphabetic()) {
+        Some(index) => s.split_at(index),
+        None => (s, ""),
+    };
+    if number
+        .chars()
+        .any(|ch| !ch.is_ascii_digit() && !" +-.()/".contains(ch))
+    {
+        return None;
+    }
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    let extension: String = extension.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        return None;
+    }
+
+    let international = if number.trim_start().starts_with('+') {
+        Some(digits.clone())
+    } else if let Some(digits) = digits.strip_prefix("00") {
+        Some(digits.to_string())
+    } else {
+        default_country_code.map(|code| {
+            let national = digits.strip_prefix('0').unwrap_or(&digits);
+            format!("{}{}", code, national)
+        })
+    };
+    let normalized = match international {
+        Some(digits) if digits.is_empty() || digits.len() > E164_MAX_DIGITS => return None,
+        Some(digits) => format!("+{}", digits),
+        None => digits,
+    };
+    Some(if extension.is_empty() {
+        normalized
+    } else {
+        format!("{};ext={}", normalized, extension)
+    })
+}
+
+/// Normalizes an email address by lowercasing its domain and, optionally, dropping a
+/// `+tag` from its local part. Returns `None` for strings without an `@`.
+pub fn normalize_email(s: &str, strip_plus_tags: bool) -> Option<String> {
+    let (local, domain) = s.trim().rsplit_once('@')?;
+    let local = match local.split_once('+') {
+        Some((local, _)) if strip_plus_tags => local,
+        _ => local,
+    };
+    Some(format!("{}@{}", local, domain.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn phone_numbers_normalize_to_e164() {
+        let normalize = normalize_phone;
+        assert_eq!(
+            normalize("(555) 123-4567", Some(1)).as_deref(),
+            Some("+15551234567")
+        );
+        assert_eq!(
+            normalize("tel:+1 555.123.4567", None).as_deref(),
+            Some("+15551234567")
+        );
+        assert_eq!(
+            normalize("0044 20 7946 0958", None).as_deref(),
+            Some("+442079460958")
+        );
+        // the trunk 0 is dropped when the default country code is added
+        assert_eq!(
+            normalize("020 7946 0958 ext. 12", Some(44)).as_deref(),
+            Some("+442079460958;ext=12")
+        );
+        assert_eq!(
+            normalize("555-123-4567", None).as_deref(),
+            Some("5551234567")
+        );
+        assert_eq!(normalize("call me", None), None);
+        assert_eq!(normalize("555_123", None), None);
+        assert_eq!(normalize("+1234567890123456", None), None);
+    }
+
+    #[test]
+    fn emails_lowercase_the_domain_and_optionally_drop_plus_tags() {
+        assert_eq!(
+            normalize_email(" Jane.Doe+news@Example.COM ", false).as_deref(),
+            Some("Jane.Doe+news@example.com")
+        );
+        assert_eq!(
+            normalize_email("Jane.Doe+news@Example.COM", true).as_deref(),
+            Some("Jane.Doe@example.com")
+        );
+        // the local part is case-sensitive
+        assert_ne!(
+            normalize_email("Jane@example.com", true),
+            normalize_email("jane@example.com", true)
+        );
+        assert_eq!(normalize_email("jane.example.com", true), None);
+    }
+
+    #[test]
+    fn money_is_read_from_numbers_strings_and_minor_units() {
+        let currency = Some("currency".to_string());
+        let read = |value: &Value| {
+            read_money(value, "amount", "scale", &currency)
+                .map(|(amount, currency)| (amount, currency.map(str::to_string)))
+        };
+        let amount = |text: &str| Decimal::parse(text).unwrap();
+
+        assert_eq!(read(&json!(10.5)), Some((amount("10.50"), None)));
+        assert_eq!(read(&json!(" 10.50 ")), Some((amount("10.5"), None)));
+        assert_eq!(
+            read(&json!({"amount": -1050, "scale": 2, "currency": "EUR"})),
+            Some((amount("-10.5"), Some("EUR".to_string())))
+        );
+        assert_eq!(read(&json!("-0.00")), Some((amount("0"), None)));
+        // currency symbols and thousands separators aren't amounts, so the values are
+        // compared as written
+        assert_eq!(read(&json!("$10.50")), None);
+        assert_eq!(read(&json!("10.50 €")), None);
+        assert_eq!(read(&json!("1,050.00")), None);
+        assert_eq!(read(&json!({"amount": 1050, "scale": "2"})), None);
+
+        let money = Comparator::Money {
+            amount_field: "amount".to_string(),
+            scale_field: "scale".to_string(),
+            currency_field: currency.clone(),
+        };
+        assert_eq!(
+            money.equal(&json!("-10.50"), &json!({"amount": -1050, "scale": 2})),
+            Some(true)
+        );
+        assert_eq!(
+            money.equal(&json!(-10.5), &json!({"amount": 1050, "scale": 2})),
+            Some(false)
+        );
+        assert_eq!(
+            money.equal(
+                &json!({"amount": 1050, "scale": 2, "currency": "EUR"}),
+                &json!({"amount": 1050, "scale": 2, "currency": "USD"})
+            ),
+            Some(false)
+        );
+        assert_eq!(money.equal(&json!("$10.50"), &json!(10.5)), None);
+    }
+}
@@ -0,0 +1,1025 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::hash::{canonical_hash, fingerprint};
+#[cfg(feature = "rayon")]
+use crate::json_diff::array_indices;
+use crate::json_diff::{
+    child_path, get_all_items_with, get_value_by_path, get_value_type, is_parent_missing,
+    is_path_within, parse_path, path_from_parts, same_type, values_equal_at, without_keys,
+    CompareOptions, FlattenOptions, PathPart, TypeMismatch,
+};
+use crate::pattern::PathPattern;
+
+/// Options for comparing documents through the library.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Check values as well as structure
+    pub check_values: bool,
+    /// Only check types, ignoring value differences (with `check_values`)
+    pub type_only: bool,
+    /// Include parent paths in missing items
+    pub include_parents: bool,
+    pub flatten: FlattenOptions,
+    pub compare: CompareOptions,
+    /// Stops the comparison early when cancelled, returning the differences found so far
+    pub cancellation: Option<CancellationToken>,
+    /// Called as paths are checked, e.g. to drive a progress bar
+    pub progress: Option<ProgressCallback>,
+}
+
+/// How far a comparison has got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Paths checked so far
+    pub processed: usize,
+    /// Paths to check in total
+    pub total: usize,
+    /// First segment of the path checked last, e.g. `users` or `[3]`
+    pub path_prefix: String,
+}
+
+/// A progress callback shared by clones; comparisons on several threads call it from
+/// each of them.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(&Progress) + Send + Sync + 'static) -> ProgressCallback {
+        ProgressCallback(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Counts the paths of one comparison as they are checked, on any number of threads,
+/// and reports every `PROGRESS_INTERVAL`th path and the last one to the callback.
+pub struct ProgressReporter {
+    callback: Option<ProgressCallback>,
+    total: usize,
+    processed: AtomicUsize,
+}
+
+/// Paths checked between two progress reports.
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+impl ProgressReporter {
+    pub fn new(callback: Option<ProgressCallback>, total: usize) -> ProgressReporter {
+        ProgressReporter {
+            callback,
+            total,
+            processed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Counts one checked path.
+    pub fn advance(&self, path: &str) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+        let processed = self.processed.fetch_add(1, Ordering::Relaxed) + 1;
+        if processed.is_multiple_of(PROGRESS_INTERVAL) || processed == self.total {
+            (callback.0)(&Progress {
+                processed,
+                total: self.total,
+                path_prefix: top_level_key(path),
+            });
+        }
+    }
+
+    /// Reports the comparison as complete, also when paths were skipped without being
+    /// checked one by one.
+    pub fn finish(&self) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+        if self.processed.swap(self.total, Ordering::Relaxed) < self.total {
+            (callback.0)(&Progress {
+                processed: self.total,
+                total: self.total,
+                path_prefix: String::new(),
+            });
+        }
+    }
+}
+
+/// Cancels comparisons from another thread, e.g. when a user aborts a long diff. Clones
+/// share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueDifference {
+    pub path: String,
+    pub base_value: Value,
+    pub compare_value: Value,
+}
+
+/// The differences found between a base and a compared document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffReport {
+    /// Path of the compared subtrees within both documents, empty for whole documents;
+    /// the reported paths are relative to it
+    pub root: String,
+    pub total_paths_checked: usize,
+    pub missing_paths: Vec<String>,
+    pub different_values: Vec<ValueDifference>,
+    pub type_mismatches: Vec<TypeMismatch>,
+    /// The comparison was cancelled, so the report only holds the differences found
+    /// until then
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// The categories of differences a `DiffReport` holds.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Missing,
+    Different,
+    TypeMismatch,
+}
+
+/// One difference of a report, borrowed from it.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffEntry<'a> {
+    Missing { path: &'a str },
+    Different(&'a ValueDifference),
+    TypeMismatch(&'a TypeMismatch),
+}
+
+impl DiffEntry<'_> {
+    pub fn path(&self) -> &str {
+        match self {
+            DiffEntry::Missing { path } => path,
+            DiffEntry::Different(difference) => &difference.path,
+            DiffEntry::TypeMismatch(mismatch) => &mismatch.path,
+        }
+    }
+
+    pub fn kind(&self) -> DiffKind {
+        match self {
+            DiffEntry::Missing { .. } => DiffKind::Missing,
+            DiffEntry::Different(_) => DiffKind::Different,
+            DiffEntry::TypeMismatch(_) => DiffKind::TypeMismatch,
+        }
+    }
+
+    /// A stable identifier of the difference in `file`, e.g. to track it across runs;
+    /// `with_values` includes the compare value, so a value that changes again gets a
+    /// new one. The paths are taken as they are in the report, relative to its root.
+    /// The CLI gives its differences the same fingerprints.
+    pub fn fingerprint(&self, file: &str, with_values: bool) -> String {
+        let (kind, value) = match self {
+            DiffEntry::Missing { .. } => ("missing", None),
+            DiffEntry::Different(difference) => ("different", Some(&difference.compare_value)),
+            DiffEntry::TypeMismatch(mismatch) => ("type_mismatch", Some(&mismatch.compare_value)),
+        };
+        fingerprint(file, kind, self.path(), value.filter(|_| with_values))
+    }
+
+    /// The position of the entry in path order; a cursor continuing after it.
+    pub fn cursor(&self) -> DiffCursor {
+        DiffCursor {
+            path: self.path().to_string(),
+            kind: self.kind(),
+        }
+    }
+
+    fn sort_key(&self) -> (&str, DiffKind) {
+        (self.path(), self.kind())
+    }
+}
+
+/// A position in the entries of a report sorted by path, and by kind within a path.
+/// Unlike an offset it stays valid when differences before it are filtered out, so it
+/// can be handed to a client to fetch the next page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffCursor {
+    pub path: String,
+    pub kind: DiffKind,
+}
+
+/// A page of entries and the cursor of the next page, if there are more entries.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffPage<'a> {
+    pub entries: Vec<DiffEntry<'a>>,
+    pub next: Option<DiffCursor>,
+}
+
+impl DiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing_paths.is_empty()
+            && self.different_values.is_empty()
+            && self.type_mismatches.is_empty()
+    }
+
+    /// Number of differences of all kinds.
+    pub fn len(&self) -> usize {
+        self.missing_paths.len() + self.different_values.len() + self.type_mismatches.len()
+    }
+
+    /// Every difference, sorted by path and by kind within a path.
+    pub fn entries(&self) -> Vec<DiffEntry<'_>> {
+        sorted_prefix(self.unsorted_entries().collect(), usize::MAX)
+    }
+
+    /// Up to `limit` entries in path order, skipping the first `offset`.
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<DiffEntry<'_>> {
+        let entries = sorted_prefix(
+            self.unsorted_entries().collect(),
+            offset.saturating_add(limit),
+        );
+        entries.into_iter().skip(offset).collect()
+    }
+
+    /// Up to `limit` entries in path order following `cursor`, or from the start without
+    /// one. Only the returned entries are sorted, so walking a huge report page by page
+    /// doesn't sort all of it for every page.
+    pub fn page_after(&self, cursor: Option<&DiffCursor>, limit: usize) -> DiffPage<'_> {
+        let remaining: Vec<DiffEntry<'_>> = self
+            .unsorted_entries()
+            .filter(|entry| {
+                cursor.is_none_or(|cursor| entry.sort_key() > (cursor.path.as_str(), cursor.kind))
+            })
+            .collect();
+        let more = remaining.len() > limit;
+        let entries = sorted_prefix(remaining, limit);
+        let next = entries.last().filter(|_| more).map(DiffEntry::cursor);
+        DiffPage { entries, next }
+    }
+
+    fn unsorted_entries(&self) -> impl Iterator<Item = DiffEntry<'_>> {
+        self.missing_paths
+            .iter()
+            .map(|path| DiffEntry::Missing { path })
+            .chain(self.different_values.iter().map(DiffEntry::Different))
+            .chain(self.type_mismatches.iter().map(DiffEntry::TypeMismatch))
+    }
+
+    /// Keeps the differences at paths matched by the pattern or below them.
+    pub fn filter_paths(&self, pattern: &PathPattern) -> DiffReport {
+        self.retain(|_, path| pattern.matches_prefix_of(path))
+    }
+
+    /// Drops every difference of the given kinds.
+    pub fn without_kinds(&self, kinds: &[DiffKind]) -> DiffReport {
+        self.retain(|kind, _| !kinds.contains(&kind))
+    }
+
+    /// Combines the differences of both reports. Reports of different subtrees are
+    /// combined with their paths made relative to the document root.
+    pub fn merge(self, other: DiffReport) -> DiffReport {
+        let (mut merged, other) = if self.root == other.root {
+            (self, other)
+        } else {
+            (self.rebased(), other.rebased())
+        };
+        merged.total_paths_checked += other.total_paths_checked;
+        merged.cancelled |= other.cancelled;
+        merged.missing_paths.extend(other.missing_paths);
+        merged.different_values.extend(other.different_values);
+        merged.type_mismatches.extend(other.type_mismatches);
+        merged
+    }
+
+    /// Keeps the first `max` differences of each kind, in the order they were found.
+    pub fn limit(mut self, max: usize) -> DiffReport {
+        self.missing_paths.truncate(max);
+        self.different_values.truncate(max);
+        self.type_mismatches.truncate(max);
+        self
+    }
+
+    /// Counts the differences of each kind per prefix of `depth` path segments, as
+    /// [`group_by_prefix`] does.
+    pub fn group_by_prefix(&self, depth: usize) -> BTreeMap<DiffKind, Vec<PrefixGroup>> {
+        let mut groups = BTreeMap::new();
+        for kind in DiffKind::value_variants() {
+            let paths = self
+                .unsorted_entries()
+                .filter(|entry| entry.kind() == *kind)
+                .map(|entry| entry.path().to_string());
+            let kind_groups = group_by_prefix(paths, depth);
+            if !kind_groups.is_empty() {
+                groups.insert(*kind, kind_groups);
+            }
+        }
+        groups
+    }
+
+    /// Groups the differences by the first segment of their path, e.g. `users` or `[0]`.
+    /// The paths stay relative to `root`; `total_paths_checked` is not known per group
+    /// and left at 0.
+    pub fn split_by_top_level_key(&self) -> BTreeMap<String, DiffReport> {
+        let mut groups: BTreeMap<String, DiffReport> = BTreeMap::new();
+        for key in self.paths().map(top_level_key) {
+            groups.entry(key.clone()).or_insert_with(|| DiffReport {
+                total_paths_checked: 0,
+                ..self.retain(|_, path| top_level_key(path) == key)
+            });
+        }
+        groups
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &str> {
+        self.missing_paths
+            .iter()
+            .chain(self.different_values.iter().map(|d| &d.path))
+            .chain(self.type_mismatches.iter().map(|m| &m.path))
+            .map(String::as_str)
+    }
+
+    /// A copy with only the differences `keep` accepts.
+    fn retain(&self, keep: impl Fn(DiffKind, &str) -> bool) -> DiffReport {
+        DiffReport {
+            root: self.root.clone(),
+            total_paths_checked: self.total_paths_checked,
+            missing_paths: self
+                .missing_paths
+                .iter()
+                .filter(|path| keep(DiffKind::Missing, path))
+                .cloned()
+                .collect(),
+            different_values: self
+                .different_values
+                .iter()
+                .filter(|d| keep(DiffKind::Different, &d.path))
+                .cloned()
+                .collect(),
+            type_mismatches: self
+                .type_mismatches
+                .iter()
+                .filter(|m| keep(DiffKind::TypeMismatch, &m.path))
+                .cloned()
+                .collect(),
+            cancelled: self.cancelled,
+        }
+    }
+
+    /// The same report with `root` prepended to every path.
+    fn rebased(mut self) -> DiffReport {
+        let root = parse_path(&self.root);
+        let rebase = |path: &mut String| {
+            let mut parts = root.clone();
+            parts.extend(parse_path(path));
+            *path = path_from_parts(&parts);
+        };
+        self.missing_paths.iter_mut().for_each(rebase);
+        self.different_values
+            .iter_mut()
+            .for_each(|d| rebase(&mut d.path));
+        self.type_mismatches
+            .iter_mut()
+            .for_each(|m| rebase(&mut m.path));
+        self.root = String::new();
+        self
+    }
+}
+
+/// The paths at and below a prefix, counted by [`group_by_prefix`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrefixGroup {
+    /// The first path segments the paths share, empty for the root
+    pub prefix: String,
+    /// Whether some of the paths are below the prefix rather than at it
+    pub below: bool,
+    pub count: usize,
+}
+
+/// Counts dotted paths per prefix of `depth` path segments, e.g. 37 paths below
+/// `settings.notifications`. Prefixes are listed in the order their first paths were.
+pub fn group_by_prefix(paths: impl IntoIterator<Item = String>, depth: usize) -> Vec<PrefixGroup> {
+    let mut groups: Vec<PrefixGroup> = Vec::new();
+    for path in paths {
+        let parts = parse_path(&path);
+        let prefix = path_from_parts(&parts[..parts.len().min(depth)]);
+        let below = parts.len() > depth;
+        match groups.iter_mut().find(|group| group.prefix == prefix) {
+            Some(group) => {
+                group.below |= below;
+                group.count += 1;
+            }
+            None => groups.push(PrefixGroup {
+                prefix,
+                below,
+                count: 1,
+            }),
+        }
+    }
+    groups
+}
+
+/// The first `n` entries in path order, selected before sorting them.
+fn sorted_prefix(mut entries: Vec<DiffEntry<'_>>, n: usize) -> Vec<DiffEntry<'_>> {
+    if n < entries.len() {
+        entries.select_nth_unstable_by(n, |a, b| a.sort_key().cmp(&b.sort_key()));
+        entries.truncate(n);
+    }
+    entries.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+    entries
+}
+
+fn top_level_key(path: &str) -> String {
+    path_from_parts(
+        &parse_path(path)
+            .into_iter()
+            .take(1)
+            .collect::<Vec<PathPart>>(),
+    )
+}
+
+/// The items of `base` to check, with a scalar root as the value at the empty path.
+fn base_items(base: &Value, flatten: &FlattenOptions) -> Vec<(String, Value)> {
+    let mut items = get_all_items_with(base, String::new(), flatten);
+    if items.is_empty() && !base.is_object() && !base.is_array() {
+        items.push((String::new(), base.clone()));
+    }
+    items
+}
+
+/// Checks every path of `base` against `other`.
+pub fn compare(base: &Value, other: &Value, options: &DiffOptions) -> DiffReport {
+    let items = base_items(base, &options.flatten);
+    let progress = ProgressReporter::new(options.progress.clone(), items.len());
+    check_items(&items, other, options, &progress)
+}
+
+/// Checks `other` against several base documents at once, e.g. against every version
+/// of a contract it may follow, returning a report per base in the same order. Paths
+/// the bases share are looked up in `other` only once. Each report is the one `compare`
+/// returns for its base.
+pub fn compare_many(bases: &[Value], other: &Value, options: &DiffOptions) -> Vec<DiffReport> {
+    let items: Vec<Vec<(String, Value)>> = bases
+        .iter()
+        .map(|base| base_items(base, &options.flatten))
+        .collect();
+    let mut found: HashMap<&str, Option<&Value>> = HashMap::new();
+    for (path, _) in items.iter().flatten() {
+        found
+            .entry(path)
+            .or_insert_with(|| get_value_by_path(other, path));
+    }
+
+    let total = items.iter().map(Vec::len).sum();
+    let progress = ProgressReporter::new(options.progress.clone(), total);
+    items
+        .iter()
+        .map(|items| check_items_with(items, |path| found[path], options, &progress))
+        .collect()
+}
+
+/// A base document prepared once for many comparisons, e.g. by a server checking
+/// payloads against a contract. Its items are flattened and its hashes computed up front;
+/// clones share them through an `Arc`, so a prepared base can be handed to other threads
+/// cheaply.
+#[derive(Debug, Clone)]
+pub struct PreparedBase {
+    inner: Arc<Prepared>,
+}
+
+#[derive(Debug)]
+struct Prepared {
+    json: Value,
+    items: Vec<(String, Value)>,
+    options: DiffOptions,
+    hash: u64,
+    /// Paths and hashes of the top-level members or elements
+    top_level_hashes: Vec<(String, u64)>,
+}
+
+impl PreparedBase {
+    pub fn new(base: Value, options: DiffOptions) -> PreparedBase {
+        let items = get_all_items_with(&base, String::new(), &options.flatten);
+        let top_level: Vec<(String, &Value)> = match &base {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, child)| (child_path("", key), child))
+                .collect(),
+            Value::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .map(|(i, child)| (format!("[{}]", i), child))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let top_level_hashes = top_level
+            .into_iter()
+            .map(|(path, child)| (path, canonical_hash(child)))
+            .collect();
+
+        PreparedBase {
+            inner: Arc::new(Prepared {
+                hash: canonical_hash(&base),
+                top_level_hashes,
+                items,
+                options,
+                json: base,
+            }),
+        }
+    }
+
+    pub fn json(&self) -> &Value {
+        &self.inner.json
+    }
+
+    pub fn options(&self) -> &DiffOptions {
+        &self.inner.options
+    }
+
+    /// Compares `other` against the base with the options it was prepared with. Documents
+    /// and top-level subtrees whose hashes match the base's are skipped without visiting
+    /// their paths.
+    pub fn compare(&self, other: &Value) -> DiffReport {
+        let prepared = &*self.inner;
+        let options = &prepared.options;
+        if prepared.items.is_empty() {
+            return compare(&prepared.json, other, options);
+        }
+
+        // equal hashes can't be trusted when ignored keys are removed or NaN differs
+        // from itself
+        let use_hashes = options.flatten.ignore_keys.is_empty() && options.compare.nan_equals_nan;
+        let progress = ProgressReporter::new(options.progress.clone(), prepared.items.len());
+        if use_hashes && canonical_hash(other) == prepared.hash {
+            progress.finish();
+            return DiffReport {
+                total_paths_checked: prepared.items.len(),
+                ..DiffReport::default()
+            };
+        }
+        let identical: Vec<&str> = prepared
+            .top_level_hashes
+            .iter()
+            .filter(|(path, hash)| {
+                use_hashes
+                    && get_value_by_path(other, path)
+                        .is_some_and(|value| canonical_hash(value) == *hash)
+            })
+            .map(|(path, _)| path.as_str())
+            .collect();
+
+        let items = prepared
+            .items
+            .iter()
+            .filter(|(path, _)| !identical.iter().any(|root| is_path_within(path, root)));
+        let report = check_items(items, other, options, &progress);
+        if report.cancelled {
+            return report;
+        }
+        progress.finish();
+        DiffReport {
+            total_paths_checked: prepared.items.len(),
+            ..report
+        }
+    }
+}
+
+/// Like `compare`, but compares the top-level members (or elements) of `base` in
+/// parallel on the rayon thread pool. The report lists the differences in the same
+/// order as `compare` does.
+#[cfg(feature = "rayon")]
+pub fn compare_parallel(base: &Value, other: &Value, options: &DiffOptions) -> DiffReport {
+    use rayon::prelude::*;
+
+    let partitions: Vec<(String, &Value)> = match base {
+        Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !options.flatten.ignore_keys.contains(key))
+            .map(|(key, child)| (child_path("", key), child))
+            .collect(),
+        Value::Array(arr) => array_indices(arr.len(), &options.flatten)
+            .into_iter()
+            .map(|i| (format!("[{}]", i), &arr[i]))
+            .collect(),
+        _ => return compare(base, other, options),
+    };
+
+    // the partitions are flattened first so progress can be reported against the total
+    let partitions: Vec<Vec<(String, Value)>> = partitions
+        .into_par_iter()
+        .map(|(path, child)| {
+            let mut items = vec![(
+                path.clone(),
+                without_keys(child, &options.flatten.ignore_keys),
+            )];
+            if child.is_object() || child.is_array() {
+                items.extend(get_all_items_with(child, path, &options.flatten));
+            }
+            items
+        })
+        .collect();
+    let total = partitions.iter().map(Vec::len).sum();
+    let progress = ProgressReporter::new(options.progress.clone(), total);
+
+    partitions
+        .par_iter()
+        .map(|items| check_items(items, other, options, &progress))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .fold(DiffReport::default(), DiffReport::merge)
+}
+
+/// Checks the base items against `other`, cloning values only for the differences.
+fn check_items<'a>(
+    items: impl IntoIterator<Item = &'a (String, Value)>,
+    other: &Value,
+    options: &DiffOptions,
+    progress: &ProgressReporter,
+) -> DiffReport {
+    check_items_with(
+        items,
+        |path| get_value_by_path(other, path),
+        options,
+        progress,
+    )
+}
+
+/// Checks the base items against the values `lookup` finds for their paths.
+fn check_items_with<'a, 'b>(
+    items: impl IntoIterator<Item = &'a (String, Value)>,
+    lookup: impl Fn(&str) -> Option<&'b Value>,
+    options: &DiffOptions,
+    progress: &ProgressReporter,
+) -> DiffReport {
+    let mut report = DiffReport::default();
+    for (path, base_value) in items {
+        if let Some(cancellation) = &options.cancellation {
+            if cancellation.is_cancelled() {
+                report.cancelled = true;
+                break;
+            }
+        }
+        report.total_paths_checked += 1;
+        progress.advance(path);
+        let Some(compare_value) = lookup(path) else {
+            if !options.include_parents || !is_parent_missing(&report.missing_paths, path) {
+                report.missing_paths.push(path.clone());
+            }
+            continue;
+        };
+        if !options.check_values {
+            continue;
+        }
+        // ignored keys are left out of compared containers, as they are of base items
+        let compare_value = if options.flatten.ignore_keys.is_empty() {
+            Cow::Borrowed(compare_value)
+        } else {
+            Cow::Owned(without_keys(compare_value, &options.flatten.ignore_keys))
+        };
+
+        if options.type_only {
+            if !same_type(base_value, &compare_value) {
+                report.type_mismatches.push(TypeMismatch {
+                    path: path.clone(),
+                    base_type: get_value_type(base_value),
+                    compare_type: get_value_type(&compare_value),
+                    base_value: base_value.clone(),
+                    compare_value: compare_value.into_owned(),
+                });
+            }
+        } else if !values_equal_at(path, base_value, &compare_value, &options.compare) {
+            report.different_values.push(ValueDifference {
+                path: path.clone(),
+                base_value: base_value.clone(),
+                compare_value: compare_value.into_owned(),
+            });
+        }
+    }
+    report
+}
+
+/// Compares only the subtrees at `path` in both documents, without cloning them first.
+/// `path` is a JSONPath without wildcards such as `$.spec.containers[0]`, or a path in
+/// the dotted form used in reports. Per-path tolerances and the reported paths are
+/// relative to the subtree.
+pub fn compare_at(
+    base: &Value,
+    other: &Value,
+    path: &str,
+    options: &DiffOptions,
+) -> Result<DiffReport> {
+    let root = if path.trim_start().starts_with('$') {
+        let pattern = PathPattern::parse_jsonpath(path)?;
+        if pattern.has_wildcards() {
+            bail!(
+                "compare_at needs the path of a single subtree, not a pattern: {}",
+                path
+            );
+        }
+        match pattern.select(base).into_iter().next() {
+            Some((root, _)) => root,
+            None => bail!("{} not found in the base document", path),
+        }
+    } else {
+        path.to_string()
+    };
+
+    let Some(base_subtree) = get_value_by_path(base, &root) else {
+        bail!("{} not found in the base document", path);
+    };
+    let Some(other_subtree) = get_value_by_path(other, &root) else {
+        bail!("{} not found in the compared document", path);
+    };
+
+    Ok(DiffReport {
+        root,
+        ..compare(base_subtree, other_subtree, options)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{parse_document_with, InputFormat, ParseOptions};
+    use serde_json::json;
+
+    fn report(root: &str, missing: &[&str], different: &[&str], mismatched: &[&str]) -> DiffReport {
+        DiffReport {
+            root: root.to_string(),
+            total_paths_checked: 10,
+            missing_paths: missing.iter().map(|path| path.to_string()).collect(),
+            different_values: different
+                .iter()
+                .map(|path| ValueDifference {
+                    path: path.to_string(),
+                    base_value: json!(1),
+                    compare_value: json!(2),
+                })
+                .collect(),
+            type_mismatches: mismatched
+                .iter()
+                .map(|path| TypeMismatch {
+                    path: path.to_string(),
+                    base_type: "number".to_string(),
+                    compare_type: "string".to_string(),
+                    base_value: json!(1),
+                    compare_value: json!("1"),
+                })
+                .collect(),
+            cancelled: false,
+        }
+    }
+
+    fn paths(report: &DiffReport) -> Vec<&str> {
+        report.paths().collect()
+    }
+
+    #[test]
+    fn filter_paths_keeps_matches_and_paths_below_them() {
+        let report = report(
+            "",
+            &["users[0].name", "users", "settings.theme"],
+            &["users[1].age"],
+            &["usersCount"],
+        );
+        let filtered = report.filter_paths(&PathPattern::parse("users[*]"));
+        assert_eq!(paths(&filtered), ["users[0].name", "users[1].age"]);
+        assert_eq!(filtered.total_paths_checked, 10);
+    }
+
+    #[test]
+    fn without_kinds_drops_every_difference_of_the_kinds() {
+        let report = report("", &["a"], &["b", "c"], &["d"]);
+        let different = report.without_kinds(&[DiffKind::Missing, DiffKind::TypeMismatch]);
+        assert_eq!(paths(&different), ["b", "c"]);
+        assert_eq!(different.len(), 2);
+        assert!(report.without_kinds(DiffKind::value_variants()).is_empty());
+    }
+
+    #[test]
+    fn merge_combines_reports_of_the_same_root() {
+        let mut cancelled = report("spec", &["b"], &[], &[]);
+        cancelled.cancelled = true;
+        let merged = report("spec", &["a"], &["c"], &[]).merge(cancelled);
+        assert_eq!(merged.root, "spec");
+        assert_eq!(paths(&merged), ["a", "b", "c"]);
+        assert_eq!(merged.total_paths_checked, 20);
+        assert!(merged.cancelled);
+    }
+
+    #[test]
+    fn merge_rebases_reports_of_different_roots() {
+        let merged = report("spec.containers[0]", &["image"], &[], &[]).merge(report(
+            "status",
+            &[],
+            &["phase"],
+            &["[0]"],
+        ));
+        assert_eq!(merged.root, "");
+        assert_eq!(
+            paths(&merged),
+            ["spec.containers[0].image", "status.phase", "status[0]"]
+        );
+    }
+
+    #[test]
+    fn split_by_top_level_key_groups_paths_by_first_segment() {
+        let report = report(
+            "",
+            &["users[0].name", "settings.theme"],
+            &["users[1].age"],
+            &["[0]", "users"],
+        );
+        let groups = report.split_by_top_level_key();
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            ["[0]", "settings", "users"]
+        );
+        assert_eq!(
+            paths(&groups["users"]),
+            ["users[0].name", "users[1].age", "users"]
+        );
+        assert_eq!(groups["users"].len(), 3);
+        assert_eq!(groups["users"].total_paths_checked, 0);
+    }
+
+    #[test]
+    fn split_by_top_level_key_counts_what_filters_left() {
+        let report = report(
+            "",
+            &["users[0].name", "settings.theme"],
+            &["users[1].age", "settings.locale"],
+            &["users[2]"],
+        );
+        let groups = report
+            .filter_paths(&PathPattern::parse("users[*]"))
+            .without_kinds(&[DiffKind::Missing])
+            .split_by_top_level_key();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(paths(&groups["users"]), ["users[1].age", "users[2]"]);
+        assert_eq!(groups["users"].len(), 2);
+    }
+
+    #[test]
+    fn limit_keeps_the_first_differences_of_each_kind() {
+        let limited = report("", &["a", "b", "c"], &["d"], &["e", "f"]).limit(2);
+        assert_eq!(paths(&limited), ["a", "b", "d", "e", "f"]);
+    }
+
+    #[test]
+    fn group_by_prefix_counts_paths_at_and_below_prefixes() {
+        let paths = ["settings.a.x", "id", "settings.b", "settings.a[0]"];
+        let groups = group_by_prefix(paths.map(String::from), 2);
+        let group = |prefix: &str, below, count| PrefixGroup {
+            prefix: prefix.to_string(),
+            below,
+            count,
+        };
+        assert_eq!(
+            groups,
+            [
+                group("settings.a", true, 2),
+                group("id", false, 1),
+                group("settings.b", false, 1),
+            ]
+        );
+        assert_eq!(group_by_prefix(["a".to_string()], 0), [group("", true, 1)]);
+    }
+
+    #[test]
+    fn report_group_by_prefix_groups_each_kind() {
+        let groups = report("", &["a.x", "a.y"], &[], &["b.z"]).group_by_prefix(1);
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            [&DiffKind::Missing, &DiffKind::TypeMismatch]
+        );
+        assert_eq!(groups[&DiffKind::Missing][0].count, 2);
+        assert_eq!(groups[&DiffKind::TypeMismatch][0].prefix, "b");
+    }
+
+    #[test]
+    fn sample_does_not_alias_with_periodic_changes() {
+        let base: Vec<usize> = (0..100_000).collect();
+        let other: Vec<usize> = (0..100_000)
+            .map(|i| if i % 10 == 0 { i + 1 } else { i })
+            .collect();
+        let options = DiffOptions {
+            check_values: true,
+            flatten: FlattenOptions {
+                sample: Some(1000),
+                ..FlattenOptions::default()
+            },
+            ..DiffOptions::default()
+        };
+        let report = compare(&json!(base), &json!(other), &options);
+        // a tenth of the sampled elements changed, as in the whole array
+        let different = report.different_values.len();
+        assert!((50..150).contains(&different), "{} differ", different);
+        let paths = |report: &DiffReport| -> Vec<String> {
+            report
+                .different_values
+                .iter()
+                .map(|diff| diff.path.clone())
+                .collect()
+        };
+        assert_eq!(
+            paths(&compare(&json!(base), &json!(other), &options)),
+            paths(&report)
+        );
+    }
+
+    #[test]
+    fn nan_unequal_leaves_strings_spelled_nan_alone() {
+        let parse = |text: &str| {
+            let options = ParseOptions {
+                non_finite: true,
+                mark_nan: true,
+                ..Default::default()
+            };
+            parse_document_with(text.as_bytes(), InputFormat::Json, &options).unwrap()
+        };
+        let base = parse(r#"{"status": "NaN", "reading": NaN, "label": NaN}"#);
+        let other = parse(r#"{"status": "NaN", "reading": NaN, "label": "NaN"}"#);
+        let options = DiffOptions {
+            check_values: true,
+            compare: CompareOptions {
+                nan_equals_nan: false,
+                ..CompareOptions::default()
+            },
+            ..DiffOptions::default()
+        };
+        let report = compare(&base, &other, &options);
+        let paths: Vec<&str> = report
+            .different_values
+            .iter()
+            .map(|diff| diff.path.as_str())
+            .collect();
+        assert_eq!(paths, ["label", "reading"]);
+    }
+
+    #[test]
+    fn match_rule_names_the_relaxation_that_matched() {
+        use crate::comparators::Comparator;
+        use crate::json_diff::{match_rule_at, MatchRule, PathComparator};
+
+        let options = CompareOptions {
+            absolute_tolerance: 0.1,
+            coerce: true,
+            path_comparators: vec![PathComparator {
+                pattern: PathPattern::parse("contact.email"),
+                comparator: Comparator::Email {
+                    strip_plus_tags: false,
+                },
+            }],
+            ..CompareOptions::default()
+        };
+        let rule = |path: &str, a: Value, b: Value| match_rule_at(path, &a, &b, &options);
+
+        assert_eq!(rule("count", json!(3), json!(3)), Some(MatchRule::Exact));
+        assert_eq!(
+            rule("ratio", json!(1.0), json!(1.05)),
+            Some(MatchRule::Tolerance)
+        );
+        assert_eq!(rule("ratio", json!(1.0), json!(1.5)), None);
+        assert_eq!(
+            rule("count", json!("3"), json!(3)),
+            Some(MatchRule::Coercion)
+        );
+        assert_eq!(
+            rule(
+                "contact.email",
+                json!("jo@Example.com"),
+                json!("jo@example.com")
+            ),
+            Some(MatchRule::Comparator("email"))
+        );
+        // a container reports what its members matched by
+        assert_eq!(
+            rule(
+                "point",
+                json!({"x": 1, "y": 2.0}),
+                json!({"x": 1, "y": 2.05})
+            ),
+            Some(MatchRule::Tolerance)
+        );
+    }
+}
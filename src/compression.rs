@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression wrapped around an input or export file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detects the compression from a `.gz` or `.zst` extension.
+    pub fn from_path(path: &Path) -> Option<Compression> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Detects the compression from the leading magic bytes of the content.
+    pub fn from_magic(content: &[u8]) -> Option<Compression> {
+        if content.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if content.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// The path without its compression extension, so `data.yaml.gz` is detected as YAML.
+pub fn strip_compression_extension(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Reads `reader` to its end, decompressing gzip or zstd content detected by magic
+/// bytes; anything else is read unchanged. With a `limit`, reading stops after `limit + 1`
+/// decompressed bytes, so an input over the limit is never inflated in full.
+pub fn read_decompressed(mut reader: impl Read, limit: Option<u64>) -> Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(ZSTD_MAGIC.len());
+    (&mut reader)
+        .take(ZSTD_MAGIC.len() as u64)
+        .read_to_end(&mut head)?;
+    let compression = Compression::from_magic(&head);
+    let reader = std::io::Cursor::new(head).chain(reader);
+    let limit = limit.map_or(u64::MAX, |max| max.saturating_add(1));
+    let mut content = Vec::new();
+    match compression {
+        Some(Compression::Gzip) => {
+            flate2::read::MultiGzDecoder::new(reader)
+                .take(limit)
+                .read_to_end(&mut content)
+                .context("Failed to decompress gzip data")?;
+        }
+        Some(Compression::Zstd) => {
+            zstd::stream::read::Decoder::new(reader)?
+                .take(limit)
+                .read_to_end(&mut content)
+                .context("Failed to decompress zstd data")?;
+        }
+        None => {
+            reader.take(limit).read_to_end(&mut content)?;
+        }
+    }
+    Ok(content)
+}
+
+/// Compresses content for writing to `path` when its extension asks for it.
+pub fn compress_for_path(path: &Path, content: Vec<u8>) -> Result<Vec<u8>> {
+    match Compression::from_path(path) {
+        Some(Compression::Gzip) => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&content)?;
+            Ok(encoder.finish()?)
+        }
+        Some(Compression::Zstd) => Ok(zstd::stream::encode_all(&content[..], 0)?),
+        None => Ok(content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_applies_to_the_decompressed_content() {
+        let content = vec![b' '; 10_000];
+        let compressed = compress_for_path(Path::new("input.json.gz"), content.clone()).unwrap();
+        assert!(compressed.len() < 100);
+
+        let read = read_decompressed(&compressed[..], Some(1_000)).unwrap();
+        assert_eq!(read.len(), 1_001);
+        let read = read_decompressed(&compressed[..], Some(10_000)).unwrap();
+        assert_eq!(read, content);
+        let read = read_decompressed(&content[..], None).unwrap();
+        assert_eq!(read, content);
+    }
+}
@@ -0,0 +1,114 @@
+//! Project config files, giving defaults for the command line options of every run in a
+//! directory, so a team doesn't repeat the same flags in each invocation.
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command, CommandFactory, FromArgMatches};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{load_json, Args, LoadOptions};
+
+/// Config files looked for in the working directory, in order; the first one found is
+/// used.
+pub const CONFIG_FILES: [&str; 2] = ["jsondiff.toml", ".jsondiffrc"];
+
+/// The config file in the working directory, if any.
+pub fn find() -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// Reads a config file into command line arguments. Its keys are long option names, such
+/// as `array-key` or `array_key`; `true` turns a flag on and lists repeat an option.
+/// `jsondiff.toml` is TOML and `.jsondiffrc` JSON.
+pub fn load(path: &Path) -> Result<Vec<OsString>> {
+    let invalid = || format!("Invalid config file: {:?}", path);
+    let config = load_json(&path.to_path_buf(), &LoadOptions::default()).with_context(invalid)?;
+    let Value::Object(options) = config else {
+        anyhow::bail!("{}: expected a table of options", invalid());
+    };
+
+    let command = Args::command();
+    let mut args = Vec::new();
+    for (key, value) in options {
+        let name = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(name.as_str()))
+            .filter(|arg| !matches!(arg.get_id().as_str(), "config" | "no_config"))
+            .with_context(|| format!("{}: unknown option {:?}", invalid(), key))?;
+        let takes_value = arg.get_action().takes_values();
+        let values = match value {
+            Value::Array(values) if takes_value => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::Bool(true) if !takes_value => {
+                    args.push(OsString::from(format!("--{}", name)));
+                    continue;
+                }
+                Value::Bool(false) if !takes_value => continue,
+                Value::String(value) if takes_value => value,
+                Value::Number(value) if takes_value => value.to_string(),
+                Value::Bool(value) if takes_value => value.to_string(),
+                _ => anyhow::bail!(
+                    "{}: {:?} expects {}",
+                    invalid(),
+                    key,
+                    if takes_value {
+                        "a string, number or list of them"
+                    } else {
+                        "true or false"
+                    }
+                ),
+            };
+            args.push(OsString::from(format!("--{}={}", name, value)));
+        }
+    }
+    Ok(args)
+}
+
+/// The command line of `Args` with a hidden `--no-<flag>` for each flag, which turns the
+/// flag off again when given after it, e.g. on the command line after the options of a
+/// config file that turns it on.
+fn command() -> Command {
+    let mut command = Args::command();
+    let flags: Vec<(String, String)> = command
+        .get_arguments()
+        .filter(|arg| matches!(arg.get_action(), ArgAction::SetTrue))
+        .filter_map(|arg| Some((arg.get_id().to_string(), arg.get_long()?.to_string())))
+        .filter(|(_, long)| !long.starts_with("no-"))
+        .collect();
+    for (id, long) in flags {
+        let negation = format!("no_{}", id);
+        command = command
+            .mut_arg(&id, |arg| arg.overrides_with(negation.clone()))
+            .arg(
+                Arg::new(negation)
+                    .long(format!("no-{}", long))
+                    .action(ArgAction::SetTrue)
+                    .overrides_with(id)
+                    .hide(true),
+            );
+    }
+    command
+}
+
+/// Parses command line arguments, accepting the `--no-<flag>` negations.
+pub fn try_parse_from<I, T>(argv: I) -> Result<Args, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    Args::from_arg_matches_mut(&mut command().try_get_matches_from(argv)?)
+}
+
+/// Parses the process's arguments like `Args::parse`, accepting the `--no-<flag>`
+/// negations.
+pub fn parse() -> Args {
+    try_parse_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+}
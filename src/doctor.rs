@@ -0,0 +1,219 @@
+//! The `doctor` subcommand, which checks a project's config file, rules file, baseline
+//! and path patterns without comparing anything, since a misconfigured pattern only
+//! shows as a run that passes for the wrong reason.
+
+use anyhow::Result;
+use colored::*;
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::ExitCode;
+
+use crate::baseline::Baseline;
+use crate::preview::{self, is_ignored, Pattern};
+use crate::{
+    config, git_object, input_url, is_stdin, load_json, load_rules, DoctorArgs, EXIT_DIFFERENCES,
+};
+
+/// The outcome of each check, printed as it's made.
+#[derive(Default)]
+struct Checkup {
+    problems: usize,
+}
+
+impl Checkup {
+    fn section(&self, title: &str) {
+        println!("\n{}", title.bold());
+    }
+
+    fn ok(&self, message: impl std::fmt::Display) {
+        println!("  {} {}", "✓".green(), message);
+    }
+
+    fn skipped(&self, message: impl std::fmt::Display) {
+        println!("  {} {}", "–".dimmed(), message.to_string().dimmed());
+    }
+
+    fn problem(&mut self, message: impl std::fmt::Display) {
+        self.problems += 1;
+        println!("  {} {}", "✗".red(), message);
+    }
+}
+
+pub fn run(doctor: &DoctorArgs) -> Result<ExitCode> {
+    let mut checkup = Checkup::default();
+
+    // the options of the config file, checked the way a run would read them
+    checkup.section("Config file");
+    let config = doctor.config.clone().or_else(config::find);
+    let mut options: Vec<OsString> = Vec::new();
+    match &config {
+        None => checkup.skipped(format!(
+            "No config file ({})",
+            config::CONFIG_FILES.join(" or ")
+        )),
+        Some(path) => match config::load(path) {
+            Ok(loaded) => options = loaded,
+            Err(e) => checkup.problem(format!("{:#}", e)),
+        },
+    }
+    let mut argv = vec![OsString::from("json_diff_checker")];
+    argv.extend(options.iter().cloned());
+    argv.push(OsString::from("base.json"));
+    let mut args = match config::try_parse_from(argv) {
+        Ok(args) => args,
+        Err(e) => {
+            // the error without clap's usage and help hints
+            let message = e.to_string();
+            let message: Vec<&str> = message
+                .lines()
+                .map(str::trim)
+                .take_while(|line| !line.starts_with("Usage:") && !line.starts_with("For more"))
+                .filter(|line| !line.is_empty())
+                .collect();
+            let message = message.join(" ");
+            checkup.problem(format!(
+                "Invalid config file: {:?}: {}",
+                config.as_ref().expect("only config options can be invalid"),
+                message.strip_prefix("error: ").unwrap_or(&message)
+            ));
+            config::try_parse_from(["json_diff_checker", "base.json"])?
+        }
+    };
+    if let Err(e) = args.apply_mode() {
+        checkup.problem(format!("{:#}", e));
+    }
+    if let (Some(path), 0) = (&config, checkup.problems) {
+        checkup.ok(format!("{:?}: {} option(s)", path, options.len()));
+    }
+
+    // the rules file, its unknown keys and the rules that can't all take effect
+    checkup.section("Rules file");
+    let rules_path = doctor.rules.as_ref().or(args.rules.as_ref());
+    let rules = match rules_path {
+        None => {
+            checkup.skipped("No rules file (--rules)");
+            None
+        }
+        Some(path) => match load_rules(path) {
+            Ok(rules) => {
+                checkup.ok(format!(
+                    "{:?}: {} path(s)",
+                    path,
+                    rules.ignore.len() + rules.paths().len()
+                ));
+                for conflict in rules.conflicts() {
+                    checkup.problem(conflict);
+                }
+                Some(rules)
+            }
+            Err(e) => {
+                checkup.problem(format!("{:#}", e));
+                None
+            }
+        },
+    };
+
+    // the baseline and the compare files it has accepted differences for
+    checkup.section("Baseline");
+    match doctor.baseline.as_ref().or(args.baseline.as_ref()) {
+        None => checkup.skipped("No baseline (--baseline)"),
+        Some(path) => match Baseline::load(path) {
+            Ok(baseline) => {
+                let mut files = 0;
+                let mut accepted = 0;
+                for (file, count) in baseline.files() {
+                    files += 1;
+                    accepted += count;
+                    let file_path = Path::new(file);
+                    if !file_path.exists()
+                        && !is_stdin(file_path)
+                        && input_url(file_path).is_none()
+                        && git_object(file_path).is_none()
+                    {
+                        checkup.problem(format!(
+                            "{:?} accepts {} difference(s) for {:?}, which doesn't exist; \
+                             re-record it with --update-baseline",
+                            path, count, file
+                        ));
+                    }
+                }
+                checkup.ok(format!(
+                    "{:?}: {} accepted difference(s) in {} file(s)",
+                    path, accepted, files
+                ));
+            }
+            Err(e) => checkup.problem(format!("{:#}", e)),
+        },
+    }
+
+    // the path patterns of the options and rules, matched against a sample input
+    checkup.section("Path patterns");
+    let patterns = match preview::patterns(&args, rules.as_ref()) {
+        Ok(patterns) => patterns,
+        Err(e) => {
+            checkup.problem(format!("{:#}", e));
+            Vec::new()
+        }
+    };
+
+    let sample = match &doctor.sample {
+        _ if patterns.is_empty() => {
+            checkup.skipped("No path patterns");
+            None
+        }
+        None => {
+            checkup.skipped(format!(
+                "{} path pattern(s); pass a sample input to check that they match",
+                patterns.len()
+            ));
+            None
+        }
+        Some(path) => match load_json(path, &args.load_options(path)) {
+            Ok(mut sample) => {
+                args.normalize(&mut sample);
+                Some(sample)
+            }
+            Err(e) => {
+                checkup.problem(format!("{:#}", e));
+                None
+            }
+        },
+    };
+    if let (Some(sample), Some(sample_path)) = (&sample, &doctor.sample) {
+        check_patterns(&mut checkup, &patterns, sample, sample_path);
+    }
+
+    println!();
+    if checkup.problems == 0 {
+        println!("{}", "✓ No problems found".green().bold());
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!(
+            "{}",
+            format!("✗ {} problem(s) found", checkup.problems)
+                .red()
+                .bold()
+        );
+        Ok(ExitCode::from(EXIT_DIFFERENCES))
+    }
+}
+
+/// Reports the patterns that match nothing in the sample, and those that only match
+/// paths another pattern ignores, so they never apply.
+fn check_patterns(checkup: &mut Checkup, patterns: &[Pattern], sample: &Value, sample_path: &Path) {
+    for pattern in patterns {
+        let label = pattern.label();
+        let matched = pattern.pattern.select(sample);
+        if matched.is_empty() {
+            checkup.problem(format!("{}: matches nothing in {:?}", label, sample_path));
+        } else if !pattern.ignores && matched.iter().all(|(path, _)| is_ignored(patterns, path)) {
+            checkup.problem(format!(
+                "{}: only matches ignored paths, so it never applies",
+                label
+            ));
+        } else {
+            checkup.ok(format!("{}: {} path(s)", label, matched.len()));
+        }
+    }
+}
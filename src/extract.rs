@@ -0,0 +1,206 @@
+use crate::input::{parse_document_with, InputFormat, ParseOptions};
+use crate::json_diff::get_value_by_path;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::Value;
+
+/// Where the JSON to compare is embedded in a file of another type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Extractor {
+    /// Fenced code blocks tagged `json`, `jsonc` or `json5` in Markdown
+    Markdown,
+    /// `<script type="application/json">` and `application/ld+json` elements in HTML,
+    /// or only the one with the given id
+    Html { id: Option<String> },
+    /// The value at a path in the YAML front matter of a Markdown file; a string value is
+    /// read as JSON text
+    FrontMatter { key: String },
+}
+
+impl Extractor {
+    /// Parses `markdown`, `html`, `html#<id>` or `front-matter:<key>`.
+    pub fn parse(spec: &str) -> Result<Extractor> {
+        match spec.split_once(['#', ':']) {
+            None if spec == "markdown" => Ok(Extractor::Markdown),
+            None if spec == "html" => Ok(Extractor::Html { id: None }),
+            Some(("html", id)) if spec.starts_with("html#") && !id.is_empty() => {
+                Ok(Extractor::Html {
+                    id: Some(id.to_string()),
+                })
+            }
+            Some(("front-matter", key)) if !key.is_empty() => Ok(Extractor::FrontMatter {
+                key: key.to_string(),
+            }),
+            _ => bail!(
+                "Invalid extractor {:?}, expected markdown, html, html#<id> or front-matter:<key>",
+                spec
+            ),
+        }
+    }
+}
+
+/// Extracts the embedded JSON from `content`. When a file embeds several values, such
+/// as several code blocks, they're compared as an array in document order.
+pub fn extract(content: &[u8], extractor: &Extractor, options: &ParseOptions) -> Result<Value> {
+    let text = String::from_utf8_lossy(content);
+    match extractor {
+        Extractor::Markdown => {
+            let blocks = fenced_blocks(&text);
+            if blocks.is_empty() {
+                bail!("No ```json code blocks found");
+            }
+            let values = blocks
+                .into_iter()
+                .map(|(line, format, block)| {
+                    parse_document_with(block.as_bytes(), format, options)
+                        .with_context(|| format!("Invalid JSON in code block at line {}", line))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(single_or_array(values))
+        }
+        Extractor::Html { id } => {
+            let scripts: Vec<_> = json_scripts(&text)
+                .into_iter()
+                .filter(|(script_id, _)| id.is_none() || script_id == id)
+                .collect();
+            match (id, scripts.is_empty()) {
+                (Some(id), true) => bail!("No JSON <script> element with id {:?} found", id),
+                (None, true) => bail!("No <script type=\"application/json\"> elements found"),
+                _ => {}
+            }
+            let values = scripts
+                .into_iter()
+                .map(|(script_id, body)| {
+                    parse_document_with(body.as_bytes(), InputFormat::Json, options).with_context(
+                        || match script_id {
+                            Some(script_id) => {
+                                format!("Invalid JSON in <script id={:?}>", script_id)
+                            }
+                            None => "Invalid JSON in <script> element".to_string(),
+                        },
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(single_or_array(values))
+        }
+        Extractor::FrontMatter { key } => {
+            let front_matter = front_matter(&text).context("No YAML front matter found")?;
+            let yaml = parse_document_with(front_matter.as_bytes(), InputFormat::Yaml, options)
+                .context("Invalid YAML front matter")?;
+            match get_value_by_path(&yaml, key) {
+                Some(Value::String(json)) => {
+                    parse_document_with(json.as_bytes(), InputFormat::Json, options)
+                        .with_context(|| format!("Invalid JSON in front matter key {:?}", key))
+                }
+                Some(value) => Ok(value.clone()),
+                None => bail!("Front matter has no key {:?}", key),
+            }
+        }
+    }
+}
+
+fn single_or_array(mut values: Vec<Value>) -> Value {
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
+
+/// The JSON code blocks of a Markdown document with the line they start at and the
+/// format their info string names.
+fn fenced_blocks(text: &str) -> Vec<(usize, InputFormat, String)> {
+    let mut blocks = Vec::new();
+    // the open fence, the line it's on and the format of a JSON block
+    let mut open: Option<(&str, usize, Option<InputFormat>, String)> = None;
+    for (number, line) in text.lines().enumerate() {
+        // fences may be indented by up to three spaces
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let trimmed = &line[indent.min(3)..];
+        let fence_len = |ch: char| trimmed.len() - trimmed.trim_start_matches(ch).len();
+        match &mut open {
+            None => {
+                for ch in ['`', '~'] {
+                    let len = fence_len(ch);
+                    if len >= 3 {
+                        let info = trimmed[len..].split_whitespace().next().unwrap_or("");
+                        let format = match info.to_ascii_lowercase().as_str() {
+                            "json" => Some(InputFormat::Json),
+                            "jsonc" | "json5" => Some(InputFormat::Json5),
+                            _ => None,
+                        };
+                        open = Some((&trimmed[..len], number + 1, format, String::new()));
+                    }
+                }
+            }
+            Some((fence, start, format, block)) => {
+                let ch = fence.chars().next().unwrap();
+                if fence_len(ch) >= fence.len() && trimmed.trim_start_matches(ch).trim().is_empty()
+                {
+                    if let Some(format) = format {
+                        blocks.push((*start, *format, std::mem::take(block)));
+                    }
+                    open = None;
+                } else if format.is_some() {
+                    block.push_str(line);
+                    block.push('\n');
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// The id and content of the `<script>` elements of an HTML document whose type is
+/// `application/json` or `application/ld+json`.
+fn json_scripts(text: &str) -> Vec<(Option<String>, &str)> {
+    let script =
+        Regex::new(r"(?is)<script\b([^>]*)>(.*?)</script\s*>").expect("valid script regex");
+    let attribute =
+        Regex::new(r#"(?i)([a-z_:][-a-z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#)
+            .expect("valid attribute regex");
+
+    script
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let mut script_type = None;
+            let mut id = None;
+            for attr in attribute.captures_iter(&captures[1]) {
+                let value = attr
+                    .get(2)
+                    .or_else(|| attr.get(3))
+                    .or_else(|| attr.get(4))
+                    .map_or("", |value| value.as_str());
+                match attr[1].to_ascii_lowercase().as_str() {
+                    "type" => script_type = Some(value.trim().to_ascii_lowercase()),
+                    "id" => id = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            matches!(
+                script_type.as_deref(),
+                Some("application/json" | "application/ld+json")
+            )
+            .then(|| (id, captures.get(2).map_or("", |body| body.as_str())))
+        })
+        .collect()
+}
+
+/// The YAML between a `---` line at the very start of the document and the next `---`
+/// or `...` line.
+fn front_matter(text: &str) -> Option<&str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut lines = text.split_inclusive('\n');
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+    let start = text.find('\n')? + 1;
+    let mut end = start;
+    for line in lines {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some(&text[start..end]);
+        }
+        end += line.len();
+    }
+    None
+}
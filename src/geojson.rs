@@ -0,0 +1,172 @@
+use serde_json::{Map, Number, Value};
+
+/// Options for normalizing GeoJSON before it is compared.
+#[derive(Debug, Clone)]
+pub struct GeoJsonOptions {
+    /// Decimal places coordinates are rounded to
+    pub precision: u32,
+}
+
+impl Default for GeoJsonOptions {
+    fn default() -> Self {
+        // 7 decimal places of a degree are about a centimeter
+        GeoJsonOptions { precision: 7 }
+    }
+}
+
+/// Rewrites GeoJSON into a canonical form so that only meaningful changes remain:
+/// coordinates and bounding boxes are rounded, polygon rings follow the right-hand rule
+/// and start at their smallest vertex, and the features of a collection become an
+/// object keyed by their `id` (or `properties.id`), so they are paired by identity
+/// instead of position.
+pub fn normalize_geojson(value: &mut Value, options: &GeoJsonOptions) {
+    match value {
+        Value::Object(map) => {
+            for child in map.values_mut() {
+                normalize_geojson(child, options);
+            }
+
+            if let Some(bbox) = map.get_mut("bbox") {
+                round_coordinates(bbox, options.precision);
+            }
+            let geometry_type = map.get("type").and_then(Value::as_str).map(str::to_string);
+            match (geometry_type.as_deref(), map.get_mut("coordinates")) {
+                (Some("Polygon"), Some(Value::Array(rings))) => {
+                    normalize_polygon(rings, options.precision);
+                }
+                (Some("MultiPolygon"), Some(Value::Array(polygons))) => {
+                    for polygon in polygons {
+                        if let Value::Array(rings) = polygon {
+                            normalize_polygon(rings, options.precision);
+                        }
+                    }
+                }
+                (Some(_), Some(coordinates)) => round_coordinates(coordinates, options.precision),
+                _ => {}
+            }
+            if geometry_type.as_deref() == Some("FeatureCollection") {
+                if let Some(features) = map.get_mut("features") {
+                    key_features(features);
+                }
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                normalize_geojson(child, options);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn round_coordinates(value: &mut Value, precision: u32) {
+    match value {
+        Value::Number(n) => {
+            if let Some(f) = n.as_f64().filter(|_| !n.is_i64() && !n.is_u64()) {
+                let scale = 10f64.powi(precision as i32);
+                if let Some(rounded) = Number::from_f64((f * scale).round() / scale) {
+                    *n = rounded;
+                }
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                round_coordinates(child, precision);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The first ring of a polygon is its exterior, which RFC 7946 wants counterclockwise;
+/// the holes that follow are clockwise.
+fn normalize_polygon(rings: &mut [Value], precision: u32) {
+    for (i, ring) in rings.iter_mut().enumerate() {
+        round_coordinates(ring, precision);
+        if let Value::Array(positions) = ring {
+            normalize_ring(positions, i == 0);
+        }
+    }
+}
+
+fn normalize_ring(positions: &mut Vec<Value>, counterclockwise: bool) {
+    // the closing position repeats the first one, so work on the open ring
+    if positions.len() > 1 && positions.first() == positions.last() {
+        positions.pop();
+    }
+    if positions.is_empty() {
+        return;
+    }
+
+    if (signed_area(positions) > 0.0) != counterclockwise {
+        positions.reverse();
+    }
+    let start = (0..positions.len())
+        .min_by(|&a, &b| compare_positions(&positions[a], &positions[b]))
+        .unwrap_or(0);
+    positions.rotate_left(start);
+    positions.push(positions[0].clone());
+}
+
+/// Shoelace formula: positive for counterclockwise rings.
+fn signed_area(positions: &[Value]) -> f64 {
+    let point = |value: &Value| {
+        let x = value.get(0).and_then(Value::as_f64).unwrap_or(0.0);
+        let y = value.get(1).and_then(Value::as_f64).unwrap_or(0.0);
+        (x, y)
+    };
+
+    positions
+        .iter()
+        .zip(positions.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            let ((x1, y1), (x2, y2)) = (point(a), point(b));
+            x1 * y2 - x2 * y1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn compare_positions(a: &Value, b: &Value) -> std::cmp::Ordering {
+    let coordinates = |value: &Value| -> Vec<f64> {
+        value
+            .as_array()
+            .map(|arr| arr.iter().filter_map(Value::as_f64).collect())
+            .unwrap_or_default()
+    };
+    coordinates(a)
+        .partial_cmp(&coordinates(b))
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Turns a feature array into an object keyed by feature id. Features without an id are
+/// keyed by their properties.
+fn key_features(features: &mut Value) {
+    let Value::Array(arr) = features else {
+        return;
+    };
+
+    let mut keyed = Map::new();
+    for feature in std::mem::take(arr) {
+        let id = feature
+            .get("id")
+            .or_else(|| feature.pointer("/properties/id"))
+            .or_else(|| feature.get("properties"))
+            .cloned()
+            .unwrap_or(Value::Null);
+        let base_key = match id {
+            Value::String(s) => s,
+            other => other.to_string(),
+        };
+
+        let mut key = base_key.clone();
+        let mut occurrence = 1;
+        while keyed.contains_key(&key) {
+            occurrence += 1;
+            key = format!("{}#{}", base_key, occurrence);
+        }
+        keyed.insert(key, feature);
+    }
+
+    *features = Value::Object(keyed);
+}
@@ -0,0 +1,76 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io;
+
+use crate::json_diff::child_path;
+
+/// Feeds serialized JSON straight into a hasher without building the string.
+struct HashWriter<'a, H: Hasher>(&'a mut H);
+
+impl<H: Hasher> io::Write for HashWriter<'_, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes the canonical (compact, key-sorted) serialization of a value, so documents
+/// that differ only in formatting or key order hash the same.
+pub fn canonical_hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // serde_json keeps object keys sorted and writing to a hasher cannot fail
+    serde_json::to_writer(HashWriter(&mut hasher), value).expect("hashing never fails");
+    hasher.finish()
+}
+
+/// SHA-256 of the canonical serialization as a hex string, stable across formatting
+/// and key order so it can be correlated with artifact registries.
+pub fn canonical_sha256(value: &Value) -> String {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, value).expect("hashing never fails");
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// A stable identifier of a difference: the first 16 hex digits of the SHA-256 of the
+/// file, the kind of difference, the path in dotted form and, optionally, the value.
+/// It stays the same across runs, versions and platforms as long as those do.
+pub fn fingerprint(file: &str, kind: &str, path: &str, value: Option<&Value>) -> String {
+    let mut hasher = Sha256::new();
+    for part in [file, kind, path] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    if let Some(value) = value {
+        serde_json::to_writer(&mut hasher, value).expect("hashing never fails");
+    }
+    hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Top-level keys whose subtrees hash the same in both documents, as paths.
+pub fn identical_top_level_paths(base: &Value, compare: &Value) -> Vec<String> {
+    let (Value::Object(base), Value::Object(compare)) = (base, compare) else {
+        return Vec::new();
+    };
+
+    base.iter()
+        .filter(|(key, value)| {
+            compare
+                .get(*key)
+                .is_some_and(|other| canonical_hash(value) == canonical_hash(other))
+        })
+        .map(|(key, _)| child_path("", key))
+        .collect()
+}
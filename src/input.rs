@@ -0,0 +1,716 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+use crate::compression::strip_compression_extension;
+use crate::json_diff::child_path;
+
+/// Formats that can be loaded into a `Value` for comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    Json,
+    /// Concatenated or newline-delimited JSON values, read as a top-level array
+    #[serde(rename = "json-stream")]
+    JsonStream,
+    /// JSON5, which also covers JSON with comments (JSONC)
+    Json5,
+    Yaml,
+    Toml,
+    Msgpack,
+    Cbor,
+}
+
+impl InputFormat {
+    /// Detects the format from the file extension, defaulting to JSON. A trailing
+    /// compression extension such as `.gz` is skipped.
+    pub fn from_path(path: &Path) -> InputFormat {
+        InputFormat::from_extension(path).unwrap_or(InputFormat::Json)
+    }
+
+    /// The format a file extension names, if it's one of the loaded formats.
+    pub fn from_extension(path: &Path) -> Option<InputFormat> {
+        let extension = strip_compression_extension(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref()? {
+            "json" => Some(InputFormat::Json),
+            "jsonl" | "ndjson" => Some(InputFormat::JsonStream),
+            "json5" | "jsonc" => Some(InputFormat::Json5),
+            "yaml" | "yml" => Some(InputFormat::Yaml),
+            "toml" => Some(InputFormat::Toml),
+            "msgpack" | "mpk" => Some(InputFormat::Msgpack),
+            "cbor" => Some(InputFormat::Cbor),
+            _ => None,
+        }
+    }
+}
+
+/// The format's usual spelling, e.g. `JSON` or `YAML`, for messages.
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            InputFormat::Json => "JSON",
+            InputFormat::JsonStream => "JSON stream",
+            InputFormat::Json5 => "JSON5",
+            InputFormat::Yaml => "YAML",
+            InputFormat::Toml => "TOML",
+            InputFormat::Msgpack => "MessagePack",
+            InputFormat::Cbor => "CBOR",
+        })
+    }
+}
+
+/// How non-finite numbers, which JSON cannot represent, are spelled after loading.
+pub const NAN: &str = "NaN";
+pub const INFINITY: &str = "Infinity";
+pub const NEG_INFINITY: &str = "-Infinity";
+/// How NaN is spelled with `ParseOptions::mark_nan`: a noncharacter, which Unicode
+/// reserves for internal use, keeps it apart from strings spelled "NaN".
+pub const NAN_MARKER: &str = "\u{fdd0}NaN";
+
+/// Options that change how documents are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Accept `NaN`, `Infinity` and `-Infinity` literals in JSON and JSON5, read as the
+    /// strings `NAN`, `INFINITY` and `NEG_INFINITY`
+    pub non_finite: bool,
+    /// Replace invalid UTF-8 and escaped lone UTF-16 surrogates with U+FFFD instead of
+    /// failing; `lossy_paths` finds the affected strings afterwards
+    pub lossy: bool,
+    pub yaml_references: YamlReferences,
+    /// Read NaN as `NAN_MARKER` instead of `NAN`, so a comparison can tell it from
+    /// strings spelled "NaN"; `unmark_nan` spells it `NAN` again
+    pub mark_nan: bool,
+}
+
+/// How YAML anchors, aliases and `<<` merge keys are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum YamlReferences {
+    /// Aliases are replaced by the values they refer to and merge keys are applied, as
+    /// applications reading the file see it
+    #[default]
+    Resolve,
+    /// Aliases are read as strings such as "*defaults" and merge keys as "<<" members,
+    /// as the file is written
+    Structural,
+}
+
+pub fn parse_document(content: &[u8], format: InputFormat) -> Result<Value> {
+    parse_document_with(content, format, &ParseOptions::default())
+}
+
+pub fn parse_document_with(
+    content: &[u8],
+    format: InputFormat,
+    options: &ParseOptions,
+) -> Result<Value> {
+    let content = if options.lossy {
+        repair_text(content, format)
+    } else {
+        Cow::Borrowed(content)
+    };
+    let json_text = matches!(
+        format,
+        InputFormat::Json | InputFormat::JsonStream | InputFormat::Json5
+    );
+    if json_text && !options.non_finite {
+        return parse_format(&content, format, options);
+    }
+
+    // only number tokens are rewritten; strings such as "Inf" are data and kept as is
+    let mut value = if json_text {
+        let text = quote_non_finite_literals(std::str::from_utf8(&content)?);
+        parse_format(text.as_bytes(), format, options)?
+    } else {
+        parse_format(&content, format, options)?
+    };
+    if !options.mark_nan {
+        unmark_nan(&mut value);
+    }
+    Ok(value)
+}
+
+/// Spells the `NAN_MARKER`s in the strings of a value as `NAN`.
+pub fn unmark_nan(value: &mut Value) {
+    match value {
+        Value::String(s) if s.contains(NAN_MARKER) => *s = s.replace(NAN_MARKER, NAN),
+        Value::Array(arr) => arr.iter_mut().for_each(unmark_nan),
+        Value::Object(map) => map.values_mut().for_each(unmark_nan),
+        _ => {}
+    }
+}
+
+fn parse_format(content: &[u8], format: InputFormat, options: &ParseOptions) -> Result<Value> {
+    match format {
+        InputFormat::Json => Ok(serde_json::from_slice(content)?),
+        InputFormat::JsonStream => Ok(Value::Array(
+            serde_json::Deserializer::from_slice(content)
+                .into_iter::<Value>()
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        InputFormat::Json5 => Ok(json5::from_str(std::str::from_utf8(content)?)?),
+        InputFormat::Yaml => match options.yaml_references {
+            YamlReferences::Resolve => {
+                let mut yaml: serde_yaml::Value = serde_yaml::from_slice(content)?;
+                yaml.apply_merge()?;
+                yaml_to_json(yaml)
+            }
+            YamlReferences::Structural => {
+                let text = quote_aliases(std::str::from_utf8(content)?)?;
+                yaml_to_json(serde_yaml::from_str(&text)?)
+            }
+        },
+        InputFormat::Toml => {
+            let toml: toml::Value = toml::from_str(std::str::from_utf8(content)?)?;
+            toml_to_json(toml)
+        }
+        InputFormat::Msgpack => {
+            let msgpack = rmpv::decode::read_value(&mut &content[..])?;
+            msgpack_to_json(msgpack, options.lossy)
+        }
+        InputFormat::Cbor => {
+            let cbor: ciborium::Value = ciborium::from_reader(content)?;
+            cbor_to_json(cbor)
+        }
+    }
+}
+
+/// Replaces invalid UTF-8 in text formats, and escapes of lone surrogates in JSON, with
+/// U+FFFD. Binary formats are left to their decoders.
+fn repair_text(content: &[u8], format: InputFormat) -> Cow<'_, [u8]> {
+    let text = match format {
+        InputFormat::Msgpack | InputFormat::Cbor => return Cow::Borrowed(content),
+        InputFormat::Json | InputFormat::JsonStream | InputFormat::Json5 => {
+            replace_lone_surrogates(String::from_utf8_lossy(content))
+        }
+        InputFormat::Yaml | InputFormat::Toml => String::from_utf8_lossy(content),
+    };
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    }
+}
+
+/// Rewrites `\uXXXX` escapes of surrogates that are not part of a pair as `\uFFFD`,
+/// which serde_json would otherwise reject.
+fn replace_lone_surrogates(text: Cow<'_, str>) -> Cow<'_, str> {
+    if !text.contains("\\u") {
+        return text;
+    }
+    let surrogate = |s: &str| {
+        let hex = s.strip_prefix("\\u")?.get(..4)?;
+        u16::from_str_radix(hex, 16)
+            .ok()
+            .filter(|unit| (0xD800..=0xDFFF).contains(unit))
+    };
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = &text[..];
+    while let Some(i) = rest.find('\\') {
+        output.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match surrogate(rest) {
+            Some(0xD800..=0xDBFF) if surrogate(&rest[6..]).is_some_and(|low| low >= 0xDC00) => {
+                output.push_str(&rest[..12]);
+                rest = &rest[12..];
+            }
+            Some(_) => {
+                output.push_str("\\uFFFD");
+                rest = &rest[6..];
+            }
+            None => {
+                // copy the backslash with the character it escapes
+                let len = rest[1..].chars().next().map_or(1, |ch| 1 + ch.len_utf8());
+                output.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+    }
+    output.push_str(rest);
+    Cow::Owned(output)
+}
+
+/// Paths of the strings, or of the members whose keys, contain U+FFFD, the replacement
+/// character lossy parsing substitutes for invalid text.
+pub fn lossy_paths(value: &Value, path: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    match value {
+        Value::String(s) if s.contains(char::REPLACEMENT_CHARACTER) => {
+            paths.push(path.to_string());
+        }
+        Value::Array(arr) => {
+            for (i, child) in arr.iter().enumerate() {
+                paths.extend(lossy_paths(child, &format!("{}[{}]", path, i)));
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = child_path(path, key);
+                if key.contains(char::REPLACEMENT_CHARACTER) {
+                    paths.push(child_path);
+                } else {
+                    paths.extend(lossy_paths(child, &child_path));
+                }
+            }
+        }
+        _ => {}
+    }
+    paths
+}
+
+/// Rewrites bare `NaN`, `Infinity` and `-Infinity` literals outside of strings and
+/// comments as quoted strings, so they survive parsing, with NaN marked.
+fn quote_non_finite_literals(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut previous: Option<char> = None;
+
+    while let Some(ch) = rest.chars().next() {
+        let is_boundary = !previous.is_some_and(|p| p.is_alphanumeric() || p == '_' || p == '$');
+
+        let skipped = match ch {
+            '"' | '\'' => string_end(rest, ch),
+            '/' if rest.starts_with("//") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest.find("*/").map_or(rest.len(), |end| end + 2),
+            _ => 0,
+        };
+        if skipped > 0 {
+            output.push_str(&rest[..skipped]);
+            previous = rest[..skipped].chars().last();
+            rest = &rest[skipped..];
+            continue;
+        }
+
+        let literal = [
+            ("-Infinity", NEG_INFINITY),
+            ("+Infinity", INFINITY),
+            ("Infinity", INFINITY),
+            ("NaN", NAN_MARKER),
+        ]
+        .into_iter()
+        .find(|(literal, _)| {
+            rest.starts_with(literal)
+                && !rest[literal.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| next.is_alphanumeric() || next == '_')
+        });
+        match literal {
+            Some((literal, spelling)) if is_boundary => {
+                output.push('"');
+                output.push_str(spelling);
+                output.push('"');
+                rest = &rest[literal.len()..];
+                previous = Some('"');
+            }
+            _ => {
+                output.push(ch);
+                rest = &rest[ch.len_utf8()..];
+                previous = Some(ch);
+            }
+        }
+    }
+
+    output
+}
+
+/// Byte length of the string literal at the start of `text`, including its quotes.
+fn string_end(text: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, ch) in text.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return i + ch.len_utf8();
+        }
+    }
+    text.len()
+}
+
+/// Non-finite floats have no JSON number, so they are read as their canonical spelling,
+/// with NaN marked until `parse_document_with` decides how to spell it.
+fn float_to_json(f: f64) -> Value {
+    match Number::from_f64(f) {
+        Some(n) => Value::Number(n),
+        None if f.is_nan() => Value::String(NAN_MARKER.to_string()),
+        None if f > 0.0 => Value::String(INFINITY.to_string()),
+        None => Value::String(NEG_INFINITY.to_string()),
+    }
+}
+
+/// Binary data has no JSON type, so it is compared as an array of byte values.
+fn bytes_to_json(bytes: Vec<u8>) -> Value {
+    Value::Array(bytes.into_iter().map(Value::from).collect())
+}
+
+fn msgpack_to_json(msgpack: rmpv::Value, lossy: bool) -> Result<Value> {
+    Ok(match msgpack {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(i), _) => Value::from(i),
+            (None, Some(u)) => Value::from(u),
+            (None, None) => float_to_json(i.as_f64().unwrap_or(f64::NAN)),
+        },
+        rmpv::Value::F32(f) => float_to_json(f as f64),
+        rmpv::Value::F64(f) => float_to_json(f),
+        rmpv::Value::String(s) if lossy && !s.is_str() => {
+            Value::String(String::from_utf8_lossy(s.as_bytes()).into_owned())
+        }
+        rmpv::Value::String(s) => match s.into_str() {
+            Some(s) => Value::String(s),
+            None => bail!("MessagePack string is not valid UTF-8"),
+        },
+        rmpv::Value::Binary(bytes) => bytes_to_json(bytes),
+        rmpv::Value::Array(arr) => Value::Array(
+            arr.into_iter()
+                .map(|value| msgpack_to_json(value, lossy))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        rmpv::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = match msgpack_to_json(key, lossy)? {
+                    Value::String(s) => s,
+                    key @ (Value::Null | Value::Bool(_) | Value::Number(_)) => key.to_string(),
+                    key => bail!("unsupported MessagePack map key: {}", key),
+                };
+                map.insert(key, msgpack_to_json(value, lossy)?);
+            }
+            Value::Object(map)
+        }
+        rmpv::Value::Ext(_, bytes) => bytes_to_json(bytes),
+    })
+}
+
+fn cbor_to_json(cbor: ciborium::Value) -> Result<Value> {
+    Ok(match cbor {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            match (i64::try_from(i), u64::try_from(i)) {
+                (Ok(i), _) => Value::from(i),
+                (_, Ok(u)) => Value::from(u),
+                _ => float_to_json(i as f64),
+            }
+        }
+        ciborium::Value::Float(f) => float_to_json(f),
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => bytes_to_json(bytes),
+        ciborium::Value::Array(arr) => Value::Array(
+            arr.into_iter()
+                .map(cbor_to_json)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        ciborium::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = match cbor_to_json(key)? {
+                    Value::String(s) => s,
+                    key @ (Value::Null | Value::Bool(_) | Value::Number(_)) => key.to_string(),
+                    key => bail!("unsupported CBOR map key: {}", key),
+                };
+                map.insert(key, cbor_to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        ciborium::Value::Tag(_, value) => cbor_to_json(*value)?,
+        other => bail!("unsupported CBOR value: {:?}", other),
+    })
+}
+
+fn toml_to_json(toml: toml::Value) -> Result<Value> {
+    Ok(match toml {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => float_to_json(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // dates and times have no JSON type, so they are compared in their RFC 3339 form
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(arr) => Value::Array(
+            arr.into_iter()
+                .map(toml_to_json)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, toml_to_json(value)?)))
+                .collect::<Result<Map<_, _>>>()?,
+        ),
+    })
+}
+
+/// Records where the alias nodes of a YAML document start, as reported by the parser.
+struct AliasPositions(Vec<usize>);
+
+impl MarkedEventReceiver for AliasPositions {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        if let Event::Alias(_) = event {
+            self.0.push(mark.index());
+        }
+    }
+}
+
+/// Rewrites YAML alias nodes such as `*defaults` as the quoted string `"*defaults"`, so
+/// the document reads with its references in place of the values they name. The aliases
+/// are found by the YAML parser, so scalars and comments containing `*` are left alone.
+fn quote_aliases(text: &str) -> Result<String> {
+    let mut aliases = AliasPositions(Vec::new());
+    Parser::new_from_str(text)
+        .load(&mut aliases, true)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    // markers count characters; the alias name runs up to a blank or flow indicator
+    let starts: HashSet<usize> = aliases.0.into_iter().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut alias: Option<String> = None;
+    for (index, c) in text.chars().enumerate() {
+        if let Some(name) = &mut alias {
+            if !(c.is_whitespace() || ",[]{}".contains(c)) {
+                name.push(c);
+                continue;
+            }
+            output.push_str(&Value::String(std::mem::take(name)).to_string());
+            alias = None;
+        }
+        if starts.contains(&index) {
+            alias = Some(c.to_string());
+        } else {
+            output.push(c);
+        }
+    }
+    if let Some(name) = alias {
+        output.push_str(&Value::String(name).to_string());
+    }
+    Ok(output)
+}
+
+fn yaml_to_json(yaml: serde_yaml::Value) -> Result<Value> {
+    Ok(match yaml {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                float_to_json(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(seq) => Value::Array(
+            seq.into_iter()
+                .map(yaml_to_json)
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, value) in mapping {
+                map.insert(yaml_key(key)?, yaml_to_json(value)?);
+            }
+            Value::Object(map)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value)?,
+    })
+}
+
+/// JSON object keys are strings, so scalar YAML keys are converted to their text form.
+fn yaml_key(key: serde_yaml::Value) -> Result<String> {
+    Ok(match key {
+        serde_yaml::Value::String(s) => s,
+        serde_yaml::Value::Null => "null".to_string(),
+        serde_yaml::Value::Bool(b) => b.to_string(),
+        serde_yaml::Value::Number(n) => n.to_string(),
+        serde_yaml::Value::Tagged(tagged) => yaml_key(tagged.value)?,
+        other => bail!("unsupported YAML mapping key: {:?}", other),
+    })
+}
+
+/// Paths of the object members whose key appeared before in the same object, which
+/// parsing silently drops in favor of the last value. Only JSON, JSON streams and JSON5
+/// are checked; the YAML and TOML parsers reject duplicate keys themselves.
+pub fn duplicate_keys(
+    content: &[u8],
+    format: InputFormat,
+    options: &ParseOptions,
+) -> Result<Vec<String>> {
+    let content = if options.lossy {
+        repair_text(content, format)
+    } else {
+        Cow::Borrowed(content)
+    };
+    let quoted;
+    let content: &[u8] = match format {
+        InputFormat::Json | InputFormat::JsonStream | InputFormat::Json5 if options.non_finite => {
+            quoted = quote_non_finite_literals(std::str::from_utf8(&content)?);
+            quoted.as_bytes()
+        }
+        _ => &content,
+    };
+
+    let duplicates = RefCell::new(Vec::new());
+    let tracker = |path: String| KeyTracker {
+        path,
+        duplicates: &duplicates,
+    };
+    match format {
+        InputFormat::Json => tracker(String::new())
+            .deserialize(&mut serde_json::Deserializer::from_slice(content))?,
+        InputFormat::JsonStream => {
+            let mut deserializer = serde_json::Deserializer::from_slice(content);
+            let mut index = 0;
+            while deserializer.end().is_err() {
+                tracker(format!("[{}]", index)).deserialize(&mut deserializer)?;
+                index += 1;
+            }
+        }
+        InputFormat::Json5 => tracker(String::new()).deserialize(
+            &mut json5::Deserializer::from_str(std::str::from_utf8(content)?)?,
+        )?,
+        _ => {}
+    }
+    Ok(duplicates.into_inner())
+}
+
+/// Walks a document without building it, recording the paths of duplicate keys.
+struct KeyTracker<'a> {
+    path: String,
+    duplicates: &'a RefCell<Vec<String>>,
+}
+
+impl<'de> DeserializeSeed<'de> for KeyTracker<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeyTracker<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let mut index = 0;
+        while seq
+            .next_element_seed(KeyTracker {
+                path: format!("{}[{}]", self.path, index),
+                duplicates: self.duplicates,
+            })?
+            .is_some()
+        {
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = child_path(&self.path, &key);
+            if !keys.insert(key) && !self.duplicates.borrow().contains(&path) {
+                self.duplicates.borrow_mut().push(path.clone());
+            }
+            map.next_value_seed(KeyTracker {
+                path,
+                duplicates: self.duplicates,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_non_finite_number_tokens_are_quoted() {
+        let quoted = quote_non_finite_literals(
+            r#"{"a": NaN, "b": -Infinity, "c": +Infinity, "d": [Infinity], "e": "NaN", "f": "say \"NaN\" twice", 'g': 'NaN'}"#,
+        );
+        assert_eq!(
+            quoted,
+            format!(
+                r#"{{"a": "{}", "b": "-Infinity", "c": "Infinity", "d": ["Infinity"], "e": "NaN", "f": "say \"NaN\" twice", 'g': 'NaN'}}"#,
+                NAN_MARKER
+            )
+        );
+        // identifiers and comments that contain the literals are left alone
+        let text = "{NaNa: 1, $NaN: 2, x_Infinity: 3} // NaN\n/* Infinity */";
+        assert_eq!(quote_non_finite_literals(text), text);
+    }
+
+    #[test]
+    fn lone_surrogates_are_replaced_and_pairs_kept() {
+        let replace = |text: &str| replace_lone_surrogates(Cow::Borrowed(text)).into_owned();
+        assert_eq!(replace(r#""\ud83d\ude00""#), r#""\ud83d\ude00""#);
+        assert_eq!(replace(r#""\ud83d!""#), r#""\uFFFD!""#);
+        assert_eq!(replace(r#""\ude00\ud83d""#), r#""\uFFFD\uFFFD""#);
+        assert_eq!(replace(r#""\ud83d\u0041""#), r#""\uFFFD\u0041""#);
+        // an escaped backslash before "u" isn't an escape
+        assert_eq!(replace(r#""\\ud83d""#), r#""\\ud83d""#);
+        assert_eq!(replace(r#""\"é\n""#), r#""\"é\n""#);
+    }
+
+    #[test]
+    fn strings_spelled_nan_are_kept_apart_from_nan() {
+        let options = ParseOptions {
+            non_finite: true,
+            mark_nan: true,
+            ..Default::default()
+        };
+        let text = br#"{"literal": NaN, "string": "NaN"}"#;
+        let value = parse_document_with(text, InputFormat::Json, &options).unwrap();
+        assert_eq!(value["literal"], NAN_MARKER);
+        assert_eq!(value["string"], NAN);
+
+        let mut value = value;
+        unmark_nan(&mut value);
+        assert_eq!(value["literal"], NAN);
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::arrays::ArrayOrder;
+use crate::json_diff::child_path;
+use crate::rules::{ArrayOrderRule, Rules, ToleranceRule};
+
+/// A jest asymmetric matcher used as a snapshot property matcher.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyMatcher {
+    /// `expect.any(Number)`, `expect.anything()`, `expect.stringMatching(...)` and
+    /// `expect.stringContaining(...)`: the value may vary
+    Any,
+    /// `expect.closeTo(number, digits)`: numbers within half a unit of the last digit
+    CloseTo { digits: i32 },
+    /// `expect.arrayContaining([...])`: the elements may come in any order
+    ArrayContaining,
+}
+
+/// Matchers with the paths they're at.
+pub type PathMatchers = Vec<(String, PropertyMatcher)>;
+
+fn matcher_regex() -> &'static Regex {
+    static MATCHER: OnceLock<Regex> = OnceLock::new();
+    MATCHER.get_or_init(|| {
+        Regex::new(
+            r"(?x)^(?:
+                expect\.(?P<call>any|anything|stringMatching|stringContaining|closeTo|arrayContaining)
+                    \((?P<args>.*)\)
+              | (?P<printed>Any<\w+>|Anything|StringMatching|StringContaining|NumberCloseTo|ArrayContaining)
+                    (?:\s+(?P<sample>.*))?
+            )$",
+        )
+        .expect("valid matcher regex")
+    })
+}
+
+impl PropertyMatcher {
+    /// Parses a matcher as written in a test, such as `expect.any(Number)`, or as jest
+    /// prints it in a snapshot, such as `Any<Number>`. `None` for other text, including
+    /// negated matchers like `expect.not.stringContaining(...)`, which can't be expressed
+    /// as rules.
+    pub fn parse(text: &str) -> Option<PropertyMatcher> {
+        let captures = matcher_regex().captures(text.trim())?;
+        let name = captures
+            .name("call")
+            .or_else(|| captures.name("printed"))?
+            .as_str();
+        Some(match name {
+            "closeTo" => {
+                // expect.closeTo(number, numDigits = 2)
+                let args = captures.name("args").map_or("", |args| args.as_str());
+                let digits = args
+                    .split(',')
+                    .nth(1)
+                    .and_then(|digits| digits.trim().parse().ok());
+                PropertyMatcher::CloseTo {
+                    digits: digits.unwrap_or(2),
+                }
+            }
+            "NumberCloseTo" => {
+                // NumberCloseTo 0.3 (5 digits)
+                let sample = captures.name("sample").map_or("", |sample| sample.as_str());
+                let digits = sample
+                    .split_once('(')
+                    .and_then(|(_, digits)| digits.split_whitespace().next())
+                    .and_then(|digits| digits.parse().ok());
+                PropertyMatcher::CloseTo {
+                    digits: digits.unwrap_or(2),
+                }
+            }
+            "arrayContaining" | "ArrayContaining" => PropertyMatcher::ArrayContaining,
+            _ => PropertyMatcher::Any,
+        })
+    }
+}
+
+/// The matchers of a property matcher object written as JSON, with each matcher as a
+/// string, e.g. `{"id": "expect.any(Number)", "total": "expect.closeTo(9.99, 2)"}`.
+pub fn matchers_in_json(value: &Value) -> PathMatchers {
+    fn walk(value: &Value, path: String, matchers: &mut PathMatchers) {
+        match value {
+            Value::String(text) => {
+                if let Some(matcher) = PropertyMatcher::parse(text) {
+                    matchers.push((path, matcher));
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk(item, format!("{}[{}]", path, i), matchers);
+                }
+            }
+            Value::Object(map) => {
+                for (key, item) in map {
+                    walk(item, child_path(&path, key), matchers);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut matchers = Vec::new();
+    walk(value, String::new(), &mut matchers);
+    matchers
+}
+
+/// The snapshots of a jest `.snap` file, as their names and printed values.
+fn snapshots(text: &str) -> Result<Vec<(String, String)>> {
+    // reads a template literal up to its closing backtick, unescaping it
+    fn literal(rest: &str) -> Option<(String, &str)> {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some((_, escaped @ ('`' | '\\' | '$'))) => value.push(escaped),
+                    Some((_, other)) => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                    None => return None,
+                },
+                '`' => return Some((value, &rest[i + 1..])),
+                ch => value.push(ch),
+            }
+        }
+        None
+    }
+
+    let mut snapshots = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("exports[`") {
+        let Some((name, after)) = literal(&rest[start + "exports[`".len()..]) else {
+            bail!("Unterminated snapshot name");
+        };
+        let Some(after) = after.trim_start().strip_prefix("] = `") else {
+            bail!("Invalid snapshot {:?}: expected ] = `", name);
+        };
+        let Some((value, after)) = literal(after) else {
+            bail!("Unterminated snapshot {:?}", name);
+        };
+        snapshots.push((name, value));
+        rest = after;
+    }
+    if snapshots.is_empty() {
+        bail!("No exports[`...`] snapshots found");
+    }
+    Ok(snapshots)
+}
+
+/// An object or array being read, with the index of its next element.
+struct Frame {
+    path: String,
+    array: bool,
+    next_index: usize,
+}
+
+/// The matchers jest printed into a snapshot, such as `"id": Any<Number>,`, with the
+/// paths they're at. Values are read line by line, as jest's pretty-format prints them.
+fn matchers_in_snapshot(snapshot: &str) -> PathMatchers {
+    let mut matchers = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut in_string = false;
+    for line in snapshot.lines() {
+        let trimmed = line.trim();
+        // strings with line breaks are printed across lines
+        if in_string {
+            in_string = !(trimmed.ends_with("\",") || trimmed.ends_with('"'));
+            continue;
+        }
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with(['}', ']']) {
+            stack.pop();
+            continue;
+        }
+
+        let (path, value) = match stack.last_mut() {
+            None => (String::new(), trimmed),
+            Some(frame) if frame.array => {
+                let path = format!("{}[{}]", frame.path, frame.next_index);
+                frame.next_index += 1;
+                (path, trimmed)
+            }
+            Some(frame) => match quoted_key(trimmed) {
+                Some((key, value)) => (child_path(&frame.path, &key), value),
+                None => continue,
+            },
+        };
+        let value = value.strip_suffix(',').unwrap_or(value).trim();
+
+        if let Some(opener) = value.strip_suffix(['{', '[']) {
+            if PropertyMatcher::parse(opener) == Some(PropertyMatcher::ArrayContaining) {
+                matchers.push((path.clone(), PropertyMatcher::ArrayContaining));
+            }
+            stack.push(Frame {
+                path,
+                array: value.ends_with('['),
+                next_index: 0,
+            });
+        } else if let Some(matcher) = PropertyMatcher::parse(value) {
+            matchers.push((path, matcher));
+        } else if value.starts_with('"') {
+            let closed = value.len() > 1 && value.ends_with('"') && !value.ends_with("\\\"");
+            in_string = !closed;
+        }
+    }
+    matchers
+}
+
+/// Splits `"key": value` into the key and the value text.
+fn quoted_key(line: &str) -> Option<(String, &str)> {
+    let rest = line.strip_prefix('"')?;
+    let mut escaped = false;
+    for (i, ch) in rest.char_indices() {
+        match ch {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => {
+                let key: String = serde_json::from_str(&line[..i + 2]).ok()?;
+                let value = rest[i + 1..].trim_start().strip_prefix(':')?;
+                return Some((key, value.trim_start()));
+            }
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// The matchers of the snapshots in a jest `.snap` file, by snapshot name.
+pub fn matchers_in_snapshots(text: &str) -> Result<Vec<(String, PathMatchers)>> {
+    Ok(snapshots(text)?
+        .into_iter()
+        .map(|(name, snapshot)| {
+            let matchers = matchers_in_snapshot(&snapshot);
+            (name, matchers)
+        })
+        .collect())
+}
+
+/// A rules file granting the leeway of the matchers: values that may vary are ignored,
+/// `closeTo` numbers get an absolute tolerance of half a unit in their last digit, like
+/// jest's, and `arrayContaining` arrays are compared in any order.
+pub fn to_rules(matchers: &[(String, PropertyMatcher)]) -> Rules {
+    let mut rules = Rules::default();
+    for (path, matcher) in matchers {
+        match matcher {
+            PropertyMatcher::Any => {
+                if !rules.ignore.contains(path) {
+                    rules.ignore.push(path.clone());
+                }
+            }
+            PropertyMatcher::CloseTo { digits } => rules.tolerances.push(ToleranceRule {
+                path: path.clone(),
+                absolute: 10f64.powi(-digits) / 2.0,
+                relative: 0.0,
+            }),
+            PropertyMatcher::ArrayContaining => rules.array_order.push(ArrayOrderRule {
+                path: path.clone(),
+                order: ArrayOrder::Ignore,
+            }),
+        }
+    }
+    rules
+}
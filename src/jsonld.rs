@@ -0,0 +1,122 @@
+use serde_json::{Map, Value};
+
+/// Term definitions in scope while expanding a JSON-LD document.
+#[derive(Debug, Clone, Default)]
+struct ActiveContext {
+    terms: Map<String, Value>,
+    vocab: Option<String>,
+}
+
+/// Expands the keys of JSON-LD documents to full IRIs using their inline `@context`, so
+/// documents that only differ in the terms, prefixes or keyword aliases they use compare
+/// equal. The `@context` members are removed afterwards.
+///
+/// Remote contexts (given as URLs) cannot be resolved and leave keys as they are.
+pub fn normalize_jsonld(value: &mut Value) {
+    expand(value, &ActiveContext::default());
+}
+
+fn expand(value: &mut Value, context: &ActiveContext) {
+    match value {
+        Value::Object(map) => {
+            let context = match map.remove("@context") {
+                Some(local) => context.merged(&local),
+                None => context.clone(),
+            };
+
+            let mut expanded = Map::new();
+            for (key, mut child) in std::mem::take(map) {
+                let key = context.expand_key(&key);
+                if key == "@type" {
+                    expand_type(&mut child, &context);
+                } else {
+                    expand(&mut child, &context);
+                }
+                expanded.insert(key, child);
+            }
+            *map = expanded;
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                expand(child, context);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Types are IRIs, so compact type names are expanded like keys.
+fn expand_type(value: &mut Value, context: &ActiveContext) {
+    match value {
+        Value::String(s) => *s = context.expand_key(s),
+        Value::Array(arr) => {
+            for child in arr {
+                expand_type(child, context);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl ActiveContext {
+    fn merged(&self, local: &Value) -> ActiveContext {
+        let mut context = self.clone();
+        match local {
+            Value::Object(definitions) => {
+                for (term, definition) in definitions {
+                    if term == "@vocab" {
+                        context.vocab = definition.as_str().map(str::to_string);
+                    } else if !term.starts_with('@') {
+                        context.terms.insert(term.clone(), definition.clone());
+                    }
+                }
+            }
+            Value::Array(contexts) => {
+                for local in contexts {
+                    context = context.merged(local);
+                }
+            }
+            // a null context resets everything in scope
+            Value::Null => context = ActiveContext::default(),
+            _ => {}
+        }
+        context
+    }
+
+    fn term_iri(&self, term: &str) -> Option<&str> {
+        match self.terms.get(term)? {
+            Value::String(iri) => Some(iri),
+            Value::Object(definition) => definition.get("@id")?.as_str(),
+            _ => None,
+        }
+    }
+
+    fn expand_key(&self, key: &str) -> String {
+        self.expand_iri(key, 0)
+    }
+
+    fn expand_iri(&self, key: &str, depth: usize) -> String {
+        // guards against terms defined in terms of each other
+        if key.starts_with('@') || depth > 8 {
+            return key.to_string();
+        }
+
+        if let Some(iri) = self.term_iri(key).filter(|iri| *iri != key) {
+            return self.expand_iri(iri, depth + 1);
+        }
+
+        if let Some((prefix, suffix)) = key.split_once(':') {
+            if !suffix.starts_with("//") {
+                if let Some(iri) = self.term_iri(prefix) {
+                    return format!("{}{}", self.expand_iri(iri, depth + 1), suffix);
+                }
+            }
+            return key.to_string();
+        }
+
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, key),
+            None => key.to_string(),
+        }
+    }
+}
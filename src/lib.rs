@@ -1,45 +1,91 @@
 use serde::{Deserialize, Serialize};
 pub use serde_json::Value;
+pub mod analysis;
+pub mod arrays;
+pub mod collation;
+pub mod comparators;
+pub mod compare;
+pub mod compression;
+pub mod extract;
+pub mod geojson;
+pub mod hash;
+pub mod input;
+pub mod jest;
+pub mod jsonld;
+pub mod merge;
+pub mod metadata;
+pub mod moves;
+pub mod patch;
+pub mod pattern;
+pub mod placeholder;
+pub mod proto;
+pub mod rules;
+pub mod schema;
+pub mod script;
+pub mod shape;
+pub mod strings;
+pub mod suppress;
+pub mod template;
+pub mod text_diff;
+pub mod timestamp;
+
 pub mod json_diff {
     use super::*;
+    use crate::pattern::PathPattern;
+
+    /// Number of values in the document: every object, array and scalar counts once.
+    pub fn count_nodes(value: &Value) -> usize {
+        1 + match value {
+            Value::Array(arr) => arr.iter().map(count_nodes).sum(),
+            Value::Object(map) => map.values().map(count_nodes).sum(),
+            _ => 0,
+        }
+    }
 
     pub fn get_all_items(value: &Value, current_path: String) -> Vec<(String, Value)> {
+        get_all_items_with(value, current_path, &FlattenOptions::default())
+    }
+
+    /// Options that change which items `get_all_items_with` produces.
+    #[derive(Debug, Clone, Default)]
+    pub struct FlattenOptions {
+        /// Only visit a deterministic sample of this many elements in larger arrays
+        pub sample: Option<usize>,
+        /// Object keys skipped wherever they occur, also inside the values of their parents
+        pub ignore_keys: Vec<String>,
+    }
+
+    pub fn get_all_items_with(
+        value: &Value,
+        current_path: String,
+        options: &FlattenOptions,
+    ) -> Vec<(String, Value)> {
         let mut items = Vec::new();
 
         match value {
             Value::Object(map) => {
                 for (key, val) in map {
-                    // If the key contains special characters, wrap it in square brackets and
-                    // quotes
-                    let new_path = if current_path.is_empty() {
-                        if needs_escaping(key) {
-                            format!("[\"{}\"]", key)
-                        } else {
-                            key.clone()
-                        }
-                    } else {
-                        if needs_escaping(key) {
-                            format!("{}[\"{}\"]", current_path, key)
-                        } else {
-                            format!("{}.{}", current_path, key)
-                        }
-                    };
+                    if options.ignore_keys.contains(key) {
+                        continue;
+                    }
+                    let new_path = child_path(&current_path, key);
 
-                    items.push((new_path.clone(), val.clone()));
+                    items.push((new_path.clone(), without_keys(val, &options.ignore_keys)));
 
                     if val.is_object() || val.is_array() {
-                        items.extend(get_all_items(val, new_path));
+                        items.extend(get_all_items_with(val, new_path, options));
                     }
                 }
             }
             Value::Array(arr) => {
-                for (i, val) in arr.iter().enumerate() {
+                for i in array_indices(arr.len(), options) {
+                    let val = &arr[i];
                     let new_path = format!("{}[{}]", current_path, i);
 
-                    items.push((new_path.clone(), val.clone()));
+                    items.push((new_path.clone(), without_keys(val, &options.ignore_keys)));
 
                     if val.is_object() || val.is_array() {
-                        items.extend(get_all_items(val, new_path));
+                        items.extend(get_all_items_with(val, new_path, options));
                     }
                 }
             }
@@ -53,6 +99,191 @@ pub mod json_diff {
         items
     }
 
+    pub(crate) fn without_keys(value: &Value, keys: &[String]) -> Value {
+        let mut value = value.clone();
+        remove_keys(&mut value, keys);
+        value
+    }
+
+    /// Removes every object member named by one of `keys`, at any depth.
+    pub fn remove_keys(value: &mut Value, keys: &[String]) {
+        if keys.is_empty() {
+            return;
+        }
+        match value {
+            Value::Object(map) => {
+                map.retain(|key, _| !keys.contains(key));
+                for child in map.values_mut() {
+                    remove_keys(child, keys);
+                }
+            }
+            Value::Array(arr) => {
+                for child in arr {
+                    remove_keys(child, keys);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Renames the object keys of `value` that match a key of `like` only when compared
+    /// case-insensitively to the spelling in `like`, at any depth, so both documents
+    /// flatten to the same paths. Keys spelled exactly as in `like` are left as they are.
+    pub fn align_key_case(value: &mut Value, like: &Value) {
+        match (value, like) {
+            (Value::Object(map), Value::Object(like_map)) => {
+                if map.keys().any(|key| !like_map.contains_key(key)) {
+                    let members = std::mem::take(map);
+                    let targets: Vec<String> = members
+                        .keys()
+                        .map(|key| {
+                            if like_map.contains_key(key) {
+                                return key.clone();
+                            }
+                            let lowercase = key.to_lowercase();
+                            like_map
+                                .keys()
+                                .find(|like_key| {
+                                    !members.contains_key(*like_key)
+                                        && like_key.to_lowercase() == lowercase
+                                })
+                                .unwrap_or(key)
+                                .clone()
+                        })
+                        .collect();
+                    // of two keys spelled differently from the same key of `like`, only
+                    // the first is renamed
+                    for (target, (key, child)) in targets.into_iter().zip(members) {
+                        let key = if map.contains_key(&target) {
+                            key
+                        } else {
+                            target
+                        };
+                        map.insert(key, child);
+                    }
+                }
+                for (key, child) in map.iter_mut() {
+                    if let Some(like_child) = like_map.get(key) {
+                        align_key_case(child, like_child);
+                    }
+                }
+            }
+            (Value::Array(arr), Value::Array(like_arr)) => {
+                for (child, like_child) in arr.iter_mut().zip(like_arr) {
+                    align_key_case(child, like_child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Indices of the array elements that are visited, spread over the array when it is
+    /// longer than the sample size: one element of each of `sample` equal stretches, at
+    /// an offset that varies from stretch to stretch so that data changing with a
+    /// period doesn't alias with the sample. The offsets are a hash of the stretch and
+    /// the array length, so every run samples the same elements.
+    pub fn array_indices(len: usize, options: &FlattenOptions) -> Vec<usize> {
+        match options.sample {
+            Some(sample) if len > sample => (0..sample)
+                .map(|i| {
+                    let start = i * len / sample;
+                    let end = (i + 1) * len / sample;
+                    start + (mix(i as u64 ^ ((len as u64) << 32)) % (end - start) as u64) as usize
+                })
+                .collect(),
+            _ => (0..len).collect(),
+        }
+    }
+
+    /// The splitmix64 finalizer, a cheap well-spread hash of an integer.
+    fn mix(mut x: u64) -> u64 {
+        x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    }
+
+    /// Counts the paths `get_all_items` would produce for which `include` returns true,
+    /// without copying the values.
+    pub fn count_paths(value: &Value, current_path: &str, include: &dyn Fn(&str) -> bool) -> usize {
+        let count = |path: String, val: &Value| {
+            usize::from(include(&path)) + count_paths(val, &path, include)
+        };
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, val)| count(child_path(current_path, key), val))
+                .sum(),
+            Value::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .map(|(i, val)| count(format!("{}[{}]", current_path, i), val))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Like `count_paths`, with each path counted by its weight; 0 leaves it out.
+    pub fn weigh_paths(value: &Value, current_path: &str, weight: &dyn Fn(&str) -> f64) -> f64 {
+        let weigh = |path: String, val: &Value| weight(&path) + weigh_paths(val, &path, weight);
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, val)| weigh(child_path(current_path, key), val))
+                .sum(),
+            Value::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .map(|(i, val)| weigh(format!("{}[{}]", current_path, i), val))
+                .sum(),
+            _ => 0.0,
+        }
+    }
+
+    /// Paths and lengths of the arrays that are sampled when flattening with `options`.
+    pub fn sampled_arrays(
+        value: &Value,
+        current_path: String,
+        options: &FlattenOptions,
+    ) -> Vec<(String, usize)> {
+        let mut arrays = Vec::new();
+
+        match value {
+            Value::Object(map) => {
+                for (key, val) in map {
+                    if !options.ignore_keys.contains(key) {
+                        arrays.extend(sampled_arrays(val, child_path(&current_path, key), options));
+                    }
+                }
+            }
+            Value::Array(arr) => {
+                let indices = array_indices(arr.len(), options);
+                if indices.len() < arr.len() {
+                    arrays.push((current_path.clone(), arr.len()));
+                }
+                for i in indices {
+                    let new_path = format!("{}[{}]", current_path, i);
+                    arrays.extend(sampled_arrays(&arr[i], new_path, options));
+                }
+            }
+            _ => {}
+        }
+
+        arrays
+    }
+
+    pub fn child_path(current_path: &str, key: &str) -> String {
+        // If the key contains special characters, wrap it in square brackets and
+        // quotes
+        if needs_escaping(key) {
+            format!("{}[\"{}\"]", current_path, key)
+        } else if current_path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", current_path, key)
+        }
+    }
+
     pub fn get_value_by_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
         let parts = parse_path(path);
         let mut current = value;
@@ -145,23 +376,456 @@ pub mod json_diff {
         parts
     }
 
+    /// Joins path parts into a dotted path, the inverse of `parse_path`.
+    pub fn path_from_parts(parts: &[PathPart]) -> String {
+        parts.iter().fold(String::new(), |path, part| match part {
+            PathPart::Key(key) => child_path(&path, key),
+            PathPart::Index(index) => format!("{}[{}]", path, index),
+        })
+    }
+
+    /// Converts path parts into a JSON Pointer (RFC 6901).
+    pub fn pointer_from_parts(parts: &[PathPart]) -> String {
+        parts
+            .iter()
+            .map(|part| match part {
+                PathPart::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+                PathPart::Index(index) => format!("/{}", index),
+            })
+            .collect()
+    }
+
+    /// Splits a JSON Pointer into path parts. A pointer doesn't say whether a segment
+    /// is an array index or an object key, so segments that are valid indices (digits
+    /// without a leading zero) become indices.
+    pub fn parts_from_pointer(pointer: &str) -> anyhow::Result<Vec<PathPart>> {
+        if pointer.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(segments) = pointer.strip_prefix('/') else {
+            anyhow::bail!("JSON Pointer must be empty or start with /: {}", pointer);
+        };
+
+        Ok(segments
+            .split('/')
+            .map(|segment| {
+                let is_index = segment.bytes().all(|b| b.is_ascii_digit())
+                    && !segment.is_empty()
+                    && (segment == "0" || !segment.starts_with('0'));
+                match segment.parse() {
+                    Ok(index) if is_index => PathPart::Index(index),
+                    _ => PathPart::Key(segment.replace("~1", "/").replace("~0", "~")),
+                }
+            })
+            .collect())
+    }
+
+    /// Converts a dotted path (`config.servers[0]["a.b"]`) into a JSON Pointer.
+    pub fn path_to_pointer(path: &str) -> String {
+        pointer_from_parts(&parse_path(path))
+    }
+
+    /// Converts a JSON Pointer into a dotted path.
+    pub fn pointer_to_path(pointer: &str) -> anyhow::Result<String> {
+        Ok(path_from_parts(&parts_from_pointer(pointer)?))
+    }
+
+    /// Returns true if `path` is `ancestor` itself or one of its descendants.
+    pub fn is_path_within(path: &str, ancestor: &str) -> bool {
+        match path.strip_prefix(ancestor) {
+            Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+            None => false,
+        }
+    }
+
     pub fn needs_escaping(key: &str) -> bool {
         key.contains('.') || key.contains('[') || key.contains(']') || key.contains('"')
     }
 
+    /// Options that change when two values are considered equal.
+    #[derive(Debug, Clone)]
+    pub struct CompareOptions {
+        /// Whether NaN equals NaN; IEEE 754 says it doesn't. Only NaN read with
+        /// `ParseOptions::mark_nan` counts, as strings spelled "NaN" are data
+        pub nan_equals_nan: bool,
+        /// Numbers are equal when they differ by at most this fraction of the larger one
+        pub relative_tolerance: f64,
+        /// Numbers are equal when they differ by at most this much
+        pub absolute_tolerance: f64,
+        /// Tolerances for the numbers at matching paths, replacing the global ones; the
+        /// last matching entry wins
+        pub path_tolerances: Vec<PathTolerance>,
+        /// Compare ISO 8601 strings with each other and with epoch numbers as instants,
+        /// equal when at most this far apart
+        pub timestamp_tolerance: Option<std::time::Duration>,
+        /// Timestamp comparison for the values at matching paths, replacing the global
+        /// setting; the last matching entry wins
+        pub path_timestamps: Vec<PathTimestamps>,
+        /// Compare strings holding a JSON number or boolean, such as `"42"` or `"true"`,
+        /// with numbers and booleans as the scalars they hold
+        pub coerce: bool,
+        /// Compare the strings at matching paths by a locale's collation rules; the last
+        /// matching entry wins
+        pub path_collations: Vec<PathCollation>,
+        /// Compare the values at matching paths with a built-in comparator, such as
+        /// phone numbers as E.164; the last matching entry wins
+        pub path_comparators: Vec<PathComparator>,
+        /// A script deciding whether the values at each path are equal, before any other
+        /// option is applied
+        pub script: Option<std::sync::Arc<crate::script::ComparatorScript>>,
+        /// Strings with `${VAR}` or `{{ var }}` placeholders equal the values they could
+        /// render to
+        pub placeholders: bool,
+    }
+
+    impl Default for CompareOptions {
+        fn default() -> Self {
+            CompareOptions {
+                nan_equals_nan: true,
+                relative_tolerance: 0.0,
+                absolute_tolerance: 0.0,
+                path_tolerances: Vec::new(),
+                timestamp_tolerance: None,
+                path_timestamps: Vec::new(),
+                coerce: false,
+                path_collations: Vec::new(),
+                path_comparators: Vec::new(),
+                script: None,
+                placeholders: false,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct PathTolerance {
+        pub pattern: PathPattern,
+        pub absolute: f64,
+        pub relative: f64,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct PathTimestamps {
+        pub pattern: PathPattern,
+        pub tolerance: std::time::Duration,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct PathCollation {
+        pub pattern: PathPattern,
+        pub collation: crate::collation::Collation,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct PathComparator {
+        pub pattern: PathPattern,
+        pub comparator: crate::comparators::Comparator,
+    }
+
+    impl CompareOptions {
+        /// The absolute and relative tolerance for the number at `path`.
+        fn tolerances_at(&self, path: &str) -> (f64, f64) {
+            self.path_tolerances
+                .iter()
+                .rev()
+                .find(|tolerance| tolerance.pattern.matches(path))
+                .map_or(
+                    (self.absolute_tolerance, self.relative_tolerance),
+                    |tolerance| (tolerance.absolute, tolerance.relative),
+                )
+        }
+
+        /// The timestamp tolerance for the values at `path`, if they are compared as
+        /// instants.
+        fn timestamp_tolerance_at(&self, path: &str) -> Option<std::time::Duration> {
+            self.path_timestamps
+                .iter()
+                .rev()
+                .find(|timestamps| timestamps.pattern.matches(path))
+                .map(|timestamps| timestamps.tolerance)
+                .or(self.timestamp_tolerance)
+        }
+
+        /// The collation for the strings at `path`, if they are compared by one.
+        fn collation_at(&self, path: &str) -> Option<&crate::collation::Collation> {
+            self.path_collations
+                .iter()
+                .rev()
+                .find(|collation| collation.pattern.matches(path))
+                .map(|collation| &collation.collation)
+        }
+
+        /// The comparator for the values at `path`, if they are compared by one.
+        pub fn comparator_at(&self, path: &str) -> Option<&crate::comparators::Comparator> {
+            self.path_comparators
+                .iter()
+                .rev()
+                .find(|comparator| comparator.pattern.matches(path))
+                .map(|comparator| &comparator.comparator)
+        }
+    }
+
+    /// A number as sign, significant digits and power of ten, read from its JSON text so
+    /// that `1.50`, `15e-1` and `1.5` are equal without a lossy round trip through `f64`.
+    #[derive(Debug, PartialEq)]
+    pub(crate) struct Decimal {
+        negative: bool,
+        digits: String,
+        exponent: i64,
+    }
+
+    impl Decimal {
+        pub(crate) fn from_number(n: &serde_json::Number) -> Decimal {
+            Decimal::from_text(&n.to_string())
+        }
+
+        /// Reads a JSON number literal such as `10.50`, returning `None` for other text.
+        pub(crate) fn parse(text: &str) -> Option<Decimal> {
+            text.parse::<serde_json::Number>().ok()?;
+            Some(Decimal::from_text(text))
+        }
+
+        fn from_text(text: &str) -> Decimal {
+            let (negative, text) = match text.strip_prefix('-') {
+                Some(text) => (true, text),
+                None => (false, text),
+            };
+            let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+                Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().unwrap_or(0)),
+                None => (text, 0),
+            };
+            let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+            let digits = format!("{}{}", integer, fraction);
+            let digits = digits.trim_start_matches('0');
+            let significant = digits.trim_end_matches('0');
+            if significant.is_empty() {
+                // -0 equals 0
+                return Decimal {
+                    negative: false,
+                    digits: String::new(),
+                    exponent: 0,
+                };
+            }
+            Decimal {
+                negative,
+                digits: significant.to_string(),
+                exponent: exponent - fraction.len() as i64
+                    + (digits.len() - significant.len()) as i64,
+            }
+        }
+
+        /// Divides by `10^scale`, e.g. reads 1050 minor units at scale 2 as 10.5.
+        pub(crate) fn scaled(self, scale: i64) -> Decimal {
+            if self.digits.is_empty() {
+                return self;
+            }
+            Decimal {
+                exponent: self.exponent - scale,
+                ..self
+            }
+        }
+
+        fn is_integer(&self) -> bool {
+            self.exponent >= 0
+        }
+
+        fn to_i128(&self) -> Option<i128> {
+            if !self.is_integer() {
+                return None;
+            }
+            if self.digits.is_empty() {
+                return Some(0);
+            }
+            let magnitude = self
+                .digits
+                .parse::<i128>()
+                .ok()?
+                .checked_mul(10i128.checked_pow(u32::try_from(self.exponent).ok()?)?)?;
+            Some(if self.negative { -magnitude } else { magnitude })
+        }
+    }
+
     pub fn values_equal(a: &Value, b: &Value) -> bool {
+        values_equal_with(a, b, &CompareOptions::default())
+    }
+
+    pub fn values_equal_with(a: &Value, b: &Value, options: &CompareOptions) -> bool {
+        values_equal_at("", a, b, options)
+    }
+
+    /// Returns true if one value is a string holding a JSON scalar of the other's type,
+    /// which only `CompareOptions::coerce` compares as equal.
+    pub fn is_coerced(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::String(s), other) | (other, Value::String(s)) => {
+                parse_scalar(s, other).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses a string as a JSON number or boolean, whichever `like` is.
+    fn parse_scalar(s: &str, like: &Value) -> Option<Value> {
+        match like {
+            Value::Number(_) => s.parse::<serde_json::Number>().ok().map(Value::Number),
+            Value::Bool(_) => s.parse::<bool>().ok().map(Value::Bool),
+            _ => None,
+        }
+    }
+
+    /// The rule by which two values are equal.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum MatchRule {
+        /// Equal as written, or numbers equal within floating-point precision
+        Exact,
+        /// Numbers within the absolute or relative tolerance
+        Tolerance,
+        /// Instants within the timestamp tolerance
+        Timestamp,
+        /// A string holding a number or boolean and that scalar, with `coerce`
+        Coercion,
+        /// A string with placeholders and a value they could render to
+        Placeholder,
+        /// Strings the collation of a locale treats as equivalent
+        Collation,
+        /// Values the built-in comparator of this name reads as the same
+        Comparator(&'static str),
+        /// Values the comparator script called equal
+        Script,
+    }
+
+    /// Compares the values found at `path`, which selects the per-path tolerances.
+    pub fn values_equal_at(path: &str, a: &Value, b: &Value, options: &CompareOptions) -> bool {
+        match_rule_at(path, a, b, options).is_some()
+    }
+
+    /// Compares the values found at `path` like `values_equal_at`, returning the rule by
+    /// which they are equal, or `None` when they differ. Containers report the first
+    /// rule other than `Exact` that their members matched by.
+    pub fn match_rule_at(
+        path: &str,
+        a: &Value,
+        b: &Value,
+        options: &CompareOptions,
+    ) -> Option<MatchRule> {
+        let is_nan = |value: &Value| value.as_str() == Some(crate::input::NAN_MARKER);
+        if !options.nan_equals_nan && (is_nan(a) || is_nan(b)) {
+            return None;
+        }
+        if let Some(verdict) = options
+            .script
+            .as_ref()
+            .and_then(|script| script.verdict(path, a, b))
+        {
+            return (verdict != crate::script::Verdict::Different).then_some(MatchRule::Script);
+        }
+        if let Some(comparator) = options.comparator_at(path) {
+            if let Some(equal) = comparator.equal(a, b) {
+                return equal.then_some(MatchRule::Comparator(comparator.name()));
+            }
+        }
+        if let Some(tolerance) = options.timestamp_tolerance_at(path) {
+            if let Some(equal) = crate::timestamp::timestamps_equal(a, b, tolerance) {
+                return equal.then_some(MatchRule::Timestamp);
+            }
+        }
+        if options.coerce {
+            match (a, b) {
+                (Value::String(s), other @ (Value::Number(_) | Value::Bool(_))) => {
+                    return parse_scalar(s, other)
+                        .is_some_and(|a| values_equal_at(path, &a, other, options))
+                        .then_some(MatchRule::Coercion);
+                }
+                (other @ (Value::Number(_) | Value::Bool(_)), Value::String(s)) => {
+                    return parse_scalar(s, other)
+                        .is_some_and(|b| values_equal_at(path, other, &b, options))
+                        .then_some(MatchRule::Coercion);
+                }
+                _ => {}
+            }
+        }
+        if options.placeholders {
+            if let Some(equal) = crate::placeholder::placeholders_match(a, b) {
+                return equal.then_some(MatchRule::Placeholder);
+            }
+        }
+        // child paths are only needed to look up per-path tolerances
+        let child = |child: String| {
+            if options.path_tolerances.is_empty()
+                && options.path_timestamps.is_empty()
+                && options.path_collations.is_empty()
+                && options.path_comparators.is_empty()
+                && options.script.is_none()
+            {
+                String::new()
+            } else {
+                child
+            }
+        };
+
         match (a, b) {
+            (Value::String(s1), Value::String(s2)) if s1 != s2 => options
+                .collation_at(path)
+                .is_some_and(|collation| collation.equal(s1, s2))
+                .then_some(MatchRule::Collation),
             (Value::Number(n1), Value::Number(n2)) => {
-                if let (Some(f1), Some(f2)) = (n1.as_f64(), n2.as_f64()) {
-                    (f1 - f2).abs() < f64::EPSILON
+                let (d1, d2) = (Decimal::from_number(n1), Decimal::from_number(n2));
+                if n1 == n2 || d1 == d2 {
+                    return Some(MatchRule::Exact);
+                }
+                let (absolute, relative) = options.tolerances_at(path);
+                // integers are compared exactly, as large ones lose precision as floats, and
+                // with arbitrary precision so are all other numbers
+                let exact = absolute == 0.0 && relative == 0.0;
+                if exact
+                    && (cfg!(feature = "arbitrary_precision")
+                        || (d1.is_integer() && d2.is_integer()))
+                {
+                    return None;
+                }
+                let integer_delta = match (d1.to_i128(), d2.to_i128()) {
+                    (Some(i1), Some(i2)) => Some(i1.abs_diff(i2) as f64),
+                    _ => None,
+                };
+
+                let (f1, f2) = (n1.as_f64()?, n2.as_f64()?);
+                let delta = integer_delta.unwrap_or((f1 - f2).abs());
+                if exact {
+                    (delta < f64::EPSILON).then_some(MatchRule::Exact)
                 } else {
-                    n1 == n2
+                    (delta <= absolute || delta <= relative * f1.abs().max(f2.abs()))
+                        .then_some(MatchRule::Tolerance)
                 }
             }
-            _ => a == b,
+            // containers are compared element by element so tolerances apply inside them
+            (Value::Array(a1), Value::Array(a2)) if a1.len() == a2.len() => {
+                members_match(a1.iter().zip(a2).enumerate().map(|(i, (v1, v2))| {
+                    match_rule_at(&child(format!("{}[{}]", path, i)), v1, v2, options)
+                }))
+            }
+            (Value::Object(m1), Value::Object(m2)) if m1.len() == m2.len() => {
+                members_match(m1.iter().map(|(key, v1)| {
+                    let v2 = m2.get(key)?;
+                    match_rule_at(&child(child_path(path, key)), v1, v2, options)
+                }))
+            }
+            _ => (a == b).then_some(MatchRule::Exact),
         }
     }
 
+    /// The rule a container matched by, given those of its members; stops at the first
+    /// member that differs.
+    fn members_match(rules: impl Iterator<Item = Option<MatchRule>>) -> Option<MatchRule> {
+        let mut matched = MatchRule::Exact;
+        for rule in rules {
+            let rule = rule?;
+            if matched == MatchRule::Exact {
+                matched = rule;
+            }
+        }
+        Some(matched)
+    }
+
     pub fn is_parent_missing(missing_paths: &[String], path: &str) -> bool {
         missing_paths
             .iter()
@@ -169,15 +833,15 @@ pub mod json_diff {
     }
 
     pub fn same_type(a: &Value, b: &Value) -> bool {
-        match (a, b) {
-            (Value::Null, Value::Null) => true,
-            (Value::Bool(_), Value::Bool(_)) => true,
-            (Value::Number(_), Value::Number(_)) => true,
-            (Value::String(_), Value::String(_)) => true,
-            (Value::Array(_), Value::Array(_)) => true,
-            (Value::Object(_), Value::Object(_)) => true,
-            _ => false,
-        }
+        matches!(
+            (a, b),
+            (Value::Null, Value::Null)
+                | (Value::Bool(_), Value::Bool(_))
+                | (Value::Number(_), Value::Number(_))
+                | (Value::String(_), Value::String(_))
+                | (Value::Array(_), Value::Array(_))
+                | (Value::Object(_), Value::Object(_))
+        )
     }
 
     pub fn get_value_type(value: &Value) -> String {
@@ -191,7 +855,7 @@ pub mod json_diff {
         }
     }
 
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum PathPart {
         Key(String),
         Index(usize),
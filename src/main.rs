@@ -1,142 +1,3213 @@
+mod baseline;
+mod capabilities;
+mod checkpoint;
+mod config;
+mod doctor;
+mod manifest;
+mod preview;
+mod render;
+#[cfg(feature = "tui")]
+mod tui;
+
 use anyhow::{Context, Result};
-use clap::Parser;
+use baseline::Baseline;
+use checkpoint::Checkpoint;
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
+use json_diff_checker::analysis::{
+    delta_statistics, path_weight, similarity, value_distribution, weighted_similarity,
+    DeltaStatistics, SimilarityWeights, ValueDistribution,
+};
+use json_diff_checker::arrays::{
+    align_arrays, align_arrays_at, ArrayAlignment, ArrayElementDiff, ArrayKey, ArrayMatchOptions,
+    ArrayOrder,
+};
+use json_diff_checker::compare::{
+    DiffKind, DiffReport, Progress, ProgressCallback, ProgressReporter, ValueDifference,
+};
+use json_diff_checker::compression::{compress_for_path, read_decompressed};
+use json_diff_checker::extract::{extract, Extractor};
+use json_diff_checker::geojson::{normalize_geojson, GeoJsonOptions};
+use json_diff_checker::hash::{
+    canonical_hash, canonical_sha256, fingerprint, identical_top_level_paths,
+};
+use json_diff_checker::input::{
+    duplicate_keys, lossy_paths, parse_document_with, unmark_nan, InputFormat, ParseOptions,
+    YamlReferences,
+};
+use json_diff_checker::jest::{matchers_in_json, matchers_in_snapshots, to_rules as jest_to_rules};
 use json_diff_checker::json_diff::*;
+use json_diff_checker::jsonld::normalize_jsonld;
+use json_diff_checker::merge::three_way_merge;
+use json_diff_checker::metadata::{metadata_differences, FileMetadata, MetadataDifference};
+use json_diff_checker::moves::{detect_moves, MovedValue};
+use json_diff_checker::patch::*;
+use json_diff_checker::pattern::{remove_matching, PathPattern};
+use json_diff_checker::placeholder::{resolve_placeholders, PlaceholderMode};
+use json_diff_checker::proto::{normalize_proto_json, ProtoJsonOptions};
+use json_diff_checker::rules::{
+    ArrayLengthViolation, ReferenceViolation, Rules, UniquenessViolation, WeightRule,
+};
+use json_diff_checker::schema::{Schema, SchemaDocument, SchemaViolation};
+use json_diff_checker::script::{ComparatorScript, Verdict};
+use json_diff_checker::shape::infer_shape;
+use json_diff_checker::strings::{normalize_strings, StringNormalization};
+use json_diff_checker::suppress::{take_inline_ignores, SUPPRESSION_KEY};
+use json_diff_checker::template::{parse_matcher, parse_template};
+use json_diff_checker::text_diff::{text_diff, TextDiff};
+use json_diff_checker::timestamp::parse_duration;
+use manifest::Manifest;
+use render::{Category, OutputStyle, Phase};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(name = "json-diff-checker")]
 #[command(author = "Your Name")]
 #[command(version = "1.0")]
 #[command(about = "Recursively check JSON differences", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(args_override_self = true)]
+// options that need values checked also work with a --mode that checks them
+#[command(group(clap::ArgGroup::new("values").args(["check_values", "mode"]).multiple(true)))]
 struct Args {
-    /// Base JSON file to compare against
-    base_file: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// JSON files to compare with the base file
+    /// Base JSON file to compare against ("-" reads stdin, http(s):// URLs are fetched)
+    #[arg(required_unless_present = "capabilities")]
+    base_file: Option<PathBuf>,
+
+    /// JSON files to compare with the base file ("-" reads stdin, http(s):// URLs are fetched,
+    /// glob patterns like "locales/*.json" are expanded)
     compare_files: Vec<PathBuf>,
 
+    /// Manifest listing more compare files, each with its own root path, ignores and
+    /// array keys
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
+    /// Config file with default options [default: jsondiff.toml or .jsondiffrc in the
+    /// working directory]
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Ignore the config file in the working directory
+    #[arg(long, conflicts_with = "config")]
+    no_config: bool,
+
     /// Check values as well as structure
     #[arg(short = 'v', long)]
     check_values: bool,
 
-    /// Only check types, ignore value differences (requires -v)
-    #[arg(short = 't', long, requires = "check_values")]
-    type_only: bool,
+    /// Only check types, ignore value differences (requires -v)
+    #[arg(short = 't', long, requires = "values")]
+    type_only: bool,
+
+    /// Show only summary
+    #[arg(short = 's', long)]
+    summary: bool,
+
+    /// Browse the results in an interactive terminal UI instead of printing them: a tree
+    /// of the reported paths, filtered by kind and searchable (needs the "tui" feature)
+    #[arg(long, conflicts_with = "two_phase")]
+    tui: bool,
+
+    /// How results are written to stdout
+    #[arg(long, value_enum, default_value_t = OutputStyle::Terminal)]
+    output: OutputStyle,
+
+    /// Leave out the header, the overall summary and status messages on stdout, so it
+    /// holds only the results; the messages go to stderr
+    #[arg(short = 'q', long)]
+    quiet: bool,
+
+    /// Export results to JSON file
+    #[arg(short = 'e', long)]
+    export: Option<PathBuf>,
+
+    /// What --export writes
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json, requires = "export")]
+    export_format: ExportFormat,
+
+    /// Add the results to those already in the --export file instead of replacing them,
+    /// replacing only those of compare files checked again (JSON exports only)
+    #[arg(long, requires = "export")]
+    export_append: bool,
+
+    /// File of accepted differences, which are left out of the report and don't fail
+    /// the run
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Record every difference found in the --baseline file, replacing its contents
+    #[arg(long, requires = "baseline")]
+    update_baseline: bool,
+
+    /// Report the structure of every compare file first (missing, extra and moved paths,
+    /// changed types), then the value differences
+    #[arg(long)]
+    two_phase: bool,
+
+    /// Structural differences in all compare files --two-phase allows before the run
+    /// fails with status 1
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 0,
+        requires = "two_phase"
+    )]
+    structure_threshold: usize,
+
+    /// Value differences in all compare files --two-phase allows before the run fails
+    /// with status 1 [default: value differences don't fail the run]
+    #[arg(long, value_name = "COUNT", requires = "two_phase")]
+    value_threshold: Option<usize>,
+
+    /// Exit with status 1 when a compare file has these differences (repeatable or
+    /// comma-separated); errors exit with status 2
+    #[arg(long, value_enum, value_delimiter = ',')]
+    fail_on: Vec<FailOn>,
+
+    /// Exit with this status instead of 1 when a compare file has these differences,
+    /// e.g. `missing=3` (repeatable); the first one given that's found decides
+    #[arg(long, value_name = "KIND=STATUS", value_parser = parse_exit_code)]
+    exit_code: Vec<(FailOn, u8)>,
+
+    /// Record each compared file here, so a re-run of a stopped run skips the files
+    /// already compared and reports their recorded results; removed when the run ends
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// Include parent paths in missing items
+    #[arg(short = 'p', long)]
+    include_parents: bool,
+
+    /// Severity of values changing to or from null (requires -v)
+    #[arg(long, value_enum, default_value = "error", requires = "values")]
+    nullability_severity: Severity,
+
+    /// Treat "<<type>>" strings in the base file as type templates
+    #[arg(long)]
+    template: bool,
+
+    /// Treat "$any", "$string", "$number", "$integer", "$boolean", "$uuid", "$iso8601"
+    /// and "$regex:<pattern>" strings in the base file as matchers
+    #[arg(long)]
+    matchers: bool,
+
+    /// Rules (contract) file with assertions checked against each compare file
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// List the base file paths each --ignore, --select and rules file pattern matches,
+    /// without comparing
+    #[arg(long)]
+    preview_rules: bool,
+
+    /// Print the input formats, comparators, output formats and rules file keys this
+    /// build supports as JSON, and exit
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Rhai script whose compare(path, expected, actual) function decides whether values
+    /// are equal, different or ignored (requires -v)
+    #[arg(long, value_name = "SCRIPT")]
+    comparator: Option<PathBuf>,
+
+    /// JSON Schema the base and compare files are validated against before comparing
+    #[arg(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
+
+    /// Input format of the base and compare files (detected from the extension by default)
+    #[arg(short = 'f', long, value_enum)]
+    format: Option<InputFormat>,
+
+    /// Accept comments, trailing commas and other JSON5 syntax in JSON files
+    #[arg(long)]
+    lenient: bool,
+
+    /// Whether YAML aliases and merge keys are resolved before comparing, or compared as
+    /// written, with aliases as strings such as "*defaults"
+    #[arg(long, value_enum, default_value_t = YamlReferences::Resolve)]
+    yaml_references: YamlReferences,
+
+    /// Report object keys that appear more than once in the same object, of which
+    /// parsing keeps only the last value
+    #[arg(long, conflicts_with = "extract")]
+    strict: bool,
+
+    /// Compare the JSON embedded in inputs without a data file extension: "markdown"
+    /// code blocks, "html" script elements (or "html#<id>"), or "front-matter:<key>"
+    #[arg(long, value_name = "EXTRACTOR", value_parser = Extractor::parse)]
+    extract: Option<Extractor>,
+
+    /// Count the distinct values at a wildcard path in base vs compare (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    distribution: Vec<String>,
+
+    /// Show a unified diff of the pretty-printed values at matching paths instead of
+    /// listing their differences path by path (repeatable, requires -v)
+    #[arg(long, value_name = "PATTERN", requires = "values")]
+    text_diff: Vec<String>,
+
+    /// Show the expected and actual values of each different value pretty-printed in two
+    /// aligned columns, wrapped to the terminal width (requires -v and --output terminal)
+    #[arg(long, requires = "values")]
+    side_by_side: bool,
+
+    /// List one line per category and prefix of N path segments, counting the
+    /// differences below it, instead of every path (exports keep every path)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    group_by_prefix: Option<u64>,
+
+    /// Print at most N differences of each category per compare file, followed by how
+    /// many more there are
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_diffs: Option<u64>,
+
+    /// Also leave the differences past --max-diffs out of the results and exports; the
+    /// counts and statistics still include them
+    #[arg(long, requires = "max_diffs")]
+    truncate_results: bool,
+
+    /// Report only differences of these kinds (repeatable or comma-separated); the
+    /// others are left out of the results and statistics and don't make a file fail,
+    /// and no similarity score is computed
+    #[arg(long, value_enum, value_delimiter = ',')]
+    only: Vec<DiffKind>,
+
+    /// Compare only a deterministic sample of N elements in arrays longer than N
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+
+    /// Skip detailed diffing of documents and top-level subtrees with identical hashes
+    #[arg(long)]
+    quick: bool,
+
+    /// HTTP header sent when fetching URL inputs, as "Name: value" (repeatable)
+    #[arg(short = 'H', long, value_name = "HEADER")]
+    header: Vec<String>,
+
+    /// Print a canonical SHA-256 of each input, independent of formatting and key order
+    #[arg(long)]
+    print_hashes: bool,
+
+    /// Also report differences in size, modification time and permissions between the
+    /// base file and each compare file on disk
+    #[arg(long)]
+    compare_metadata: bool,
+
+    /// Accept NaN and Infinity literals and read them as "NaN", "Infinity" and
+    /// "-Infinity"
+    #[arg(long)]
+    non_finite: bool,
+
+    /// Replace invalid UTF-8 and lone UTF-16 surrogates with U+FFFD instead of failing,
+    /// and report the affected paths
+    #[arg(long)]
+    lossy: bool,
+
+    /// Treat NaN as different from every value, including NaN (requires -v)
+    #[arg(long, requires = "values")]
+    nan_unequal: bool,
+
+    /// Numbers differing by at most this much are equal (requires -v)
+    #[arg(long, value_name = "TOLERANCE", requires = "values")]
+    abs_tol: Option<f64>,
+
+    /// Numbers differing by at most this fraction of the larger one are equal (requires -v)
+    #[arg(long, value_name = "TOLERANCE", requires = "values")]
+    rel_tol: Option<f64>,
+
+    /// Compare ISO 8601 strings with each other and with epoch numbers (seconds, or
+    /// milliseconds for large values) as instants (requires -v)
+    #[arg(long, requires = "values")]
+    timestamps: bool,
+
+    /// Instants at most this far apart are equal, e.g. 500ms, 1s or 2m (with --timestamps)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "timestamps")]
+    timestamp_tolerance: Option<Duration>,
+
+    /// How array elements are paired: by index, with "ignore" as a multiset, or with "lcs"
+    /// along a longest common subsequence; both report missing and extra elements
+    /// (requires -v)
+    #[arg(long, value_enum, default_value_t = ArrayOrder::Index, requires = "values")]
+    array_order: ArrayOrder,
+
+    /// Pair the elements of the arrays at a path by a field instead of by index, as
+    /// "path=field", e.g. "items=id" or "orders[*].lines=sku" (repeatable, requires -v)
+    #[arg(long, value_name = "PATH=FIELD", value_parser = ArrayKey::parse, requires = "values")]
+    array_key: Vec<ArrayKey>,
+
+    /// Compare strings holding a number or boolean, such as "42" or "true", with numbers
+    /// and booleans as the values they hold (requires -v)
+    #[arg(long, requires = "values")]
+    coerce: bool,
+
+    /// Compare templated files with rendered ones: "match" treats ${VAR} and {{ var }}
+    /// placeholders as equal to any value (requires -v), "resolve" fills them in from the
+    /// environment
+    #[arg(long, value_enum, value_name = "MODE")]
+    placeholders: Option<PlaceholderMode>,
+
+    /// Show a progress bar on stderr while comparing, when stderr is a terminal
+    #[arg(long)]
+    progress: bool,
+
+    /// Stop comparing after this long, e.g. 10s or 2m, and report the differences found
+    /// so far as partial, with the percentage of paths covered
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    deadline: Option<Duration>,
+
+    /// Report how far numeric values drifted: max and mean absolute and relative deltas
+    /// (requires -v)
+    #[arg(long, requires = "values")]
+    delta_stats: bool,
+
+    /// Preset that tunes the comparison for a kind of data
+    #[arg(long, value_enum)]
+    mode: Option<Mode>,
+
+    /// Ignore paths matching a pattern like "metadata.*" or "items[*].updatedAt",
+    /// including everything below them (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    ignore: Vec<String>,
+
+    /// Only compare the subtrees selected by a JSONPath such as '$.spec.containers[*]'
+    /// (repeatable)
+    #[arg(long, value_name = "JSONPATH")]
+    select: Vec<String>,
+
+    /// Only compare the paths listed in FILE, one per line, without walking the
+    /// documents; lines starting with "/" are JSON Pointers and "#" starts a comment
+    #[arg(long, value_name = "FILE", conflicts_with = "select")]
+    paths_file: Option<PathBuf>,
+
+    /// Normalize string values before comparing: trim, case and/or unicode-nfc,
+    /// comma-separated (e.g. trim,case)
+    #[arg(long, value_enum, value_name = "NORMALIZATIONS", value_delimiter = ',')]
+    normalize_strings: Vec<StringNormalization>,
+
+    /// Match object keys case-insensitively, e.g. "Content-Type" with "content-type";
+    /// paths are reported with the base file's spelling
+    #[arg(long)]
+    ignore_key_case: bool,
+
+    /// How much missing, extra and differing paths count against the similarity score,
+    /// from 0 to 1 each, e.g. "extra=0,different=0.5" [default: 1 each]
+    #[arg(long, value_name = "WEIGHTS", value_parser = SimilarityWeights::parse)]
+    similarity_weights: Option<SimilarityWeights>,
+
+    /// Include the compare value in the fingerprints of changed values, so a value that
+    /// changes again gets a new one [default: file, kind and path only]
+    #[arg(long)]
+    fingerprint_values: bool,
+
+    /// Exit with status 1 when a compare file is less similar than this percentage, by
+    /// its weighted similarity when the rules file has path weights
+    #[arg(long, value_name = "PERCENT")]
+    min_similarity: Option<f64>,
+
+    /// Report a missing path whose value appears unchanged under a new key or parent,
+    /// e.g. "user.name" → "user.fullName", as renamed or moved instead of missing
+    #[arg(long)]
+    detect_moves: bool,
+
+    /// Ignore every occurrence of an object key, e.g. "timestamp", at any depth (repeatable)
+    #[arg(long, value_name = "KEY")]
+    ignore_key: Vec<String>,
+
+    /// Apply the ignores declared by `__jsondiff` members in the base file, e.g.
+    /// `"__jsondiff": {"ignore": ["legacyField"]}`; the members aren't compared
+    #[arg(long)]
+    inline_ignores: bool,
+
+    /// Decimal places GeoJSON coordinates are rounded to in --mode geojson [default: 7]
+    #[arg(long, value_name = "DECIMALS")]
+    coordinate_precision: Option<u32>,
+
+    /// JSON file mapping protobuf enum value names to numbers, e.g. {"STATUS_ACTIVE": 1},
+    /// for --mode proto-json
+    #[arg(long, value_name = "FILE")]
+    proto_enums: Option<PathBuf>,
+
+    /// How paths are written in the output and exports
+    #[arg(long, value_enum, default_value_t = PathStyle::Dotted)]
+    path_style: PathStyle,
+
+    /// Limit on the size of each input after decompression, e.g. 500M (suffixes K, M, G)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_file_size: Option<u64>,
+
+    /// Limit on the number of values (objects, arrays and scalars) in each input
+    #[arg(long, value_name = "COUNT")]
+    max_nodes: Option<usize>,
+
+    /// What to do with inputs over --max-file-size or --max-nodes
+    #[arg(long, value_enum, default_value_t = LimitAction::Abort)]
+    on_limit: LimitAction,
+
+    #[arg(skip)]
+    proto_options: ProtoJsonOptions,
+
+    /// Path of the value in each compare file that is compared with the base document,
+    /// set by manifest entries
+    #[arg(skip)]
+    compare_root: Option<String>,
+}
+
+impl Args {
+    fn base_file(&self) -> &PathBuf {
+        self.base_file
+            .as_ref()
+            .expect("base file is required without a subcommand")
+    }
+
+    fn input_format(&self, path: &Path) -> Option<InputFormat> {
+        match self.format {
+            None if self.lenient && InputFormat::from_path(path) == InputFormat::Json => {
+                Some(InputFormat::Json5)
+            }
+            format => format,
+        }
+    }
+
+    /// Turns on the flags implied by `--mode` and loads the files it needs.
+    fn apply_mode(&mut self) -> Result<()> {
+        if let Some(path) = &self.proto_enums {
+            self.proto_options.enum_values =
+                serde_json::from_value(load_json(path, &LoadOptions::default())?)
+                    .with_context(|| format!("Invalid protobuf enum file: {:?}", path))?;
+        }
+
+        match self.mode {
+            Some(Mode::NumericDataset) => {
+                self.check_values = true;
+                self.non_finite = true;
+                self.delta_stats = true;
+            }
+            Some(Mode::Geojson) | Some(Mode::ProtoJson) | Some(Mode::Schema) => {
+                self.check_values = true
+            }
+            Some(Mode::Jsonld) | None => {}
+        }
+        Ok(())
+    }
+
+    /// Rewrites a loaded document into the canonical form of the selected `--mode`.
+    /// Returns the paths of the optional keys of the schema `--mode schema` infers.
+    fn normalize(&self, json: &mut Value) -> BTreeSet<String> {
+        let mut optional_paths = BTreeSet::new();
+        if self.placeholders == Some(PlaceholderMode::Resolve) {
+            resolve_placeholders(json, &|name| std::env::var(name).ok());
+        }
+        match self.mode {
+            Some(Mode::Geojson) => {
+                let mut options = GeoJsonOptions::default();
+                if let Some(precision) = self.coordinate_precision {
+                    options.precision = precision;
+                }
+                normalize_geojson(json, &options);
+            }
+            Some(Mode::Jsonld) => normalize_jsonld(json),
+            Some(Mode::ProtoJson) => normalize_proto_json(json, &self.proto_options),
+            Some(Mode::Schema) => {
+                let shape = infer_shape(json);
+                *json = shape.schema;
+                optional_paths = shape.optional_paths;
+            }
+            _ => {}
+        }
+        normalize_strings(json, &self.normalize_strings);
+        optional_paths
+    }
+
+    /// Normalizes a loaded document and, with `--paths-file`, reduces it to the values at
+    /// the listed paths, running `align` just before. The paths refer to the document a
+    /// `--mode` rewrites, so only then is the whole document normalized. Returns what
+    /// `normalize` does.
+    fn normalize_listed(
+        &self,
+        json: &mut Value,
+        listed: Option<&[String]>,
+        align: impl FnOnce(&mut Value),
+    ) -> BTreeSet<String> {
+        let Some(listed) = listed else {
+            let optional_paths = self.normalize(json);
+            align(json);
+            return optional_paths;
+        };
+        let rewrites = !matches!(self.mode, None | Some(Mode::NumericDataset));
+        let mut optional_paths = BTreeSet::new();
+        if rewrites {
+            optional_paths = self.normalize(json);
+        }
+        align(json);
+        *json = sparse_copy(json, listed);
+        if !rewrites {
+            optional_paths = self.normalize(json);
+        }
+        optional_paths
+    }
+
+    fn load_options(&self, path: &Path) -> LoadOptions {
+        LoadOptions {
+            format: self.input_format(path),
+            // data files, such as a fixture compared with a README's code block, are
+            // loaded as usual
+            extract: self
+                .extract
+                .clone()
+                .filter(|_| InputFormat::from_extension(path).is_none()),
+            headers: self.header.clone(),
+            parse: ParseOptions {
+                non_finite: self.non_finite,
+                lossy: self.lossy,
+                yaml_references: self.yaml_references,
+                mark_nan: self.nan_unequal,
+            },
+            // with --on-limit hash, oversized inputs are still read to be hashed
+            max_file_size: self
+                .max_file_size
+                .filter(|_| self.on_limit == LimitAction::Abort),
+        }
+    }
+
+    /// The options with the ignores of a rules file added to `--ignore`.
+    fn with_rules_ignores(&self, rules: &Rules) -> Args {
+        let mut args = self.clone();
+        args.ignore.extend(rules.ignore.iter().cloned());
+        args
+    }
+
+    /// The arguments with the base file's inline ignores; copies of its `__jsondiff`
+    /// members in compare files are dropped too.
+    fn with_inline_ignores(&self, ignores: Vec<String>) -> Args {
+        let mut args = self.clone();
+        args.ignore.extend(ignores);
+        if args.inline_ignores {
+            args.ignore_key.push(SUPPRESSION_KEY.to_string());
+        }
+        args
+    }
+
+    fn ignore_patterns(&self) -> Vec<PathPattern> {
+        self.ignore
+            .iter()
+            .map(|pattern| PathPattern::parse(pattern))
+            .collect()
+    }
+
+    fn selectors(&self) -> Result<Vec<PathPattern>> {
+        self.select
+            .iter()
+            .map(|expression| PathPattern::parse_jsonpath(expression))
+            .collect()
+    }
+
+    fn array_match_options(&self, rules: &Rules) -> ArrayMatchOptions {
+        ArrayMatchOptions {
+            order: self.array_order,
+            path_orders: rules
+                .array_order
+                .iter()
+                .map(|rule| rule.to_path_array_order())
+                .collect(),
+            keys: self.array_key.clone(),
+        }
+    }
+
+    fn compare_options(
+        &self,
+        rules: &Rules,
+        script: Option<&Arc<ComparatorScript>>,
+    ) -> Result<CompareOptions> {
+        Ok(CompareOptions {
+            nan_equals_nan: !self.nan_unequal,
+            relative_tolerance: self.rel_tol.unwrap_or(match self.mode {
+                Some(Mode::NumericDataset) => NUMERIC_DATASET_RELATIVE_TOLERANCE,
+                _ => 0.0,
+            }),
+            absolute_tolerance: self.abs_tol.unwrap_or(0.0),
+            path_tolerances: rules
+                .tolerances
+                .iter()
+                .map(|rule| rule.to_path_tolerance())
+                .collect(),
+            timestamp_tolerance: self
+                .timestamps
+                .then(|| self.timestamp_tolerance.unwrap_or_default()),
+            path_timestamps: rules
+                .timestamps
+                .iter()
+                .map(|rule| rule.to_path_timestamps())
+                .collect(),
+            coerce: self.coerce,
+            placeholders: self.placeholders == Some(PlaceholderMode::Match),
+            path_collations: rules
+                .collate
+                .iter()
+                .map(|rule| rule.to_path_collation())
+                .collect::<Result<_>>()?,
+            path_comparators: rules
+                .comparators
+                .iter()
+                .map(|rule| rule.to_path_comparator())
+                .collect(),
+            script: script.cloned(),
+        })
+    }
+}
+
+/// How an input is read: its format, when not detected from the path, HTTP headers for
+/// URL inputs, parsing options and the size files are checked against before reading.
+#[derive(Debug, Default)]
+struct LoadOptions {
+    format: Option<InputFormat>,
+    extract: Option<Extractor>,
+    headers: Vec<String>,
+    parse: ParseOptions,
+    max_file_size: Option<u64>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Apply an exported diff or an RFC 6902 JSON Patch to a base file
+    Apply(ApplyArgs),
+    /// Three-way merge two JSON files against their common ancestor
+    Merge(MergeArgs),
+    /// Check the config file, rules file, baseline and path patterns without comparing
+    Doctor(DoctorArgs),
+    /// Convert jest snapshot property matchers, such as expect.any(Number), into a rules
+    /// file
+    ImportJest(ImportJestArgs),
+    /// Merge exported results, e.g. of sharded CI jobs, into one export and summarize
+    /// them with recomputed overall statistics
+    Aggregate(AggregateArgs),
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct AggregateArgs {
+    /// Exported results (from --export) to merge; glob patterns are expanded
+    #[arg(required = true)]
+    exports: Vec<PathBuf>,
+
+    /// Write the merged results here, as one export
+    #[arg(short = 'e', long)]
+    export: Option<PathBuf>,
+
+    /// How the summary is written to stdout
+    #[arg(long, value_enum, default_value_t = OutputStyle::Terminal)]
+    output: OutputStyle,
+
+    /// Exit with status 1 when a merged result has these differences (repeatable or
+    /// comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    fail_on: Vec<FailOn>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ImportJestArgs {
+    /// A jest .snap file, or a JSON property matcher object with matchers as strings,
+    /// e.g. {"id": "expect.any(Number)"}
+    input: PathBuf,
+
+    /// Write the rules file here instead of stdout
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Only import the snapshot with this name, e.g. "user profile 1" [default: all]
+    #[arg(long, value_name = "NAME")]
+    snapshot: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ApplyArgs {
+    /// Base JSON file to apply the changes to
+    base_file: PathBuf,
+
+    /// Exported results (from --export) or RFC 6902 JSON Patch file
+    patch_file: PathBuf,
+
+    /// Write the reconstructed document to a file instead of stdout
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+
+    /// Compare file to take the diff from when the export contains several
+    #[arg(long)]
+    compare_file: Option<String>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct DoctorArgs {
+    /// Sample input to check that the path patterns match something in
+    sample: Option<PathBuf>,
+
+    /// Config file to check [default: jsondiff.toml or .jsondiffrc in the working
+    /// directory]
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Rules file to check [default: the config file's]
+    #[arg(long, value_name = "FILE")]
+    rules: Option<PathBuf>,
+
+    /// Baseline to check [default: the config file's]
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct MergeArgs {
+    /// Common ancestor of both versions
+    ancestor: PathBuf,
+
+    /// Our version of the file
+    ours: PathBuf,
+
+    /// Their version of the file
+    theirs: PathBuf,
+
+    /// Write the merged document to a file instead of stdout
+    #[arg(short = 'o', long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Float-heavy simulation or ML output: value checks with a relative tolerance,
+    /// NaN and Infinity support and numeric delta statistics
+    NumericDataset,
+    /// GeoJSON: coordinates rounded to --coordinate-precision, polygon rings in canonical
+    /// orientation and features paired by id
+    Geojson,
+    /// JSON-LD: keys and types expanded to full IRIs through the inline @context
+    Jsonld,
+    /// Protobuf JSON: int64 strings as numbers, enum names as numbers (with
+    /// --proto-enums) and fields with default values treated as omitted
+    ProtoJson,
+    /// Structural schemas: each document is reduced to its types, optional keys and the
+    /// merged shape of its array elements, which are compared instead of the values
+    Schema,
+}
+
+/// What `--export` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// The comparison results, for scripts and `apply`
+    Json,
+    /// A standalone HTML report with a tab per compare file, collapsible sections, and
+    /// search and category filters
+    Html,
+    /// A Markdown report with a section per compare file and its differences as tables,
+    /// for pull request descriptions and comments
+    Markdown,
+    /// JUnit XML with a test case per compare file, failing with its differences, for
+    /// the test report views of CI servers
+    Junit,
+    /// A SARIF 2.1.0 log with a result per difference, located in its compare file, for
+    /// GitHub code scanning and other SARIF consumers
+    Sarif,
+    /// A CSV row per difference with its compare file, path, kind, values and types,
+    /// for spreadsheets
+    Csv,
+    /// Reviewdog diagnostics (rdjson), one per difference, for posting them as review
+    /// comments with reviewdog
+    Rdjson,
+    /// Checkstyle XML with an error per difference in its compare file, for CI plugins
+    /// and editors that read Checkstyle reports
+    Checkstyle,
+    /// A Graphviz graph of the document tree with the differing nodes colored by
+    /// category, to render with `dot`
+    Dot,
+}
+
+/// Differences that make the run fail with `--fail-on`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    Missing,
+    Different,
+    TypeMismatch,
+    /// File attributes that differ, with `--compare-metadata`
+    Metadata,
+    /// Anything that makes a compare file fail, as in the report's status
+    Any,
+}
+
+impl FailOn {
+    fn matches(self, result: &ComparisonResult) -> bool {
+        match self {
+            FailOn::Missing => !result.missing_paths.is_empty(),
+            FailOn::Different => !result.different_values.is_empty(),
+            FailOn::TypeMismatch => !result.type_mismatches.is_empty(),
+            FailOn::Metadata => !result.metadata_differences.is_empty(),
+            FailOn::Any => result.has_failures(),
+        }
+    }
+}
+
+/// Exit status of a run whose `--fail-on` differences were found, or of a merge with
+/// conflicts.
+const EXIT_DIFFERENCES: u8 = 1;
+/// Exit status of a run that stopped with an error.
+const EXIT_ERROR: u8 = 2;
+
+/// Parses an `--exit-code` mapping, `<kind>=<status>` such as `type-mismatch=4`.
+fn parse_exit_code(s: &str) -> Result<(FailOn, u8), String> {
+    let invalid = || {
+        format!(
+            "invalid exit code {:?}, expected <kind>=<status> such as missing=3",
+            s
+        )
+    };
+    let (kind, code) = s.split_once('=').ok_or_else(invalid)?;
+    let kind = FailOn::from_str(kind.trim(), true).map_err(|_| invalid())?;
+    match code.trim().parse::<u8>() {
+        Ok(0) => Err(format!("exit code {:?}: status 0 means success", s)),
+        Ok(EXIT_ERROR) => Err(format!(
+            "exit code {:?}: status {} is reserved for errors",
+            s, EXIT_ERROR
+        )),
+        Ok(code) => Ok((kind, code)),
+        Err(_) => Err(invalid()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LimitAction {
+    /// Stop with an error naming the input and the limit
+    Abort,
+    /// Compare the input by its canonical hash only, skipping the path-by-path comparison
+    Hash,
+}
+
+/// Relative tolerance of `--mode numeric-dataset`, about the precision lost when
+/// floats pass through text and back in a different tool.
+const NUMERIC_DATASET_RELATIVE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PathStyle {
+    /// config.servers[0]["a.b"]
+    #[default]
+    Dotted,
+    /// JSON Pointer (RFC 6901): /config/servers/0/a.b
+    Pointer,
+}
+
+impl PathStyle {
+    /// A path written in this style in dotted form, e.g. to match it whatever the style
+    /// it was reported in.
+    fn to_dotted(self, path: &str) -> String {
+        match self {
+            PathStyle::Dotted => path.to_string(),
+            PathStyle::Pointer => pointer_to_path(path).unwrap_or_else(|_| path.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    /// Reported and counted as a difference
+    Error,
+    /// Reported, but the file still counts as a match
+    Warning,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ComparisonResult {
+    base_file: String,
+    compare_file: String,
+    /// Other names the compare file was passed as, e.g. by overlapping glob patterns;
+    /// it's compared once
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    identical: bool,
+    #[serde(default)]
+    base_hash: Option<String>,
+    #[serde(default)]
+    compare_hash: Option<String>,
+    /// File attributes that differ, with `--compare-metadata`
+    #[serde(default)]
+    metadata_differences: Vec<MetadataDifference>,
+    #[serde(default)]
+    path_style: PathStyle,
+    /// Whether the differences hold the compare values (`-v` without `-t`), which
+    /// `apply` needs
+    #[serde(default)]
+    values_checked: bool,
+    #[serde(default)]
+    base_lossy_paths: Vec<String>,
+    #[serde(default)]
+    lossy_paths: Vec<String>,
+    /// Paths of repeated object keys in the base file, with `--strict`
+    #[serde(default)]
+    base_duplicate_keys: Vec<String>,
+    /// Paths of repeated object keys in the compare file, with `--strict`
+    #[serde(default)]
+    duplicate_keys: Vec<String>,
+    /// Why the documents were only compared by canonical hash (`--on-limit hash`)
+    #[serde(default)]
+    limit_exceeded: Option<String>,
+    /// Percentage of base paths checked when `--deadline` stopped the comparison early
+    #[serde(default)]
+    partial_coverage: Option<f64>,
+    /// Differences accepted by `--baseline` and left out of the result
+    #[serde(default)]
+    baselined: usize,
+    /// Outermost paths only the compare file has, listed with `--two-phase`
+    #[serde(default)]
+    extra_paths: Vec<String>,
+    /// The outermost paths only the compare file has with their values, recorded with
+    /// `-v` for `apply`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    added_values: Vec<AddedValue>,
+    /// Whether `--only`, `--ignore`, `--ignore-key`, `--select`, `--paths-file`,
+    /// `--sample` or `--baseline` left differences out, so `apply` can't replay it
+    #[serde(default)]
+    filtered: bool,
+    /// Violations of `--schema` in the base and the compare file
+    #[serde(default)]
+    schema_violations: Vec<SchemaViolation>,
+    missing_paths: Vec<String>,
+    /// Missing paths whose value was found at another path with `--detect-moves`
+    #[serde(default)]
+    moved_values: Vec<MovedValue>,
+    /// Order-insensitive and keyed arrays with elements found in only one document
+    #[serde(default)]
+    array_elements: Vec<ArrayElementDiff>,
+    different_values: Vec<ValueDifference>,
+    type_mismatches: Vec<TypeMismatch>,
+    #[serde(default)]
+    nullability_changes: Vec<NullabilityChange>,
+    /// Keys that became optional or required in the schemas inferred with `--mode schema`
+    #[serde(default)]
+    optionality_changes: Vec<OptionalityChange>,
+    #[serde(default)]
+    template_violations: Vec<TemplateViolation>,
+    #[serde(default)]
+    text_diffs: Vec<TextDiff>,
+    #[serde(default)]
+    array_length_violations: Vec<ArrayLengthViolation>,
+    #[serde(default)]
+    uniqueness_violations: Vec<UniquenessViolation>,
+    #[serde(default)]
+    reference_violations: Vec<ReferenceViolation>,
+    #[serde(default)]
+    distributions: Vec<ValueDistribution>,
+    #[serde(default)]
+    delta_statistics: Option<DeltaStatistics>,
+    #[serde(default)]
+    sampled_arrays: Vec<SampledArray>,
+    statistics: Statistics,
+    /// Stable identifiers of the reported differences
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fingerprints: Vec<DiffFingerprint>,
+    /// How many differences of each kind `--truncate-results` left out
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    truncated: BTreeMap<String, usize>,
+    /// What the comparison relaxed, so a result without differences isn't taken for
+    /// more than was checked
+    #[serde(default)]
+    warnings: Vec<ComparisonWarning>,
+}
+
+/// A value at a path only the compare file has.
+#[derive(Debug, Serialize, Deserialize)]
+struct AddedValue {
+    path: String,
+    value: Value,
+}
+
+/// The fingerprint of a reported difference, the same in every run that reports it.
+#[derive(Debug, Serialize, Deserialize)]
+struct DiffFingerprint {
+    kind: String,
+    path: String,
+    id: String,
+}
+
+impl ComparisonResult {
+    fn has_failures(&self) -> bool {
+        !self.missing_paths.is_empty()
+            || !self.extra_paths.is_empty()
+            || !self.schema_violations.is_empty()
+            || !self.duplicate_keys.is_empty()
+            || !self.moved_values.is_empty()
+            || !self.array_elements.is_empty()
+            || !self.different_values.is_empty()
+            || !self.type_mismatches.is_empty()
+            || !self.template_violations.is_empty()
+            || !self.text_diffs.is_empty()
+            || !self.array_length_violations.is_empty()
+            || !self.uniqueness_violations.is_empty()
+            || !self.reference_violations.is_empty()
+            || !self.optionality_changes.is_empty()
+            || (self.limit_exceeded.is_some() && !self.identical)
+            || self.partial_coverage.is_some()
+            || self
+                .nullability_changes
+                .iter()
+                .any(|change| change.severity == Severity::Error)
+    }
+
+    fn is_perfect_match(&self) -> bool {
+        !self.has_failures() && self.nullability_changes.is_empty()
+    }
+
+    /// The paths of the differences `retain_diffs` visits, with their categories;
+    /// uniqueness violations are left out, as they're at a rule's pattern.
+    fn diff_paths(&self) -> Vec<(Category, &str)> {
+        fn paths(category: Category, paths: &[String]) -> impl Iterator<Item = (Category, &str)> {
+            paths.iter().map(move |path| (category, path.as_str()))
+        }
+        self.schema_violations
+            .iter()
+            .map(|v| (Category::Schema, v.path.as_str()))
+            .chain(paths(Category::DuplicateKey, &self.duplicate_keys))
+            .chain(paths(Category::Missing, &self.missing_paths))
+            .chain(paths(Category::Extra, &self.extra_paths))
+            .chain(
+                self.moved_values
+                    .iter()
+                    .map(|m| (Category::Moved, m.from.as_str())),
+            )
+            .chain(
+                self.array_elements
+                    .iter()
+                    .map(|a| (Category::Unmatched, a.path.as_str())),
+            )
+            .chain(
+                self.different_values
+                    .iter()
+                    .map(|d| (Category::Different, d.path.as_str())),
+            )
+            .chain(
+                self.type_mismatches
+                    .iter()
+                    .map(|m| (Category::TypeMismatch, m.path.as_str())),
+            )
+            .chain(
+                self.nullability_changes
+                    .iter()
+                    .map(|c| (Category::Nullability, c.path.as_str())),
+            )
+            .chain(
+                self.optionality_changes
+                    .iter()
+                    .map(|c| (Category::Optionality, c.path.as_str())),
+            )
+            .chain(
+                self.template_violations
+                    .iter()
+                    .map(|v| (Category::Template, v.path.as_str())),
+            )
+            .chain(
+                self.text_diffs
+                    .iter()
+                    .map(|d| (Category::TextDiff, d.path.as_str())),
+            )
+            .chain(
+                self.array_length_violations
+                    .iter()
+                    .map(|v| (Category::ArrayLength, v.path.as_str())),
+            )
+            .chain(
+                self.reference_violations
+                    .iter()
+                    .map(|v| (Category::Reference, v.path.as_str())),
+            )
+            .collect()
+    }
+
+    /// Keeps the differences that make the file fail for which `keep` returns true, given
+    /// their category, path and, for changed values, the compare value. Returns how many
+    /// were removed.
+    fn retain_diffs(
+        &mut self,
+        mut keep: impl FnMut(Category, &str, Option<&Value>) -> bool,
+    ) -> usize {
+        let mut removed = 0;
+        let mut keep = |category, path: &str, value: Option<&Value>| {
+            let kept = keep(category, path, value);
+            removed += usize::from(!kept);
+            kept
+        };
+        self.schema_violations
+            .retain(|v| keep(Category::Schema, &v.path, None));
+        self.duplicate_keys
+            .retain(|path| keep(Category::DuplicateKey, path, None));
+        self.missing_paths
+            .retain(|path| keep(Category::Missing, path, None));
+        self.extra_paths
+            .retain(|path| keep(Category::Extra, path, None));
+        self.moved_values
+            .retain(|m| keep(Category::Moved, &m.from, None));
+        self.array_elements
+            .retain(|a| keep(Category::Unmatched, &a.path, None));
+        self.different_values
+            .retain(|d| keep(Category::Different, &d.path, Some(&d.compare_value)));
+        self.type_mismatches
+            .retain(|m| keep(Category::TypeMismatch, &m.path, Some(&m.compare_value)));
+        self.nullability_changes
+            .retain(|c| keep(Category::Nullability, &c.path, None));
+        self.optionality_changes
+            .retain(|c| keep(Category::Optionality, &c.path, None));
+        self.template_violations
+            .retain(|v| keep(Category::Template, &v.path, None));
+        self.text_diffs
+            .retain(|d| keep(Category::TextDiff, &d.path, None));
+        self.array_length_violations
+            .retain(|v| keep(Category::ArrayLength, &v.path, None));
+        // values that must be unique are identified by their rule and the repeated value
+        self.uniqueness_violations
+            .retain(|v| keep(Category::Duplicate, &v.rule, Some(&v.value)));
+        self.reference_violations
+            .retain(|v| keep(Category::Reference, &v.path, None));
+        removed
+    }
+
+    /// The missing paths, value differences and type mismatches as a library report,
+    /// taken out of the result; `put_diff_report` puts them back.
+    fn take_diff_report(&mut self) -> DiffReport {
+        DiffReport {
+            root: String::new(),
+            total_paths_checked: self.statistics.total_paths_checked,
+            missing_paths: std::mem::take(&mut self.missing_paths),
+            different_values: std::mem::take(&mut self.different_values),
+            type_mismatches: std::mem::take(&mut self.type_mismatches),
+            cancelled: self.partial_coverage.is_some(),
+        }
+    }
+
+    fn put_diff_report(&mut self, report: DiffReport) {
+        self.missing_paths = report.missing_paths;
+        self.different_values = report.different_values;
+        self.type_mismatches = report.type_mismatches;
+    }
+
+    /// Keeps only the differences of the given kinds, as `DiffReport::without_kinds`
+    /// leaves them; differences of the kinds a `DiffReport` doesn't have are dropped.
+    fn keep_only(&mut self, kinds: &[DiffKind]) {
+        let others: Vec<DiffKind> = DiffKind::value_variants()
+            .iter()
+            .copied()
+            .filter(|kind| !kinds.contains(kind))
+            .collect();
+        let report = self.take_diff_report().without_kinds(&others);
+        self.put_diff_report(report);
+        self.retain_diffs(|category, _, _| {
+            matches!(
+                category,
+                Category::Missing | Category::Different | Category::TypeMismatch
+            )
+        });
+        self.recount();
+        self.filtered = true;
+        // extra paths aren't among the kinds, and a score over some kinds would mislead
+        self.statistics.extra_path_count = 0;
+        self.statistics.similarity = None;
+        self.statistics.weighted_similarity = None;
+    }
+
+    /// Sets the difference counts of the statistics to the differences left in the
+    /// result.
+    fn recount(&mut self) {
+        let statistics = &mut self.statistics;
+        statistics.missing_count = self.missing_paths.len();
+        statistics.moved_count = self.moved_values.len();
+        statistics.unmatched_element_count = self
+            .array_elements
+            .iter()
+            .map(|diff| diff.missing.len() + diff.extra.len())
+            .sum();
+        statistics.different_count = self.different_values.len();
+        statistics.type_mismatch_count = self.type_mismatches.len();
+        statistics.nullability_change_count = self.nullability_changes.len();
+        statistics.optionality_change_count = self.optionality_changes.len();
+        statistics.schema_violation_count = self.schema_violations.len();
+        statistics.duplicate_key_count = self.duplicate_keys.len();
+        statistics.template_violation_count = self.template_violations.len();
+        statistics.text_diff_count = self.text_diffs.len();
+        statistics.array_length_violation_count = self.array_length_violations.len();
+        statistics.uniqueness_violation_count = self.uniqueness_violations.len();
+        statistics.reference_violation_count = self.reference_violations.len();
+    }
+
+    /// Keeps the first `max` differences of each kind and counts the others as truncated.
+    fn truncate_diffs(&mut self, max: usize) {
+        let mut truncated = std::mem::take(&mut self.truncated);
+        // missing paths, value differences and type mismatches are limited as a DiffReport,
+        // the other kinds below
+        let report = self.take_diff_report();
+        let counts = [
+            (Category::Missing, report.missing_paths.len()),
+            (Category::Different, report.different_values.len()),
+            (Category::TypeMismatch, report.type_mismatches.len()),
+        ];
+        let limited = report.limit(max);
+        for (category, count) in counts {
+            if count > max {
+                *truncated.entry(category.key().to_string()).or_default() += count - max;
+            }
+        }
+        self.put_diff_report(limited);
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        self.retain_diffs(|category, _, _| {
+            let count = kept.entry(category.key()).or_default();
+            *count += 1;
+            if *count <= max {
+                return true;
+            }
+            *truncated.entry(category.key().to_string()).or_default() += 1;
+            false
+        });
+        for (kind, count) in &truncated {
+            self.warnings.push(ComparisonWarning::new(
+                WarningKind::Truncation,
+                None,
+                format!(
+                    "{} {} differences past --max-diffs were left out",
+                    count, kind
+                ),
+            ));
+        }
+        self.truncated = truncated;
+    }
+
+    /// How many differences of a category `--truncate-results` left out.
+    fn truncated_count(&self, category: Category) -> usize {
+        self.truncated.get(category.key()).copied().unwrap_or(0)
+    }
+
+    /// Fingerprints the differences that make the file fail, from the compare file as
+    /// given and the paths in dotted form, whatever `--path-style` shows them in.
+    fn fingerprint_diffs(&mut self, with_values: bool) {
+        let file = self.compare_file.clone();
+        let path_style = self.path_style;
+        let mut fingerprints = Vec::new();
+        self.retain_diffs(|category, path, value| {
+            fingerprints.push(DiffFingerprint {
+                kind: category.key().to_string(),
+                path: path.to_string(),
+                id: fingerprint(
+                    &file,
+                    category.key(),
+                    &path_style.to_dotted(path),
+                    // a repeated value is what identifies a duplicate
+                    value.filter(|_| with_values || category == Category::Duplicate),
+                ),
+            });
+            true
+        });
+        self.fingerprints = fingerprints;
+    }
+
+    /// Spells NaN in the reported values as the documents do, once `--nan-unequal`
+    /// compared them with NaN marked.
+    fn unmark_nan(self) -> Result<ComparisonResult> {
+        let mut value = serde_json::to_value(self)?;
+        unmark_nan(&mut value);
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Rewrites every reported document path, e.g. into JSON Pointers.
+    fn convert_paths(&mut self, convert: impl Fn(&str) -> String) {
+        let paths = self
+            .missing_paths
+            .iter_mut()
+            .chain(self.extra_paths.iter_mut())
+            .chain(self.added_values.iter_mut().map(|a| &mut a.path))
+            .chain(self.base_lossy_paths.iter_mut())
+            .chain(self.lossy_paths.iter_mut())
+            .chain(self.base_duplicate_keys.iter_mut())
+            .chain(self.duplicate_keys.iter_mut())
+            .chain(self.schema_violations.iter_mut().map(|v| &mut v.path))
+            .chain(
+                self.moved_values
+                    .iter_mut()
+                    .flat_map(|m| [&mut m.from, &mut m.to]),
+            )
+            .chain(self.array_elements.iter_mut().map(|a| &mut a.path))
+            .chain(self.different_values.iter_mut().map(|d| &mut d.path))
+            .chain(self.type_mismatches.iter_mut().map(|m| &mut m.path))
+            .chain(self.nullability_changes.iter_mut().map(|c| &mut c.path))
+            .chain(self.optionality_changes.iter_mut().map(|c| &mut c.path))
+            .chain(self.template_violations.iter_mut().map(|v| &mut v.path))
+            .chain(self.text_diffs.iter_mut().map(|d| &mut d.path))
+            .chain(self.array_length_violations.iter_mut().map(|v| &mut v.path))
+            .chain(
+                self.uniqueness_violations
+                    .iter_mut()
+                    .flat_map(|v| v.paths.iter_mut()),
+            )
+            .chain(self.reference_violations.iter_mut().map(|v| &mut v.path))
+            .chain(self.sampled_arrays.iter_mut().map(|a| &mut a.path))
+            .chain(self.warnings.iter_mut().filter_map(|w| w.path.as_mut()))
+            .chain(
+                self.delta_statistics
+                    .iter_mut()
+                    .filter_map(|d| d.max_abs_delta_path.as_mut()),
+            );
+        for path in paths {
+            *path = convert(path);
+        }
+    }
+}
+
+/// What a comparison relaxed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WarningKind {
+    /// Numbers matched within `--abs-tol`, `--rel-tol` or a rules file tolerance
+    Tolerance,
+    /// A stringified scalar matched a scalar with `--coerce`
+    Coercion,
+    /// Values matched by a timestamp, placeholder, collation or comparator rule
+    RelaxedMatch,
+    /// Strings were normalized with `--normalize-strings`
+    Normalization,
+    /// Object keys were matched with `--ignore-key-case`
+    KeyCase,
+    /// Only the types of values were compared, with `-t`
+    TypeOnly,
+    /// Only some elements of an array were compared, with `--sample`
+    Sampling,
+    /// An array too different to align with `--array-order lcs` was paired by index
+    Unaligned,
+    /// Differences were left out by `--truncate-results`
+    Truncation,
+    /// `--deadline` stopped the comparison early
+    Partial,
+    /// The documents were over a size limit and only compared by hash
+    HashOnly,
+    /// Invalid text was replaced with `--lossy`
+    LossyString,
+}
+
+impl WarningKind {
+    fn key(self) -> &'static str {
+        match self {
+            WarningKind::Tolerance => "tolerance",
+            WarningKind::Coercion => "coercion",
+            WarningKind::RelaxedMatch => "relaxed_match",
+            WarningKind::Normalization => "normalization",
+            WarningKind::KeyCase => "key_case",
+            WarningKind::TypeOnly => "type_only",
+            WarningKind::Sampling => "sampling",
+            WarningKind::Unaligned => "unaligned",
+            WarningKind::Truncation => "truncation",
+            WarningKind::Partial => "partial",
+            WarningKind::HashOnly => "hash_only",
+            WarningKind::LossyString => "lossy_string",
+        }
+    }
+}
+
+/// Something a comparison relaxed, and where.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ComparisonWarning {
+    kind: WarningKind,
+    /// The path it applied to, when it wasn't the whole document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    message: String,
+    /// The rule a `relaxed_match` was made by, e.g. `collation` or `comparator:phone`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl ComparisonWarning {
+    fn new(kind: WarningKind, path: Option<&str>, message: impl Into<String>) -> Self {
+        ComparisonWarning {
+            kind,
+            path: path.map(str::to_string),
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    /// Two values at a path that are equal only by a relaxed rule, named in the message
+    /// and, for a `relaxed_match`, in the detail.
+    fn relaxed_match(
+        path: &str,
+        base_value: &Value,
+        compare_value: &Value,
+        rule: MatchRule,
+    ) -> Self {
+        let (kind, detail, description) = match rule {
+            MatchRule::Coercion => (WarningKind::Coercion, None, "after coercion".to_string()),
+            MatchRule::Exact | MatchRule::Tolerance => (
+                WarningKind::Tolerance,
+                None,
+                "within the numeric tolerance".to_string(),
+            ),
+            MatchRule::Timestamp => (
+                WarningKind::RelaxedMatch,
+                Some("timestamp".to_string()),
+                "as instants within the timestamp tolerance".to_string(),
+            ),
+            MatchRule::Placeholder => (
+                WarningKind::RelaxedMatch,
+                Some("placeholder".to_string()),
+                "by rendering the placeholders".to_string(),
+            ),
+            MatchRule::Collation => (
+                WarningKind::RelaxedMatch,
+                Some("collation".to_string()),
+                "by the collation rules".to_string(),
+            ),
+            MatchRule::Comparator(name) => (
+                WarningKind::RelaxedMatch,
+                Some(format!("comparator:{}", name)),
+                format!("by the {} comparator", name),
+            ),
+            MatchRule::Script => (
+                WarningKind::RelaxedMatch,
+                Some("script".to_string()),
+                "by the comparator script".to_string(),
+            ),
+        };
+        ComparisonWarning {
+            detail,
+            ..ComparisonWarning::new(
+                kind,
+                Some(path),
+                format!(
+                    "{} and {} matched {}",
+                    format_value(base_value),
+                    format_value(compare_value),
+                    description
+                ),
+            )
+        }
+    }
+}
+
+/// Whether two values matched only by a relaxed rule: scalars that aren't the same, not
+/// even as numbers. Containers are left to the paths inside them.
+fn is_relaxed_match(base_value: &Value, compare_value: &Value) -> bool {
+    match (base_value, compare_value) {
+        (Value::Object(_) | Value::Array(_), _) => false,
+        (Value::Number(base), Value::Number(compare)) => base.as_f64() != compare.as_f64(),
+        _ => base_value != compare_value,
+    }
+}
+
+/// The relaxations that apply to the whole comparison of a compare file.
+fn document_warnings(
+    args: &Args,
+    limit_exceeded: Option<&str>,
+    partial_coverage: Option<f64>,
+    sampled_arrays: &[SampledArray],
+    unaligned_arrays: &[String],
+    lossy_paths: &[String],
+) -> Vec<ComparisonWarning> {
+    let mut warnings = Vec::new();
+    if let Some(reason) = limit_exceeded {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::HashOnly,
+            None,
+            format!("{}, compared by canonical hash only", reason),
+        ));
+    }
+    if let Some(coverage) = partial_coverage {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::Partial,
+            None,
+            format!(
+                "the deadline passed with {:.1}% of paths compared",
+                coverage
+            ),
+        ));
+    }
+    if args.check_values && args.type_only {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::TypeOnly,
+            None,
+            "only the types of values were compared",
+        ));
+    }
+    if !args.normalize_strings.is_empty() {
+        let normalizations: Vec<_> = args
+            .normalize_strings
+            .iter()
+            .filter_map(|normalization| normalization.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        warnings.push(ComparisonWarning::new(
+            WarningKind::Normalization,
+            None,
+            format!(
+                "strings were normalized before comparing: {}",
+                normalizations.join(", ")
+            ),
+        ));
+    }
+    if args.ignore_key_case {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::KeyCase,
+            None,
+            "object keys were matched case-insensitively",
+        ));
+    }
+    for array in sampled_arrays {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::Sampling,
+            Some(&array.path),
+            format!(
+                "{} of {} elements were compared",
+                array.sampled, array.length
+            ),
+        ));
+    }
+    for path in unaligned_arrays {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::Unaligned,
+            Some(path),
+            "the arrays differ too much to align along a common subsequence, so their \
+             elements were paired by index",
+        ));
+    }
+    for path in lossy_paths {
+        warnings.push(ComparisonWarning::new(
+            WarningKind::LossyString,
+            Some(path),
+            "invalid text was replaced with U+FFFD",
+        ));
+    }
+    warnings
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NullabilityChange {
+    path: String,
+    base_value: Value,
+    compare_value: Value,
+    severity: Severity,
+}
+
+/// A key that is optional in one document's inferred schema and required in the other's,
+/// with `--mode schema`.
+#[derive(Debug, Serialize, Deserialize)]
+struct OptionalityChange {
+    path: String,
+    base: Optionality,
+    compare: Optionality,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Optionality {
+    /// Only some of the merged objects have the key
+    Optional,
+    /// Every merged object has the key
+    Required,
+}
+
+impl Optionality {
+    fn of(path: &str, optional_paths: &BTreeSet<String>) -> Optionality {
+        if optional_paths.contains(path) {
+            Optionality::Optional
+        } else {
+            Optionality::Required
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Optionality::Optional => "optional",
+            Optionality::Required => "required",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TemplateViolation {
+    path: String,
+    template: String,
+    compare_value: Value,
+    reason: String,
+}
+
+/// Extrapolated results for an array of which only a sample was compared.
+#[derive(Debug, Serialize, Deserialize)]
+struct SampledArray {
+    path: String,
+    length: usize,
+    sampled: usize,
+    differing: usize,
+    estimated_difference_rate: f64,
+    estimated_differing: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Statistics {
+    total_paths_checked: usize,
+    missing_count: usize,
+    #[serde(default)]
+    moved_count: usize,
+    /// Missing and extra elements of order-insensitive arrays
+    #[serde(default)]
+    unmatched_element_count: usize,
+    different_count: usize,
+    type_mismatch_count: usize,
+    nullability_change_count: usize,
+    #[serde(default)]
+    optionality_change_count: usize,
+    #[serde(default)]
+    schema_violation_count: usize,
+    #[serde(default)]
+    duplicate_key_count: usize,
+    #[serde(default)]
+    template_violation_count: usize,
+    #[serde(default)]
+    text_diff_count: usize,
+    #[serde(default)]
+    array_length_violation_count: usize,
+    #[serde(default)]
+    uniqueness_violation_count: usize,
+    #[serde(default)]
+    reference_violation_count: usize,
+    /// Base paths skipped because they match an `--ignore` pattern
+    #[serde(default)]
+    ignored_count: usize,
+    match_count: usize,
+    /// Matches between a stringified scalar and a scalar, counted with `--coerce`
+    #[serde(default)]
+    coerced_match_count: usize,
+    /// Compare paths without a counterpart in the base file
+    #[serde(default)]
+    extra_path_count: usize,
+    /// Percentage of the union of both files' paths that matched, weighted by
+    /// `--similarity-weights`; not computed for sampled, partial, hash-only or
+    /// `--paths-file` comparisons
+    #[serde(default)]
+    similarity: Option<f64>,
+    /// The similarity with each path counted by its weight from the rules file's
+    /// `weights`; only computed when it has any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weighted_similarity: Option<f64>,
+}
+
+impl Statistics {
+    /// The similarity `--min-similarity` checks: the weighted one where there is one.
+    fn score(&self) -> Option<f64> {
+        self.weighted_similarity.or(self.similarity)
+    }
+}
+
+/// The loaded base file with everything derived from it once for all compare files.
+struct BaseDocument {
+    json: Value,
+    items: Vec<(String, Value)>,
+    sampled_arrays: Vec<(String, usize)>,
+    hash: Option<String>,
+    ignored_count: usize,
+    lossy_paths: Vec<String>,
+    duplicate_keys: Vec<String>,
+    /// Violations of `--schema` in the base file, reported with every compare file
+    schema_violations: Vec<SchemaViolation>,
+    /// Set when the base file is over a size limit and only compared by hash
+    limit_exceeded: Option<String>,
+    /// The paths of `--paths-file`, the only values both documents are reduced to
+    listed_paths: Option<Vec<String>>,
+    /// Keys only some objects have in the schema inferred with `--mode schema`
+    optional_paths: BTreeSet<String>,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run() -> Result<ExitCode> {
+    let mut args = config::parse();
+    let config = match &args.config {
+        Some(path) => Some(path.clone()),
+        None if !args.no_config && args.command.is_none() => config::find(),
+        None => None,
+    };
+    if let Some(path) = config {
+        // options from the config file go first, so those on the command line override
+        // them
+        let mut argv: Vec<_> = std::env::args_os().collect();
+        argv.splice(1..1, config::load(&path)?);
+        args = config::try_parse_from(argv)
+            .map_err(|e| anyhow::anyhow!("{}", e.render()))
+            .with_context(|| format!("Invalid config file: {:?}", path))?;
+    }
+    args.apply_mode()?;
+
+    match &args.command {
+        Some(Command::Apply(apply_args)) => run_apply(apply_args).map(|_| ExitCode::SUCCESS),
+        Some(Command::Merge(merge_args)) => run_merge(merge_args),
+        Some(Command::Doctor(doctor_args)) => doctor::run(doctor_args),
+        Some(Command::ImportJest(import_args)) => {
+            run_import_jest(import_args).map(|_| ExitCode::SUCCESS)
+        }
+        Some(Command::Aggregate(aggregate_args)) => run_aggregate(aggregate_args),
+        None if args.capabilities => capabilities::run(),
+        None if args.preview_rules => preview::run(&args),
+        None => run_compare(&args),
+    }
+}
+
+fn run_compare(args: &Args) -> Result<ExitCode> {
+    let rules = match &args.rules {
+        Some(path) => load_rules(path)?,
+        None => Rules::default(),
+    };
+    // the rules file's ignores apply like --ignore
+    let args = &args.with_rules_ignores(&rules);
+    if args.export_append && args.export_format != ExportFormat::Json {
+        anyhow::bail!("--export-append only works with --export-format json");
+    }
+    if args.side_by_side && args.output != OutputStyle::Terminal {
+        anyhow::bail!("--side-by-side only works with --output terminal");
+    }
+    if args.tui {
+        if !cfg!(feature = "tui") {
+            anyhow::bail!("--tui requires building with the \"tui\" feature");
+        }
+        if !args.output.is_terminal() || !std::io::stdout().is_terminal() {
+            anyhow::bail!("--tui only works with --output terminal or tree, on a terminal");
+        }
+    }
+    let deadline = args.deadline.map(|duration| Instant::now() + duration);
+    let mut all_results = Vec::new();
+    let compare_files = expand_globs(&args.compare_files)?;
+
+    let stdin_inputs = std::iter::once(args.base_file())
+        .chain(&compare_files)
+        .filter(|path| is_stdin(path))
+        .count();
+    if stdin_inputs > 1 {
+        anyhow::bail!("stdin (\"-\") can only be used for one input");
+    }
+    let compare_files = dedupe_inputs(compare_files)?;
+
+    let schema = args
+        .schema
+        .as_ref()
+        .map(|path| {
+            Schema::new(&load_json(path, &LoadOptions::default())?)
+                .with_context(|| format!("Invalid JSON Schema: {:?}", path))
+        })
+        .transpose()?;
+
+    // load base JSON file
+    let (mut base_json, limit_exceeded, duplicate_keys) = load_limited(args, args.base_file())?;
+    let inline_ignores = if args.inline_ignores {
+        take_inline_ignores(&mut base_json)
+            .with_context(|| format!("Invalid suppression in {}", display_name(args.base_file())))?
+    } else {
+        Vec::new()
+    };
+    // the base file's inline ignores apply like --ignore
+    let args = &args.with_inline_ignores(inline_ignores);
+    let schema_violations = schema
+        .as_ref()
+        .map(|schema| schema.validate(&base_json, SchemaDocument::Base))
+        .unwrap_or_default();
+    let manifest = args.manifest.as_deref().map(Manifest::load).transpose()?;
+    let entries = manifest
+        .as_ref()
+        .map_or(&[][..], |manifest| &manifest.files[..]);
+    // manifest entries that ignore paths flatten their own copy of the base file
+    let entry_bases = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .changes_base()
+                .then(|| {
+                    prepare_base(
+                        &entry.apply(args),
+                        base_json.clone(),
+                        limit_exceeded.clone(),
+                        duplicate_keys.clone(),
+                        schema_violations.clone(),
+                    )
+                })
+                .transpose()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let base = prepare_base(
+        args,
+        base_json,
+        limit_exceeded,
+        duplicate_keys,
+        schema_violations,
+    )?;
+    let script = args
+        .comparator
+        .as_deref()
+        .map(ComparatorScript::load)
+        .transpose()?
+        .map(Arc::new);
+
+    // print header information
+    let mut renderer = render::renderer(args.output);
+    let header = render::header_fields(args, &base);
+    if !args.quiet && !args.tui {
+        renderer.header("JSON Diff Checker", &header);
+    }
+
+    // the compare files on the command line, then those of the manifest with their
+    // overrides
+    let mut targets: Vec<(Args, &BaseDocument, PathBuf, Vec<PathBuf>)> = compare_files
+        .into_iter()
+        .map(|(path, aliases)| (args.clone(), &base, path, aliases))
+        .collect();
+    for (entry, entry_base) in entries.iter().zip(&entry_bases) {
+        let entry_args = entry.apply(args);
+        for (path, aliases) in dedupe_inputs(expand_globs(std::slice::from_ref(&entry.path))?)? {
+            targets.push((
+                entry_args.clone(),
+                entry_base.as_ref().unwrap_or(&base),
+                path,
+                aliases,
+            ));
+        }
+    }
+
+    // a baseline is recorded from scratch, dropping the differences that were fixed
+    let mut baseline = match &args.baseline {
+        Some(_) if args.update_baseline => Some(Baseline::default()),
+        Some(path) => Some(Baseline::load(path)?),
+        None => None,
+    };
+
+    let mut checkpoint = args
+        .checkpoint
+        .as_deref()
+        .map(|path| Checkpoint::open(path, &display_name(args.base_file())))
+        .transpose()?;
+
+    // check each compare file
+    for (args, base, compare_file, aliases) in &targets {
+        // files an earlier run already compared are reported as recorded
+        let recorded = checkpoint
+            .as_mut()
+            .and_then(|checkpoint| checkpoint.take(&display_name(compare_file)));
+        let mut result = match recorded {
+            Some(result) => result,
+            None => {
+                let mut result = compare_single_file(
+                    args,
+                    base,
+                    &rules,
+                    schema.as_ref(),
+                    script.as_ref(),
+                    compare_file,
+                    deadline,
+                )?;
+                if args.nan_unequal {
+                    result = result.unmark_nan()?;
+                }
+                if args.path_style == PathStyle::Pointer {
+                    result.convert_paths(path_to_pointer);
+                    result.path_style = PathStyle::Pointer;
+                }
+                result.aliases = aliases.iter().map(|alias| display_name(alias)).collect();
+                if let Some(checkpoint) = &mut checkpoint {
+                    checkpoint.record(&result)?;
+                }
+                result
+            }
+        };
+        if let Some(baseline) = &mut baseline {
+            if args.update_baseline {
+                baseline.record(&mut result);
+            }
+            result.baselined = baseline.suppress(&mut result);
+            result.filtered |= result.baselined > 0;
+        }
+        if !args.only.is_empty() {
+            result.keep_only(&args.only);
+        }
+        if let (true, Some(max)) = (args.truncate_results, args.max_diffs) {
+            result.truncate_diffs(max as usize);
+        }
+        result.fingerprint_diffs(args.fingerprint_values);
+
+        // output results; --two-phase and --tui report them once all files are compared
+        if !args.two_phase && !args.tui {
+            if args.summary {
+                renderer.file_summary(&render::file_summary(&result));
+            } else {
+                renderer.file(&for_stdout(render::file_report(&result, args), args));
+            }
+        }
+
+        all_results.push(result);
+    }
+
+    let mut phase_fields = Vec::new();
+    let mut phase_failed = false;
+    if args.two_phase {
+        for (phase, threshold) in [
+            (Phase::Structure, Some(args.structure_threshold)),
+            (Phase::Values, args.value_threshold),
+        ] {
+            renderer.heading(phase.title());
+            for (result, (args, ..)) in all_results.iter().zip(&targets) {
+                if args.summary {
+                    renderer.file_summary(&render::phase_summary(result, phase));
+                } else {
+                    renderer.file(&for_stdout(render::phase_report(result, args, phase), args));
+                }
+            }
+            let mut count = 0;
+            for result in &mut all_results {
+                let mut categories = HashSet::new();
+                result.retain_diffs(|category, _, _| {
+                    count += usize::from(phase.includes(category));
+                    categories.insert(category);
+                    true
+                });
+                // differences --truncate-results left out still count against the threshold
+                count += categories
+                    .into_iter()
+                    .filter(|&category| phase.includes(category))
+                    .map(|category| result.truncated_count(category))
+                    .sum::<usize>();
+            }
+            phase_failed |= threshold.is_some_and(|threshold| count > threshold);
+            phase_fields.push(render::phase_field(phase, count, threshold));
+        }
+    }
+
+    // print overall summary if multiple files are compared, or the phases' verdicts
+    let mut overall = render::overall_fields(&all_results);
+    overall.extend(phase_fields);
+    if args.tui {
+        browse(&all_results, &targets, overall.clone())?;
+    } else if (targets.len() > 1 || args.two_phase) && !args.quiet {
+        renderer.overall(&overall);
+    }
+    renderer.finish();
+    if args.output == OutputStyle::Json {
+        println!("{}", serde_json::to_string_pretty(&all_results)?);
+    }
+
+    // export results if specified
+    if let Some(export_path) = &args.export {
+        let reports = || -> Vec<_> {
+            all_results
+                .iter()
+                .zip(&targets)
+                .map(|(result, (args, ..))| render::file_report(result, args))
+                .collect()
+        };
+        let content = match args.export_format {
+            ExportFormat::Json if args.export_append && export_path.exists() => {
+                // a re-run replaces the results of the compare files it checked again
+                let rerun: HashSet<(&str, &str)> = all_results
+                    .iter()
+                    .map(|result| (result.base_file.as_str(), result.compare_file.as_str()))
+                    .collect();
+                let previous = load_exported(export_path)?;
+                let results: Vec<&ComparisonResult> = previous
+                    .iter()
+                    .filter(|result| {
+                        !rerun.contains(&(result.base_file.as_str(), result.compare_file.as_str()))
+                    })
+                    .chain(&all_results)
+                    .collect();
+                serde_json::to_string_pretty(&results)?
+            }
+            ExportFormat::Json => serde_json::to_string_pretty(&all_results)?,
+            ExportFormat::Html => {
+                render::html_page("JSON Diff Checker", &header, &reports(), &overall)
+            }
+            ExportFormat::Markdown => {
+                render::markdown_report("JSON Diff Checker", &header, &reports(), &overall)
+            }
+            ExportFormat::Junit => render::junit_report("JSON Diff Checker", &header, &reports()),
+            ExportFormat::Sarif => render::sarif_report(&header, &reports()),
+            ExportFormat::Csv => render::csv_entries(&all_results),
+            ExportFormat::Rdjson => render::rdjson_report(&reports()),
+            ExportFormat::Checkstyle => render::checkstyle_report(&reports()),
+            ExportFormat::Dot => render::dot_graph(&base.json, &all_results),
+        };
+        export_results(export_path, content)?;
+        let message = format!("✓ Results exported to {:?}", export_path);
+        // other output styles keep stdout for the report itself
+        if args.output.is_terminal() && !args.quiet {
+            println!("\n{}", message.green().bold());
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+    if let (Some(baseline), Some(path), true) = (&baseline, &args.baseline, args.update_baseline) {
+        baseline.save(path)?;
+        let message = format!("✓ Baseline written to {:?}", path);
+        if args.output.is_terminal() && !args.quiet {
+            println!("\n{}", message.green().bold());
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.finish()?;
+    }
+
+    let found = |kind: FailOn| all_results.iter().any(|result| kind.matches(result));
+    let mapped = args
+        .exit_code
+        .iter()
+        .find(|(kind, _)| found(*kind))
+        .map(|(_, code)| *code);
+    let dissimilar = args.min_similarity.is_some_and(|min| {
+        all_results
+            .iter()
+            .any(|result| result.statistics.score().is_some_and(|score| score < min))
+    });
+    let failed = phase_failed || dissimilar || args.fail_on.iter().any(|kind| found(*kind));
+    Ok(match mapped {
+        Some(code) => ExitCode::from(code),
+        None if failed => ExitCode::from(EXIT_DIFFERENCES),
+        None => ExitCode::SUCCESS,
+    })
+}
+
+/// Opens the results in the `--tui` browser, with the values of different values to
+/// expand pretty-printed.
+#[cfg(feature = "tui")]
+fn browse(
+    results: &[ComparisonResult],
+    targets: &[(Args, &BaseDocument, PathBuf, Vec<PathBuf>)],
+    overall: Vec<render::Field>,
+) -> Result<()> {
+    let reports = results
+        .iter()
+        .zip(targets)
+        .map(|(result, (args, ..))| {
+            let args = Args {
+                side_by_side: true,
+                ..args.clone()
+            };
+            render::file_report(result, &args)
+        })
+        .collect();
+    tui::browse("JSON Diff Checker", reports, overall)
+}
+
+#[cfg(not(feature = "tui"))]
+fn browse(
+    _results: &[ComparisonResult],
+    _targets: &[(Args, &BaseDocument, PathBuf, Vec<PathBuf>)],
+    _overall: Vec<render::Field>,
+) -> Result<()> {
+    anyhow::bail!("--tui requires building with the \"tui\" feature")
+}
+
+/// The report with its paths grouped by `--group-by-prefix` and its entries limited by
+/// `--max-diffs`, for the output on stdout.
+fn for_stdout(mut report: render::FileReport, args: &Args) -> render::FileReport {
+    if let Some(depth) = args.group_by_prefix {
+        render::group_by_prefix(&mut report, depth as usize);
+    }
+    if let Some(max) = args.max_diffs {
+        render::limit_entries(&mut report, max as usize);
+    }
+    report
+}
+
+/// Prepares the loaded base file for comparing: normalizes it and flattens the paths
+/// that are selected and not ignored.
+fn prepare_base(
+    args: &Args,
+    mut base_json: Value,
+    limit_exceeded: Option<String>,
+    duplicate_keys: Vec<String>,
+    schema_violations: Vec<SchemaViolation>,
+) -> Result<BaseDocument> {
+    let flatten_options = FlattenOptions {
+        sample: args.sample,
+        ignore_keys: args.ignore_key.clone(),
+    };
+    let hash = args.print_hashes.then(|| canonical_sha256(&base_json));
+    let listed_paths = args
+        .paths_file
+        .as_deref()
+        .map(load_paths_file)
+        .transpose()?;
+    let optional_paths = args.normalize_listed(&mut base_json, listed_paths.as_deref(), |_| {});
+    let lossy_paths = if args.lossy {
+        lossy_paths(&base_json, "")
+    } else {
+        Vec::new()
+    };
+    let ignore_patterns = args.ignore_patterns();
+    let is_ignored = |path: &str| {
+        ignore_patterns
+            .iter()
+            .any(|pattern| pattern.matches_prefix_of(path))
+    };
+    let selectors = args.selectors()?;
+    let is_selected = |path: &str| {
+        selectors.is_empty()
+            || selectors
+                .iter()
+                .any(|selector| selector.matches_prefix_of(path))
+    };
+    let unfiltered_count = (listed_paths.is_none()
+        && (!ignore_patterns.is_empty() || !args.ignore_key.is_empty()))
+    .then(|| {
+        let unfiltered_options = FlattenOptions {
+            ignore_keys: Vec::new(),
+            ..flatten_options.clone()
+        };
+        get_all_items_with(&base_json, String::new(), &unfiltered_options)
+            .iter()
+            .filter(|(path, _)| is_selected(path))
+            .count()
+    });
+    remove_matching(&mut base_json, &ignore_patterns);
+
+    // ignored array elements are left behind as nulls to keep their siblings' paths;
+    // an oversized base is never flattened, and a paths file is looked up path by path
+    let mut ignored_listed = 0;
+    let items: Vec<_> = if limit_exceeded.is_some() {
+        Vec::new()
+    } else if let (Some(listed), Some(paths_file)) = (&listed_paths, &args.paths_file) {
+        let mut items = Vec::new();
+        for path in listed {
+            if is_ignored(path) {
+                ignored_listed += 1;
+                continue;
+            }
+            let value = get_value_by_path(&base_json, path).with_context(|| {
+                format!(
+                    "Path {:?} from {:?} not found in the base file",
+                    path, paths_file
+                )
+            })?;
+            items.push((path.clone(), value.clone()));
+        }
+        items
+    } else {
+        get_all_items_with(&base_json, String::new(), &flatten_options)
+            .into_iter()
+            .filter(|(path, _)| is_selected(path) && !is_ignored(path))
+            .collect()
+    };
+    let sampled = if limit_exceeded.is_some() {
+        Vec::new()
+    } else {
+        sampled_arrays(&base_json, String::new(), &flatten_options)
+    };
+    // the whole-document checks (--quick, --text-diff roots, distributions) see the same
+    // keys as the flattened items
+    remove_keys(&mut base_json, &args.ignore_key);
+
+    Ok(BaseDocument {
+        ignored_count: unfiltered_count
+            .map_or(ignored_listed, |count| count.saturating_sub(items.len())),
+        items,
+        sampled_arrays: sampled,
+        hash,
+        lossy_paths,
+        duplicate_keys,
+        schema_violations,
+        limit_exceeded,
+        listed_paths,
+        optional_paths,
+        json: base_json,
+    })
+}
+
+/// A copy of `json` with only the values at `paths`, in place: the objects and arrays
+/// around them are rebuilt, with nulls before the listed elements of an array. Paths
+/// the document doesn't have are left out.
+fn sparse_copy(json: &Value, paths: &[String]) -> Value {
+    let mut copy = Value::Null;
+    for path in paths {
+        let Some(value) = get_value_by_path(json, path) else {
+            continue;
+        };
+        let mut slot = &mut copy;
+        for part in parse_path(path) {
+            slot = match part {
+                PathPart::Key(key) => {
+                    if !slot.is_object() {
+                        *slot = Value::Object(Default::default());
+                    }
+                    slot.as_object_mut()
+                        .unwrap()
+                        .entry(key)
+                        .or_insert(Value::Null)
+                }
+                PathPart::Index(index) => {
+                    if !slot.is_array() {
+                        *slot = Value::Array(Vec::new());
+                    }
+                    let arr = slot.as_array_mut().unwrap();
+                    if arr.len() <= index {
+                        arr.resize(index + 1, Value::Null);
+                    }
+                    &mut arr[index]
+                }
+            };
+        }
+        *slot = value.clone();
+    }
+    copy
+}
+
+/// Reads the paths of `--paths-file` in dotted form, in the order listed and without
+/// repeats.
+fn load_paths_file(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read paths file: {:?}", path))?;
+    let mut paths: Vec<String> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = if line.starts_with('/') {
+            pointer_to_path(line)
+                .with_context(|| format!("Invalid path on line {} of {:?}", number + 1, path))?
+        } else {
+            path_from_parts(&parse_path(line))
+        };
+        if !paths.contains(&parsed) {
+            paths.push(parsed);
+        }
+    }
+    if paths.is_empty() {
+        anyhow::bail!("No paths listed in {:?}", path);
+    }
+    Ok(paths)
+}
+
+/// Expands glob patterns in the given paths ourselves, so patterns behave the same on
+/// shells without globbing and in Windows cmd. Matches are sorted for a stable order.
+fn expand_globs(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        let is_pattern = pattern.contains(['*', '?', '['])
+            && !path.exists()
+            && !is_stdin(path)
+            && input_url(path).is_none()
+            && git_object(path).is_none();
+        if !is_pattern {
+            expanded.push(path.clone());
+            continue;
+        }
+
+        let mut matches = glob::glob(&pattern)
+            .with_context(|| format!("Invalid glob pattern: {}", pattern))?
+            .collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            anyhow::bail!("No files match: {}", pattern);
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+
+    Ok(expanded)
+}
+
+/// Drops inputs that were passed more than once: the same file under another path, or
+/// another file with the same content. Each remaining input comes with the paths it was
+/// passed as again, in the order they were given.
+fn dedupe_inputs(paths: Vec<PathBuf>) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+    let is_local =
+        |path: &Path| !is_stdin(path) && input_url(path).is_none() && git_object(path).is_none();
+    let mut inputs: Vec<Option<(PathBuf, Vec<PathBuf>)>> = Vec::new();
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+    for path in paths {
+        let identity = if is_local(&path) {
+            fs::canonicalize(&path).unwrap_or_else(|_| path.clone())
+        } else {
+            path.clone()
+        };
+        match seen.get(&identity) {
+            Some(&index) => inputs[index].as_mut().unwrap().1.push(path),
+            None => {
+                seen.insert(identity, inputs.len());
+                inputs.push(Some((path, Vec::new())));
+            }
+        }
+    }
+
+    // copies are only hashed when another file has the same size
+    let mut by_size: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, (path, _)) in inputs.iter().flatten().enumerate() {
+        if !is_local(path) {
+            continue;
+        }
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.is_file() {
+                by_size.entry(metadata.len()).or_default().push(index);
+            }
+        }
+    }
+    let mut first_copy: HashMap<usize, usize> = HashMap::new();
+    for indices in by_size.into_values().filter(|indices| indices.len() > 1) {
+        let mut by_hash = HashMap::new();
+        for index in indices {
+            let path = &inputs[index].as_ref().unwrap().0;
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut fs::File::open(path)?, &mut hasher)
+                .with_context(|| format!("Failed to read file: {:?}", path))?;
+            let first = *by_hash.entry(hasher.finalize()).or_insert(index);
+            if first != index {
+                first_copy.insert(index, first);
+            }
+        }
+    }
+    for index in 0..inputs.len() {
+        if let Some(&first) = first_copy.get(&index) {
+            let (path, aliases) = inputs[index].take().unwrap();
+            let (_, first_aliases) = inputs[first].as_mut().unwrap();
+            first_aliases.push(path);
+            first_aliases.extend(aliases);
+        }
+    }
+    Ok(inputs.into_iter().flatten().collect())
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn display_name(path: &Path) -> String {
+    if is_stdin(path) {
+        "<stdin>".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+fn input_url(path: &Path) -> Option<&str> {
+    path.to_str()
+        .filter(|p| p.starts_with("http://") || p.starts_with("https://"))
+}
+
+/// Inputs of the form `rev:path` (e.g. `HEAD~1:config.json`) name a file in the git
+/// object store, unless they exist on disk.
+fn git_object(path: &Path) -> Option<&str> {
+    let spec = path.to_str()?;
+    let (rev, file) = spec.split_once(':')?;
+    // a single letter followed by a separator is a Windows drive, not a revision
+    let is_drive = rev.len() == 1 && file.starts_with(['/', '\\']);
+    if rev.is_empty() || file.is_empty() || is_drive || input_url(path).is_some() || path.exists() {
+        return None;
+    }
+    Some(spec)
+}
+
+/// The size, modification time and permissions of an input on disk; stdin, URLs and
+/// git objects have none.
+fn file_metadata(path: &Path) -> Option<FileMetadata> {
+    if is_stdin(path) || input_url(path).is_some() || git_object(path).is_some() {
+        return None;
+    }
+    FileMetadata::read(path).ok()
+}
+
+fn read_git_object(spec: &str) -> Result<Vec<u8>> {
+    let output = std::process::Command::new("git")
+        .args(["cat-file", "blob", spec])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to read {} from git: {}",
+            spec,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+fn load_json(path: &PathBuf, options: &LoadOptions) -> Result<Value> {
+    let content = read_input(path, options)?;
+    parse_input(path, &content, options)
+}
+
+/// Loads a base or compare input, enforcing `--max-file-size` and `--max-nodes`. With
+/// `--on-limit hash` an input over a limit is loaded anyway, together with the reason it
+/// is only compared by hash. With `--strict` the paths of repeated keys are returned too.
+fn load_limited(args: &Args, path: &PathBuf) -> Result<(Value, Option<String>, Vec<String>)> {
+    let options = args.load_options(path);
+    let content = read_input(path, &options)?;
+    let mut limit_exceeded = args
+        .max_file_size
+        .filter(|max| content.len() as u64 > *max)
+        .map(|max| size_limit_message(path, content.len() as u64, max));
+    if let (Some(message), LimitAction::Abort) = (&limit_exceeded, args.on_limit) {
+        anyhow::bail!("{}", message);
+    }
+
+    let json = parse_input(path, &content, &options)?;
+    let duplicate_keys = if args.strict {
+        let format = detect_format(path, &options);
+        duplicate_keys(&content, format, &options.parse)
+            .with_context(|| format!("Failed to parse {} from: {}", format, display_name(path)))?
+    } else {
+        Vec::new()
+    };
+    // the raw bytes are not needed while the nodes are counted
+    drop(content);
+    if limit_exceeded.is_none() {
+        if let Some(max) = args.max_nodes {
+            let nodes = count_nodes(&json);
+            if nodes > max {
+                let message = format!(
+                    "{} has {} values, more than --max-nodes {}",
+                    display_name(path),
+                    nodes,
+                    max
+                );
+                if args.on_limit == LimitAction::Abort {
+                    anyhow::bail!("{}", message);
+                }
+                limit_exceeded = Some(message);
+            }
+        }
+    }
+    Ok((json, limit_exceeded, duplicate_keys))
+}
+
+fn size_limit_message(path: &Path, size: u64, max: u64) -> String {
+    format!(
+        "{} is {} bytes, more than --max-file-size {}",
+        display_name(path),
+        size,
+        max
+    )
+}
+
+/// Parses sizes like `1048576`, `64K`, `500M` or `2GB`, with binary multiples.
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let number = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, multiplier) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1u64 << 10),
+        Some('M') => (&number[..number.len() - 1], 1 << 20),
+        Some('G') => (&number[..number.len() - 1], 1 << 30),
+        _ => (number, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| {
+            format!(
+                "invalid size {:?}, expected e.g. 1048576, 64K, 500M or 2G",
+                s
+            )
+        })
+}
 
-    /// Show only summary
-    #[arg(short = 's', long)]
-    summary: bool,
+/// Reads and decompresses an input from stdin, a URL, git or a file. Inputs are read
+/// through `--max-file-size`, so one over it fails before it is inflated in full.
+fn read_input(path: &PathBuf, options: &LoadOptions) -> Result<Vec<u8>> {
+    let limit = options.max_file_size;
+    let content = if is_stdin(path) {
+        read_decompressed(std::io::stdin().lock(), limit).context("Failed to read stdin")?
+    } else if let Some(url) = input_url(path) {
+        fetch_url(url, &options.headers, limit)?
+    } else if let Some(spec) = git_object(path) {
+        read_decompressed(&read_git_object(spec)?[..], limit)
+            .with_context(|| format!("Failed to read: {:?}", path))?
+    } else {
+        let file =
+            fs::File::open(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+        read_decompressed(std::io::BufReader::new(file), limit)
+            .with_context(|| format!("Failed to read: {:?}", path))?
+    };
+    if let Some(max) = limit.filter(|max| content.len() as u64 > *max) {
+        anyhow::bail!(
+            "{} is more than --max-file-size {} bytes after decompression",
+            display_name(path),
+            max
+        );
+    }
+    Ok(content)
+}
 
-    /// Export results to JSON file
-    #[arg(short = 'e', long)]
-    export: Option<PathBuf>,
+fn detect_format(path: &Path, options: &LoadOptions) -> InputFormat {
+    options.format.unwrap_or_else(|| match input_url(path) {
+        // detect the format from the URL path, without query string or fragment
+        Some(url) => InputFormat::from_path(Path::new(url.split(['?', '#']).next().unwrap_or(url))),
+        None => InputFormat::from_path(path),
+    })
+}
 
-    /// Include parent paths in missing items
-    #[arg(short = 'p', long)]
-    include_parents: bool,
+fn parse_input(path: &Path, content: &[u8], options: &LoadOptions) -> Result<Value> {
+    if let Some(extractor) = &options.extract {
+        return extract(content, extractor, &options.parse)
+            .with_context(|| format!("Failed to extract JSON from: {:?}", path));
+    }
+    let format = detect_format(path, options);
+    let json = parse_document_with(content, format, &options.parse)
+        .with_context(|| format!("Failed to parse {} from: {}", format, display_name(path)))?;
+    Ok(json)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ComparisonResult {
-    base_file: String,
-    compare_file: String,
-    missing_paths: Vec<String>,
-    different_values: Vec<ValueDifference>,
-    type_mismatches: Vec<TypeMismatch>,
-    statistics: Statistics,
+#[cfg(feature = "http")]
+fn fetch_url(url: &str, headers: &[String], limit: Option<u64>) -> Result<Vec<u8>> {
+    let mut request = ureq::get(url);
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .with_context(|| format!("Invalid header {:?}, expected \"Name: value\"", header))?;
+        request = request.set(name.trim(), value.trim());
+    }
+
+    let response = request
+        .call()
+        .with_context(|| format!("Failed to fetch: {}", url))?;
+    read_decompressed(response.into_reader(), limit)
+        .with_context(|| format!("Failed to read response from: {}", url))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ValueDifference {
-    path: String,
-    base_value: Value,
-    compare_value: Value,
+#[cfg(not(feature = "http"))]
+fn fetch_url(url: &str, _headers: &[String], _limit: Option<u64>) -> Result<Vec<u8>> {
+    anyhow::bail!(
+        "Fetching {} requires building with the \"http\" feature",
+        url
+    )
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Statistics {
-    total_paths_checked: usize,
-    missing_count: usize,
-    different_count: usize,
-    type_mismatch_count: usize,
-    match_count: usize,
+fn run_apply(args: &ApplyArgs) -> Result<()> {
+    let mut document = load_json(&args.base_file, &LoadOptions::default())?;
+    let patch = load_json(&args.patch_file, &LoadOptions::default())?;
+
+    if is_json_patch(&patch) {
+        let operations: Vec<PatchOperation> = serde_json::from_value(patch)
+            .with_context(|| format!("Invalid JSON Patch in: {:?}", args.patch_file))?;
+        apply_patch(&mut document, &operations)?;
+    } else {
+        let results: Vec<ComparisonResult> = serde_json::from_value(patch).with_context(|| {
+            format!(
+                "Expected exported results or a JSON Patch in: {:?}",
+                args.patch_file
+            )
+        })?;
+        let result = select_result(&results, args.compare_file.as_deref())?;
+        check_applicable(result)?;
+        for operation in diff_to_operations(result) {
+            // children of an already removed path are gone with their parent
+            if let PatchOperation::Remove { path } = &operation {
+                if document.pointer(path).is_none() {
+                    continue;
+                }
+            }
+            apply_operation(&mut document, &operation)?;
+        }
+    }
+
+    let output = serde_json::to_string_pretty(&document)?;
+    match &args.output {
+        Some(path) => fs::write(path, output + "\n")
+            .with_context(|| format!("Failed to write file: {:?}", path))?,
+        None => println!("{}", output),
+    }
+
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let mut all_results = Vec::new();
+fn run_merge(args: &MergeArgs) -> Result<ExitCode> {
+    let ancestor = load_json(&args.ancestor, &LoadOptions::default())?;
+    let ours = load_json(&args.ours, &LoadOptions::default())?;
+    let theirs = load_json(&args.theirs, &LoadOptions::default())?;
 
-    // load base JSON file
-    let base_json = load_json(&args.base_file)?;
-    let base_items = get_all_items(&base_json, String::new());
+    let result = three_way_merge(&ancestor, &ours, &theirs);
 
-    // print header information
-    print_header(&args, &base_items);
+    let output = serde_json::to_string_pretty(&result.merged)?;
+    match &args.output {
+        Some(path) => fs::write(path, output + "\n")
+            .with_context(|| format!("Failed to write file: {:?}", path))?,
+        None => println!("{}", output),
+    }
 
-    // check each compare file
-    for compare_file in &args.compare_files {
-        let result = compare_single_file(&args, &base_items, compare_file)?;
+    if result.conflicts.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
 
-        // output results
-        if args.summary {
-            print_summary(&result);
+    // conflicts go to stderr so stdout stays a valid JSON document
+    eprintln!(
+        "{} Merge conflicts ({}):",
+        "✗".red(),
+        result.conflicts.len()
+    );
+    for conflict in &result.conflicts {
+        let path = if conflict.path.is_empty() {
+            "(root)"
         } else {
-            print_detailed_results(&result, &args);
+            conflict.path.as_str()
+        };
+        eprintln!("  {} {}", "└".bright_black(), path.bright_red());
+        for (label, value) in [
+            ("ancestor:", &conflict.ancestor),
+            ("ours:    ", &conflict.ours),
+            ("theirs:  ", &conflict.theirs),
+        ] {
+            let value = value
+                .as_ref()
+                .map(format_value)
+                .unwrap_or_else(|| "(absent)".to_string());
+            eprintln!("    {} {}", label.bright_black(), value);
         }
+    }
+    eprintln!("Conflicting paths keep our version.");
 
-        all_results.push(result);
+    Ok(ExitCode::from(EXIT_DIFFERENCES))
+}
+
+fn run_import_jest(args: &ImportJestArgs) -> Result<()> {
+    let is_snapshot = args.input.extension().is_some_and(|ext| ext == "snap");
+    let matchers = if is_snapshot {
+        let text = String::from_utf8(read_input(&args.input, &LoadOptions::default())?)
+            .with_context(|| format!("Snapshot file is not UTF-8: {:?}", args.input))?;
+        let snapshots = matchers_in_snapshots(&text)
+            .with_context(|| format!("Invalid snapshot file: {:?}", args.input))?;
+        let names: Vec<String> = snapshots.iter().map(|(name, _)| name.clone()).collect();
+        let selected: Vec<_> = snapshots
+            .into_iter()
+            .filter(|(name, _)| args.snapshot.as_ref().is_none_or(|wanted| wanted == name))
+            .flat_map(|(_, matchers)| matchers)
+            .collect();
+        if let (Some(wanted), true) = (&args.snapshot, selected.is_empty()) {
+            if !names.contains(wanted) {
+                anyhow::bail!(
+                    "No snapshot named {:?} in {:?}; it has {}",
+                    wanted,
+                    args.input,
+                    names
+                        .iter()
+                        .map(|name| format!("{:?}", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+        selected
+    } else {
+        if args.snapshot.is_some() {
+            anyhow::bail!("--snapshot only applies to .snap files");
+        }
+        matchers_in_json(&load_json(&args.input, &LoadOptions::default())?)
+    };
+
+    let rules = jest_to_rules(&matchers);
+    let output = serde_json::to_string_pretty(&rules)?;
+    match &args.output {
+        Some(path) => fs::write(path, output + "\n")
+            .with_context(|| format!("Failed to write file: {:?}", path))?,
+        None => println!("{}", output),
     }
+    // the summary goes to stderr so stdout stays a valid rules file
+    eprintln!(
+        "{} Imported {} matcher(s): {} ignore(s), {} tolerance(s), {} unordered array(s)",
+        "✓".green(),
+        matchers.len(),
+        rules.ignore.len(),
+        rules.tolerances.len(),
+        rules.array_order.len()
+    );
+    Ok(())
+}
+
+/// The results of an `--export` file.
+fn load_exported(path: &PathBuf) -> Result<Vec<ComparisonResult>> {
+    serde_json::from_value(load_json(path, &LoadOptions::default())?)
+        .with_context(|| format!("Expected exported results in: {:?}", path))
+}
 
-    // print overall summary if multiple files are compared
-    if args.compare_files.len() > 1 {
-        print_overall_summary(&all_results);
+fn run_aggregate(args: &AggregateArgs) -> Result<ExitCode> {
+    let exports = expand_globs(&args.exports)?;
+    // a compare file checked against the same base in several exports, e.g. by a
+    // re-run job, keeps its last result
+    let mut results: Vec<ComparisonResult> = Vec::new();
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+    for path in &exports {
+        for result in load_exported(path)? {
+            let key = (result.base_file.clone(), result.compare_file.clone());
+            match positions.get(&key) {
+                Some(&position) => results[position] = result,
+                None => {
+                    positions.insert(key, results.len());
+                    results.push(result);
+                }
+            }
+        }
+    }
+
+    let mut renderer = render::renderer(args.output);
+    renderer.header(
+        "JSON Diff Checker",
+        &render::aggregate_fields(exports.len(), &results),
+    );
+    for result in &results {
+        renderer.file_summary(&render::file_summary(result));
+    }
+    renderer.overall(&render::overall_fields(&results));
+    renderer.finish();
+    if args.output == OutputStyle::Json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
     }
 
-    // export results if specified
     if let Some(export_path) = &args.export {
-        export_results(export_path, &all_results)?;
-        println!(
-            "\n{}",
-            format!("✓ Results exported to {:?}", export_path)
-                .green()
-                .bold()
-        );
+        export_results(export_path, serde_json::to_string_pretty(&results)?)?;
+        let message = format!("✓ Merged results exported to {:?}", export_path);
+        if args.output.is_terminal() {
+            println!("\n{}", message.green().bold());
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    let failed = args
+        .fail_on
+        .iter()
+        .any(|kind| results.iter().any(|result| kind.matches(result)));
+    Ok(if failed {
+        ExitCode::from(EXIT_DIFFERENCES)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn select_result<'a>(
+    results: &'a [ComparisonResult],
+    compare_file: Option<&str>,
+) -> Result<&'a ComparisonResult> {
+    match compare_file {
+        Some(name) => results
+            .iter()
+            .find(|r| r.compare_file == name || r.compare_file.ends_with(name))
+            .with_context(|| format!("No results for compare file {:?} in export", name)),
+        None if results.len() == 1 => Ok(&results[0]),
+        None => anyhow::bail!(
+            "Export contains {} results, select one with --compare-file",
+            results.len()
+        ),
     }
+}
 
+/// Fails unless an exported result has everything `apply` needs to rebuild the compare
+/// document from the base: the compare values of all differences, none of them left out,
+/// and no moves or unmatched array elements, which it has no operations for.
+fn check_applicable(result: &ComparisonResult) -> Result<()> {
+    let file = &result.compare_file;
+    if !result.values_checked {
+        anyhow::bail!(
+            "The result for {} has no compare values to apply; export it with -v and without -t",
+            file
+        );
+    }
+    if (result.limit_exceeded.is_some() && !result.identical) || result.partial_coverage.is_some() {
+        anyhow::bail!(
+            "The result for {} is incomplete, from a size limit or --deadline, and can't be applied",
+            file
+        );
+    }
+    if !result.truncated.is_empty() {
+        anyhow::bail!(
+            "The result for {} lacks the differences --truncate-results left out and can't be applied",
+            file
+        );
+    }
+    if result.filtered {
+        anyhow::bail!(
+            "The result for {} leaves out differences, by --only, --ignore, --ignore-key, \
+             --select, --paths-file, --sample or --baseline, and can't be applied",
+            file
+        );
+    }
+    let unreplayable = result
+        .moved_values
+        .first()
+        .map(|m| &m.to)
+        .or(result.array_elements.first().map(|a| &a.path));
+    if let Some(path) = unreplayable {
+        anyhow::bail!(
+            "The result for {} has changes apply can't replay, e.g. at {}: moved values or \
+             unmatched array elements",
+            file,
+            path
+        );
+    }
     Ok(())
 }
 
-fn load_json(path: &PathBuf) -> Result<Value> {
-    let content =
-        fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
-    let json = serde_json::from_str(&content)
-        .with_context(|| format!("Failed to parse JSON from: {:?}", path))?;
-    Ok(json)
+/// Converts an exported comparison result into patch operations that turn the base
+/// document into the compare document.
+fn diff_to_operations(result: &ComparisonResult) -> Vec<PatchOperation> {
+    let mut operations = Vec::new();
+    let to_pointer = |path: &str| match result.path_style {
+        PathStyle::Dotted => path_to_pointer(path),
+        PathStyle::Pointer => path.to_string(),
+    };
+
+    let changed_values = result
+        .different_values
+        .iter()
+        .map(|d| (&d.path, &d.compare_value))
+        .chain(
+            result
+                .type_mismatches
+                .iter()
+                .map(|m| (&m.path, &m.compare_value)),
+        )
+        .chain(
+            result
+                .nullability_changes
+                .iter()
+                .map(|c| (&c.path, &c.compare_value)),
+        )
+        .chain(
+            result
+                .text_diffs
+                .iter()
+                .map(|d| (&d.path, &d.compare_value)),
+        );
+    let mut replaced = Vec::new();
+    for (path, value) in changed_values {
+        replaced.push(to_pointer(path));
+        operations.push(PatchOperation::Replace {
+            path: to_pointer(path),
+            value: value.clone(),
+        });
+    }
+
+    // remove deepest paths and highest array indices first so earlier removals
+    // don't shift the positions of later ones
+    for path in result.missing_paths.iter().rev() {
+        operations.push(PatchOperation::Remove {
+            path: to_pointer(path),
+        });
+    }
+
+    // added paths go in last and in document order, so array elements are appended at
+    // their indices; those within a replaced value came with it
+    for added in &result.added_values {
+        let path = to_pointer(&added.path);
+        let is_replaced = replaced.iter().any(|replaced| {
+            path.strip_prefix(replaced.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        if !is_replaced {
+            operations.push(PatchOperation::Add {
+                path,
+                value: added.value.clone(),
+            });
+        }
+    }
+
+    operations
+}
+
+fn load_rules(path: &PathBuf) -> Result<Rules> {
+    let rules: Rules = serde_json::from_value(load_json(path, &LoadOptions::default())?)
+        .with_context(|| format!("Invalid rules file: {:?}", path))?;
+    // collations are checked up front, before any file is compared
+    for rule in &rules.collate {
+        rule.to_path_collation()
+            .with_context(|| format!("Invalid rules file: {:?}", path))?;
+    }
+    if let Some(rule) = rules
+        .weights
+        .iter()
+        .find(|rule| !(rule.weight.is_finite() && rule.weight >= 0.0))
+    {
+        anyhow::bail!(
+            "Invalid rules file: {:?}: weight {} of {:?} must be zero or more",
+            path,
+            rule.weight,
+            rule.path
+        );
+    }
+    Ok(rules)
 }
 
 fn compare_single_file(
     args: &Args,
-    base_items: &[(String, Value)],
+    base: &BaseDocument,
+    rules: &Rules,
+    schema: Option<&Schema>,
+    script: Option<&Arc<ComparatorScript>>,
     compare_file: &PathBuf,
+    deadline: Option<Instant>,
 ) -> Result<ComparisonResult> {
-    let compare_json = load_json(compare_file)?;
+    let (mut compare_json, compare_limit_exceeded, duplicate_keys) =
+        load_limited(args, compare_file)?;
+    if let Some(root) = &args.compare_root {
+        compare_json = get_value_by_path(&compare_json, root)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "Root path {:?} not found in {}",
+                    root,
+                    display_name(compare_file)
+                )
+            })?;
+    }
+    // the documents are validated as read, before any normalization
+    let schema_violations: Vec<_> = base
+        .schema_violations
+        .iter()
+        .cloned()
+        .chain(
+            schema
+                .iter()
+                .flat_map(|schema| schema.validate(&compare_json, SchemaDocument::Compare)),
+        )
+        .collect();
+    let compare_hash = args.print_hashes.then(|| canonical_sha256(&compare_json));
+    let metadata_differences = match (
+        args.compare_metadata,
+        file_metadata(args.base_file()),
+        file_metadata(compare_file),
+    ) {
+        (true, Some(base), Some(compare)) => metadata_differences(&base, &compare),
+        _ => Vec::new(),
+    };
+    let optional_paths =
+        args.normalize_listed(&mut compare_json, base.listed_paths.as_deref(), |json| {
+            if args.ignore_key_case {
+                align_key_case(json, &base.json);
+            }
+        });
+    let lossy_paths = if args.lossy {
+        lossy_paths(&compare_json, "")
+    } else {
+        Vec::new()
+    };
+    remove_matching(&mut compare_json, &args.ignore_patterns());
+    remove_keys(&mut compare_json, &args.ignore_key);
+    // paths below order-insensitive and keyed arrays are looked up where their elements
+    // moved to
+    let mut alignment = if !args.check_values {
+        Default::default()
+    } else if let Some(listed) = &base.listed_paths {
+        // only the arrays within the listed values are aligned, where the values are listed
+        let options = args.array_match_options(rules);
+        let mut alignment = ArrayAlignment::default();
+        for path in listed {
+            if listed
+                .iter()
+                .any(|other| other != path && is_path_within(path, other))
+            {
+                continue;
+            }
+            if let (Some(compare_value), Some(base_value)) = (
+                compare_json.pointer_mut(&path_to_pointer(path)),
+                get_value_by_path(&base.json, path),
+            ) {
+                alignment.extend(align_arrays_at(compare_value, base_value, path, &options));
+            }
+        }
+        alignment
+    } else {
+        align_arrays(
+            &mut compare_json,
+            &base.json,
+            &args.array_match_options(rules),
+        )
+    };
+    // arrays outside the --select subtrees aren't compared, unless selected paths are
+    // within their elements
+    if !args.select.is_empty() {
+        alignment.diffs.retain(|diff| {
+            base.items
+                .iter()
+                .any(|(path, _)| is_path_within(path, &diff.path))
+        });
+    }
     let mut missing_paths = Vec::new();
     let mut different_values = Vec::new();
     let mut type_mismatches = Vec::new();
+    let mut nullability_changes = Vec::new();
+    let mut template_violations = Vec::new();
+    let compare_options = args.compare_options(rules, script)?;
+    let mut numeric_pairs = Vec::new();
+    let mut coerced_match_count = 0;
+    let mut warnings = Vec::new();
+    let mut unmatched_path_count = 0;
+    let mut compared_subtree: Option<String> = None;
+    let mut absent_count = 0;
+    let weights: Vec<_> = rules
+        .weights
+        .iter()
+        .map(WeightRule::to_path_weight)
+        .collect();
+    let weight = |path: &str| path_weight(&weights, path);
+    let mut unmatched_weight = 0.0;
+    let mut absent_weight = 0.0;
 
-    for (path, base_value) in base_items {
-        match get_value_by_path(&compare_json, path) {
-            None => {
-                if !args.include_parents || !is_parent_missing(&missing_paths, path) {
-                    missing_paths.push(path.clone());
+    // inputs over a size limit are only compared by their canonical hashes
+    let limit_exceeded = base.limit_exceeded.clone().or(compare_limit_exceeded);
+    let hash_only = limit_exceeded.is_some();
+    let no_rules = Rules::default();
+    let (items, rules) = if hash_only {
+        (&[][..], &no_rules)
+    } else {
+        (&base.items[..], rules)
+    };
+
+    let identical =
+        (args.quick || hash_only) && canonical_hash(&base.json) == canonical_hash(&compare_json);
+    let identical_subtrees = if args.quick && !identical && !hash_only {
+        identical_top_level_paths(&base.json, &compare_json)
+    } else {
+        Vec::new()
+    };
+
+    let text_diff_roots = if identical || hash_only {
+        Vec::new()
+    } else {
+        text_diff_roots(args, base, &compare_json)
+    };
+    let text_diffs: Vec<TextDiff> = text_diff_roots
+        .iter()
+        .filter_map(|(path, base_value, compare_value)| text_diff(path, base_value, compare_value))
+        .collect();
+
+    let progress_bar = (args.progress && std::io::stderr().is_terminal())
+        .then(|| progress_bar(display_name(compare_file)));
+    let progress = ProgressReporter::new(progress_bar, items.len());
+    let mut checked_count = items.len();
+    for (index, (path, base_value)) in items.iter().enumerate() {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            checked_count = index;
+            break;
+        }
+        progress.advance(path);
+        if identical
+            || identical_subtrees
+                .iter()
+                .chain(text_diff_roots.iter().map(|(root, _, _)| root))
+                .any(|subtree| is_path_within(path, subtree))
+        {
+            continue;
+        }
+
+        let template = if args.template {
+            parse_template(base_value)
+                .with_context(|| format!("Invalid template at path: {}", path))?
+        } else {
+            None
+        };
+        let template = match template {
+            None if args.matchers => parse_matcher(base_value)
+                .with_context(|| format!("Invalid matcher at path: {}", path))?,
+            template => template,
+        };
+
+        let Some(compare_path) = alignment.compare_path(path) else {
+            unmatched_path_count += 1;
+            unmatched_weight += weight(path);
+            continue;
+        };
+        // the members of values read by a comparator, such as money objects, were
+        // compared with them
+        if compared_subtree
+            .as_deref()
+            .is_some_and(|root| is_path_within(path, root))
+        {
+            continue;
+        }
+        let compare_value = get_value_by_path(&compare_json, &compare_path);
+        if compare_value.is_none() {
+            absent_count += 1;
+            absent_weight += weight(path);
+        }
+        let verdict = match (compare_value, script) {
+            (Some(compare_value), Some(script)) if args.check_values => {
+                script.verdict(path, base_value, compare_value)
+            }
+            _ => None,
+        };
+        if args.check_values && (base_value.is_object() || base_value.is_array()) {
+            compared_subtree = compare_value
+                .zip(compare_options.comparator_at(path))
+                .and_then(|(compare_value, comparator)| comparator.equal(base_value, compare_value))
+                .or(verdict.map(|_| true))
+                .map(|_| path.clone());
+        }
+        // the script's verdict replaces all other checks of the values
+        match (verdict, compare_value) {
+            (Some(Verdict::Different), Some(compare_value)) => {
+                different_values.push(ValueDifference {
+                    path: path.clone(),
+                    base_value: base_value.clone(),
+                    compare_value: compare_value.clone(),
+                });
+                continue;
+            }
+            (Some(Verdict::Equal), Some(compare_value)) => {
+                if is_relaxed_match(base_value, compare_value) {
+                    warnings.push(ComparisonWarning::relaxed_match(
+                        path,
+                        base_value,
+                        compare_value,
+                        MatchRule::Script,
+                    ));
+                }
+                continue;
+            }
+            (Some(Verdict::Equal | Verdict::Ignore), _) => continue,
+            _ => {}
+        }
+        match (compare_value, &template) {
+            (None, _) if !args.include_parents || !is_parent_missing(&missing_paths, path) => {
+                missing_paths.push(path.clone());
+            }
+            (Some(compare_value), Some(template)) => {
+                if let Some(reason) = template.check(compare_value) {
+                    template_violations.push(TemplateViolation {
+                        path: path.clone(),
+                        template: base_value.as_str().unwrap_or_default().to_string(),
+                        compare_value: compare_value.clone(),
+                        reason,
+                    });
                 }
             }
-            Some(compare_value) if args.check_values => {
-                if args.type_only {
+            // the templates inside a container are checked at their own paths
+            (Some(_), None) if contains_template(args, base_value) => {}
+            (Some(compare_value), None) if args.check_values => {
+                if args.delta_stats {
+                    numeric_pairs.push((path.as_str(), base_value, compare_value));
+                }
+                if base_value.is_null() != compare_value.is_null() {
+                    // null transitions are tracked separately from type and value differences
+                    nullability_changes.push(NullabilityChange {
+                        path: path.clone(),
+                        base_value: base_value.clone(),
+                        compare_value: compare_value.clone(),
+                        severity: args.nullability_severity,
+                    });
+                } else if args.type_only {
                     // only check types
                     if !same_type(base_value, compare_value) {
                         type_mismatches.push(TypeMismatch {
@@ -149,13 +3220,35 @@ fn compare_single_file(
                     }
                     // If the types are the same, we consider it a match even if values differ
                 } else {
-                    // check both type and value
-                    if !values_equal(base_value, compare_value) {
-                        different_values.push(ValueDifference {
+                    match match_rule_at(path, base_value, compare_value, &compare_options) {
+                        // check both type and value; the structure phase of --two-phase
+                        // reports changed types on their own
+                        None if args.two_phase && !same_type(base_value, compare_value) => {
+                            type_mismatches.push(TypeMismatch {
+                                path: path.clone(),
+                                base_type: get_value_type(base_value),
+                                compare_type: get_value_type(compare_value),
+                                base_value: base_value.clone(),
+                                compare_value: compare_value.clone(),
+                            });
+                        }
+                        None => different_values.push(ValueDifference {
                             path: path.clone(),
                             base_value: base_value.clone(),
                             compare_value: compare_value.clone(),
-                        });
+                        }),
+                        Some(rule) if is_relaxed_match(base_value, compare_value) => {
+                            if rule == MatchRule::Coercion {
+                                coerced_match_count += 1;
+                            }
+                            warnings.push(ComparisonWarning::relaxed_match(
+                                path,
+                                base_value,
+                                compare_value,
+                                rule,
+                            ));
+                        }
+                        Some(_) => {}
                     }
                 }
             }
@@ -163,210 +3256,403 @@ fn compare_single_file(
         }
     }
 
+    progress.finish();
+    if let Some(error) = script.and_then(|script| script.take_error()) {
+        return Err(error.context(format!("Failed to compare {:?}", compare_file)));
+    }
+
+    // the whole-document checks are skipped once the deadline has passed
+    let partial = checked_count < items.len();
+
+    let moved_values = if args.detect_moves && !partial {
+        detect_moves(&missing_paths, &base.json, &compare_json)
+    } else {
+        Vec::new()
+    };
+    let moved_path_count = missing_paths.len();
+    missing_paths.retain(|path| {
+        !moved_values.iter().any(|moved| {
+            path.strip_prefix(&moved.from)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+        })
+    });
+    let moved_path_count = moved_path_count - missing_paths.len();
+    // the structure phase of --two-phase lists the outermost path of each subtree only
+    // the compare file has, leaving out those reported as unmatched elements or moves;
+    // -v records them with their values for `apply`
+    let values_checked = args.check_values && !args.type_only;
+    let extra_paths = if (args.two_phase || values_checked)
+        && !hash_only
+        && !partial
+        && args.sample.is_none()
+        && args.paths_file.is_none()
+    {
+        let base_paths: HashSet<&str> = items.iter().map(|(path, _)| path.as_str()).collect();
+        let selectors = args.selectors()?;
+        let flatten_options = FlattenOptions {
+            sample: None,
+            ignore_keys: args.ignore_key.clone(),
+        };
+        let mut extra_paths: Vec<String> = Vec::new();
+        for (path, _) in get_all_items_with(&compare_json, String::new(), &flatten_options) {
+            let is_selected = selectors.is_empty()
+                || selectors
+                    .iter()
+                    .any(|selector| selector.matches_prefix_of(&path));
+            let is_reported = alignment
+                .diffs
+                .iter()
+                .map(|diff| &diff.path)
+                .chain(moved_values.iter().map(|moved| &moved.to))
+                .chain(extra_paths.last())
+                .any(|reported| is_path_within(&path, reported));
+            if is_selected && !is_reported && !base_paths.contains(path.as_str()) {
+                extra_paths.push(path);
+            }
+        }
+        extra_paths
+    } else {
+        Vec::new()
+    };
+    let added_values = if values_checked {
+        extra_paths
+            .iter()
+            .filter_map(|path| {
+                Some(AddedValue {
+                    path: path.clone(),
+                    value: get_value_by_path(&compare_json, path)?.clone(),
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let extra_paths = if args.two_phase {
+        extra_paths
+    } else {
+        Vec::new()
+    };
+    let rules = if partial { &no_rules } else { rules };
+    // with --paths-file only the rules about values within the listed paths are checked
+    let is_listed = |pattern: &str| {
+        base.listed_paths.as_ref().is_none_or(|listed| {
+            let pattern = PathPattern::parse(pattern);
+            listed.iter().any(|path| pattern.is_within(path))
+        })
+    };
+    let array_length_violations: Vec<_> = rules
+        .array_lengths
+        .iter()
+        .filter(|rule| is_listed(&rule.path))
+        .filter_map(|rule| rule.check(&compare_json))
+        .collect();
+    let uniqueness_violations: Vec<_> = rules
+        .unique
+        .iter()
+        .filter(|rule| is_listed(&rule.path))
+        .flat_map(|rule| rule.check(&compare_json))
+        .collect();
+    let reference_violations: Vec<_> = rules
+        .references
+        .iter()
+        .filter(|rule| is_listed(&rule.path) && is_listed(&rule.target))
+        .flat_map(|rule| rule.check(&compare_json))
+        .collect();
+
+    let distributions = args
+        .distribution
+        .iter()
+        .filter(|_| !hash_only && !partial)
+        .map(|pattern| value_distribution(pattern, &base.json, &compare_json))
+        .collect();
+    let delta_statistics =
+        (args.delta_stats && !hash_only && !partial).then(|| delta_statistics(numeric_pairs));
+
+    let diff_paths: Vec<&str> = missing_paths
+        .iter()
+        .chain(different_values.iter().map(|d| &d.path))
+        .chain(type_mismatches.iter().map(|m| &m.path))
+        .chain(nullability_changes.iter().map(|c| &c.path))
+        .chain(template_violations.iter().map(|v| &v.path))
+        .chain(text_diffs.iter().map(|d| &d.path))
+        .map(String::as_str)
+        .collect();
+    let sampled_arrays: Vec<_> = base
+        .sampled_arrays
+        .iter()
+        .map(|(path, length)| sampled_array_estimate(path, *length, args.sample, &diff_paths))
+        .collect();
+
+    let match_count = checked_count
+        - unmatched_path_count
+        - moved_path_count
+        - missing_paths.len()
+        - different_values.len()
+        - type_mismatches.len()
+        - nullability_changes.len()
+        - template_violations.len()
+        - text_diffs.len();
+    // base paths found in the compare file are paired with one compare path each, the
+    // rest of the compare paths are extra; sampled and partial comparisons don't see them
+    let ignore_patterns = args.ignore_patterns();
+    let selectors = args.selectors().unwrap_or_default();
+    let is_compared = |path: &str| {
+        (selectors.is_empty()
+            || selectors
+                .iter()
+                .any(|selector| selector.matches_prefix_of(path)))
+            && !ignore_patterns
+                .iter()
+                .any(|pattern| pattern.matches_prefix_of(path))
+    };
+    // keys that only some objects have in one schema and all of them in the other; those
+    // only one document has are missing or extra paths
+    let optionality_changes: Vec<_> = base
+        .optional_paths
+        .symmetric_difference(&optional_paths)
+        .filter(|path| !hash_only && !partial && is_compared(path))
+        .filter(|path| {
+            get_value_by_path(&base.json, path).is_some()
+                && get_value_by_path(&compare_json, path).is_some()
+        })
+        .map(|path| OptionalityChange {
+            path: path.clone(),
+            base: Optionality::of(path, &base.optional_paths),
+            compare: Optionality::of(path, &optional_paths),
+        })
+        .collect();
+    // a paths file compares too few paths to score the whole documents
+    let extra_path_count =
+        (!hash_only && !partial && args.sample.is_none() && args.paths_file.is_none()).then(|| {
+            let compare_count = count_paths(&compare_json, "", &is_compared);
+            compare_count.saturating_sub(checked_count - absent_count - unmatched_path_count)
+        });
+    let similarity = extra_path_count.map(|extra| {
+        let missing = absent_count + unmatched_path_count;
+        similarity(
+            match_count,
+            missing,
+            extra,
+            (checked_count - match_count).saturating_sub(missing),
+            &args.similarity_weights.unwrap_or_default(),
+        )
+    });
+    // the same score with the paths of each kind counted by their weights
+    let weighted_similarity = extra_path_count.filter(|_| !weights.is_empty()).map(|_| {
+        let checked: f64 = items[..checked_count]
+            .iter()
+            .map(|(path, _)| weight(path))
+            .sum();
+        let missing = absent_weight + unmatched_weight;
+        let different: f64 = different_values
+            .iter()
+            .map(|d| &d.path)
+            .chain(type_mismatches.iter().map(|m| &m.path))
+            .chain(nullability_changes.iter().map(|c| &c.path))
+            .chain(template_violations.iter().map(|v| &v.path))
+            .chain(text_diffs.iter().map(|d| &d.path))
+            .map(|path| weight(path))
+            .sum();
+        let compare_weight = weigh_paths(&compare_json, "", &|path| {
+            if is_compared(path) {
+                weight(path)
+            } else {
+                0.0
+            }
+        });
+        weighted_similarity(
+            (checked - missing - different).max(0.0),
+            missing,
+            (compare_weight - (checked - missing)).max(0.0),
+            different,
+            &args.similarity_weights.unwrap_or_default(),
+        )
+    });
+
     let statistics = Statistics {
-        total_paths_checked: base_items.len(),
+        total_paths_checked: checked_count,
         missing_count: missing_paths.len(),
+        moved_count: moved_values.len(),
+        unmatched_element_count: alignment
+            .diffs
+            .iter()
+            .map(|diff| diff.missing.len() + diff.extra.len())
+            .sum(),
         different_count: different_values.len(),
         type_mismatch_count: type_mismatches.len(),
-        match_count: base_items.len() - missing_paths.len() - different_values.len(),
+        nullability_change_count: nullability_changes.len(),
+        optionality_change_count: optionality_changes.len(),
+        schema_violation_count: schema_violations.len(),
+        duplicate_key_count: duplicate_keys.len(),
+        template_violation_count: template_violations.len(),
+        text_diff_count: text_diffs.len(),
+        array_length_violation_count: array_length_violations.len(),
+        uniqueness_violation_count: uniqueness_violations.len(),
+        reference_violation_count: reference_violations.len(),
+        ignored_count: base.ignored_count,
+        match_count,
+        coerced_match_count,
+        extra_path_count: extra_path_count.unwrap_or_default(),
+        similarity,
+        weighted_similarity,
     };
 
+    let partial_coverage = partial.then(|| checked_count as f64 * 100.0 / items.len() as f64);
+    warnings.extend(document_warnings(
+        args,
+        limit_exceeded.as_deref(),
+        partial_coverage,
+        &sampled_arrays,
+        &alignment.unaligned,
+        &lossy_paths,
+    ));
+
     Ok(ComparisonResult {
-        base_file: args.base_file.display().to_string(),
-        compare_file: compare_file.display().to_string(),
+        base_file: display_name(args.base_file()),
+        compare_file: display_name(compare_file),
+        aliases: Vec::new(),
+        identical,
+        base_hash: base.hash.clone(),
+        compare_hash,
+        metadata_differences,
+        path_style: PathStyle::Dotted,
+        values_checked,
+        base_lossy_paths: base.lossy_paths.clone(),
+        lossy_paths,
+        base_duplicate_keys: base.duplicate_keys.clone(),
+        duplicate_keys,
+        limit_exceeded,
+        partial_coverage,
+        baselined: 0,
+        extra_paths,
+        added_values,
+        filtered: !args.ignore.is_empty()
+            || !args.ignore_key.is_empty()
+            || !args.select.is_empty()
+            || args.paths_file.is_some()
+            || args.sample.is_some(),
+        schema_violations,
         missing_paths,
+        moved_values,
+        array_elements: alignment.diffs,
         different_values,
         type_mismatches,
+        nullability_changes,
+        optionality_changes,
+        template_violations,
+        text_diffs,
+        array_length_violations,
+        uniqueness_violations,
+        reference_violations,
+        distributions,
+        delta_statistics,
+        sampled_arrays,
         statistics,
+        fingerprints: Vec::new(),
+        truncated: BTreeMap::new(),
+        warnings,
     })
 }
 
-fn print_header(args: &Args, base_items: &[(String, Value)]) {
-    println!("{}", "═".repeat(80).bright_blue());
-    println!("{}", "JSON Diff Checker".bright_white().bold());
-    println!("{}", "═".repeat(80).bright_blue());
-    println!(
-        "Base file: {}",
-        args.base_file.display().to_string().bright_yellow()
-    );
-    println!(
-        "Total items: {}",
-        base_items.len().to_string().bright_green()
-    );
-    println!(
-        "Value checking: {}",
-        if args.check_values {
-            if args.type_only {
-                "Type only".bright_cyan()
-            } else {
-                "Full comparison".bright_green()
-            }
-        } else {
-            "Disabled".bright_black()
-        }
-    );
-    println!("{}\n", "─".repeat(80).bright_black());
-}
-
-fn print_detailed_results(result: &ComparisonResult, args: &Args) {
-    let path = PathBuf::from(&result.compare_file);
-    let filename = path.file_name().unwrap_or_default().to_string_lossy();
-
-    println!("{} {}", "▶".bright_blue(), filename.bright_white().bold());
-
-    if !result.missing_paths.is_empty() {
-        println!(
-            "\n  {} Missing paths ({}):",
-            "✗".red(),
-            result.missing_paths.len()
-        );
-        for path in &result.missing_paths {
-            println!("    {} {}", "└".bright_black(), path.bright_red());
-        }
-    }
-
-    if args.check_values && !args.type_only && !result.different_values.is_empty() {
-        println!(
-            "\n  {} Different values ({}):",
-            "≠".yellow(),
-            result.different_values.len()
-        );
-        for diff in &result.different_values {
-            println!("    {} {}", "└".bright_black(), diff.path.bright_yellow());
-            println!(
-                "      {} {}",
-                "expected:".bright_black(),
-                format_value(&diff.base_value).green()
-            );
-            println!(
-                "      {} {}",
-                "actual:  ".bright_black(),
-                format_value(&diff.compare_value).red()
-            );
+/// Draws the `--progress` bar of one compare file on stderr, and clears it when the file
+/// is done.
+fn progress_bar(file: String) -> ProgressCallback {
+    const WIDTH: usize = 30;
+    ProgressCallback::new(move |progress: &Progress| {
+        if progress.processed >= progress.total {
+            eprint!("\r\x1b[2K");
+            return;
         }
-    }
-
-    if args.check_values && args.type_only && !result.type_mismatches.is_empty() {
-        println!(
-            "\n  {} Type mismatches ({}):",
-            "⚠".bright_magenta(),
-            result.type_mismatches.len()
+        let filled = progress.processed * WIDTH / progress.total;
+        eprint!(
+            "\r\x1b[2K{} {}{} {:>3}% {}",
+            file,
+            "█".repeat(filled).bright_blue(),
+            "░".repeat(WIDTH - filled).bright_black(),
+            progress.processed * 100 / progress.total,
+            progress.path_prefix.bright_black()
         );
-        for mismatch in &result.type_mismatches {
-            println!(
-                "    {} {}",
-                "└".bright_black(),
-                mismatch.path.bright_magenta()
-            );
-            println!(
-                "      {} {} → {}",
-                "type:".bright_black(),
-                mismatch.base_type.green(),
-                mismatch.compare_type.red()
-            );
-        }
-    }
+    })
+}
 
-    if result.missing_paths.is_empty()
-        && result.different_values.is_empty()
-        && result.type_mismatches.is_empty()
-    {
-        println!("  {} All items match!", "✓".bright_green());
+/// Returns true if a base container holds a `--template` or `--matchers` placeholder,
+/// which its literal value would never equal.
+fn contains_template(args: &Args, value: &Value) -> bool {
+    if !args.template && !args.matchers {
+        return false;
     }
-
-    println!();
+    let mut children: Box<dyn Iterator<Item = &Value>> = match value {
+        Value::Array(arr) => Box::new(arr.iter()),
+        Value::Object(map) => Box::new(map.values()),
+        _ => return false,
+    };
+    children.any(|child| {
+        (args.template && matches!(parse_template(child), Ok(Some(_))))
+            || (args.matchers && matches!(parse_matcher(child), Ok(Some(_))))
+            || contains_template(args, child)
+    })
 }
 
-fn print_summary(result: &ComparisonResult) {
-    let path = PathBuf::from(&result.compare_file);
-    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+/// Outermost base paths selected by `--text-diff` that are also present in the compare
+/// document, with both values. Everything below them is compared as text instead.
+fn text_diff_roots<'a>(
+    args: &Args,
+    base: &'a BaseDocument,
+    compare_json: &'a Value,
+) -> Vec<(String, &'a Value, &'a Value)> {
+    let patterns: Vec<PathPattern> = args
+        .text_diff
+        .iter()
+        .map(|pattern| PathPattern::parse(pattern))
+        .collect();
+    let mut roots: Vec<(String, &Value, &Value)> = Vec::new();
 
-    let status = if result.missing_paths.is_empty()
-        && result.different_values.is_empty()
-        && result.type_mismatches.is_empty()
-    {
-        format!("{} OK", "✓").bright_green().to_string()
-    } else {
-        let mut parts = vec![];
-        if !result.missing_paths.is_empty() {
-            parts.push(
-                format!("{} missing", result.missing_paths.len())
-                    .red()
-                    .to_string(),
-            );
-        }
-        if !result.different_values.is_empty() {
-            parts.push(
-                format!("{} different", result.different_values.len())
-                    .yellow()
-                    .to_string(),
-            );
+    // items are in document order, so ancestors are seen before their descendants
+    for (path, base_value) in &base.items {
+        if !patterns.iter().any(|pattern| pattern.matches(path))
+            || roots.iter().any(|(root, _, _)| is_path_within(path, root))
+        {
+            continue;
         }
-        if !result.type_mismatches.is_empty() {
-            parts.push(
-                format!("{} type mismatch", result.type_mismatches.len())
-                    .bright_magenta()
-                    .to_string(),
-            );
+        if let Some(compare_value) = get_value_by_path(compare_json, path) {
+            roots.push((path.clone(), base_value, compare_value));
         }
-        parts.join(", ")
-    };
+    }
 
-    println!("{:<30} {}", filename, status);
+    roots
 }
 
-fn print_overall_summary(results: &[ComparisonResult]) {
-    println!("{}", "─".repeat(80).bright_black());
-    println!("{}", "Summary".bright_white().bold());
-    println!("{}", "─".repeat(80).bright_black());
-
-    let total_files = results.len();
-    let perfect_matches = results
+/// Extrapolates the share of differing elements in a sampled array from the
+/// differences found in its sampled elements.
+fn sampled_array_estimate(
+    path: &str,
+    length: usize,
+    sample: Option<usize>,
+    diff_paths: &[&str],
+) -> SampledArray {
+    let sampled = sample.unwrap_or(length).min(length);
+    let differing_elements: HashSet<usize> = diff_paths
         .iter()
-        .filter(|r| {
-            r.missing_paths.is_empty()
-                && r.different_values.is_empty()
-                && r.type_mismatches.is_empty()
+        .filter_map(|diff_path| {
+            let rest = diff_path.strip_prefix(path)?.strip_prefix('[')?;
+            rest.split(']').next()?.parse().ok()
         })
-        .count();
-    let with_missing = results
-        .iter()
-        .filter(|r| !r.missing_paths.is_empty())
-        .count();
-    let with_different = results
-        .iter()
-        .filter(|r| !r.different_values.is_empty())
-        .count();
-    let with_type_mismatch = results
-        .iter()
-        .filter(|r| !r.type_mismatches.is_empty())
-        .count();
+        .collect();
 
-    println!(
-        "Total files checked: {}",
-        total_files.to_string().bright_white()
-    );
-    println!(
-        "Perfect matches: {}",
-        perfect_matches.to_string().bright_green()
-    );
-    if with_missing > 0 {
-        println!(
-            "Files with missing paths: {}",
-            with_missing.to_string().bright_red()
-        );
-    }
-    if with_different > 0 {
-        println!(
-            "Files with different values: {}",
-            with_different.to_string().bright_yellow()
-        );
-    }
-    if with_type_mismatch > 0 {
-        println!(
-            "Files with type mismatches: {}",
-            with_type_mismatch.to_string().bright_magenta()
-        );
+    let differing = differing_elements.len();
+    let rate = if sampled == 0 {
+        0.0
+    } else {
+        differing as f64 / sampled as f64
+    };
+
+    SampledArray {
+        path: path.to_string(),
+        length,
+        sampled,
+        differing,
+        estimated_difference_rate: rate,
+        estimated_differing: (rate * length as f64).round() as usize,
     }
 }
 
@@ -378,8 +3664,7 @@ fn format_value(value: &Value) -> String {
     }
 }
 
-fn export_results(path: &PathBuf, results: &[ComparisonResult]) -> Result<()> {
-    let json = serde_json::to_string_pretty(results)?;
-    fs::write(path, json)?;
+fn export_results(path: &PathBuf, content: String) -> Result<()> {
+    fs::write(path, compress_for_path(path, content.into_bytes())?)?;
     Ok(())
 }
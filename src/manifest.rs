@@ -0,0 +1,77 @@
+//! Manifest files, listing compare files with the options each of them overrides, so
+//! differently shaped files are compared against one base in a single run.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::{load_json, Args, LoadOptions};
+use json_diff_checker::arrays::ArrayKey;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Compare files, given as a path or glob pattern relative to the manifest, and the
+/// options they're compared with in addition to those on the command line.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    /// Path of the value in the compare file that is compared with the base document
+    #[serde(default)]
+    pub root: Option<String>,
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub ignore_key: Vec<String>,
+    /// Array keys as `<path>=<field>`, like `--array-key`
+    #[serde(default)]
+    pub array_key: Vec<String>,
+}
+
+impl Manifest {
+    /// Loads a manifest, resolving the relative paths of its entries against the
+    /// manifest's directory.
+    pub fn load(path: &Path) -> Result<Manifest> {
+        let mut manifest: Manifest =
+            serde_json::from_value(load_json(&path.to_path_buf(), &LoadOptions::default())?)
+                .with_context(|| format!("Invalid manifest file: {:?}", path))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for entry in &mut manifest.files {
+            let is_url = entry.path.to_string_lossy().contains("://");
+            if entry.path.is_relative() && !is_url && !crate::is_stdin(&entry.path) {
+                entry.path = dir.join(&entry.path);
+            }
+            // array keys are checked up front, before any file is compared
+            for key in &entry.array_key {
+                ArrayKey::parse(key)
+                    .with_context(|| format!("Invalid manifest file: {:?}", path))?;
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+impl ManifestEntry {
+    /// Whether the entry ignores paths, which the base document is flattened without.
+    pub fn changes_base(&self) -> bool {
+        !self.ignore.is_empty() || !self.ignore_key.is_empty()
+    }
+
+    /// The command line options with this entry's overrides applied.
+    pub fn apply(&self, args: &Args) -> Args {
+        let mut args = args.clone();
+        args.compare_root = self.root.clone();
+        args.ignore.extend(self.ignore.iter().cloned());
+        args.ignore_key.extend(self.ignore_key.iter().cloned());
+        args.array_key.extend(
+            self.array_key
+                .iter()
+                .filter_map(|key| ArrayKey::parse(key).ok()),
+        );
+        args
+    }
+}
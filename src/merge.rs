@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::json_diff::child_path;
+
+/// A path changed differently on both sides of a three-way merge.
+/// `None` means the path is absent in that document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub path: String,
+    pub ancestor: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub merged: Value,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merges the changes made in `ours` and `theirs` relative to their common `ancestor`.
+///
+/// Objects are merged key by key, and arrays element by element when all three
+/// versions have the same length. Anything else changed on both sides is a conflict,
+/// for which the merged document keeps our version.
+pub fn three_way_merge(ancestor: &Value, ours: &Value, theirs: &Value) -> MergeResult {
+    let mut conflicts = Vec::new();
+    let merged = merge_values("", Some(ancestor), Some(ours), Some(theirs), &mut conflicts)
+        .unwrap_or(Value::Null);
+
+    MergeResult { merged, conflicts }
+}
+
+fn merge_values(
+    path: &str,
+    ancestor: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Option<Value> {
+    if ours == theirs || theirs == ancestor {
+        return ours.cloned();
+    }
+    if ours == ancestor {
+        return theirs.cloned();
+    }
+
+    match (ancestor, ours, theirs) {
+        (None | Some(Value::Object(_)), Some(Value::Object(o)), Some(Value::Object(t))) => {
+            let empty = Map::new();
+            let a = ancestor.and_then(Value::as_object).unwrap_or(&empty);
+
+            let mut merged = Map::new();
+            let keys = o
+                .keys()
+                .chain(t.keys().filter(|k| !o.contains_key(*k)))
+                .chain(
+                    a.keys()
+                        .filter(|k| !o.contains_key(*k) && !t.contains_key(*k)),
+                );
+            for key in keys {
+                let value = merge_values(
+                    &child_path(path, key),
+                    a.get(key),
+                    o.get(key),
+                    t.get(key),
+                    conflicts,
+                );
+                if let Some(value) = value {
+                    merged.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        (Some(Value::Array(a)), Some(Value::Array(o)), Some(Value::Array(t)))
+            if a.len() == o.len() && o.len() == t.len() =>
+        {
+            let merged = (0..a.len())
+                .filter_map(|i| {
+                    merge_values(
+                        &format!("{}[{}]", path, i),
+                        a.get(i),
+                        o.get(i),
+                        t.get(i),
+                        conflicts,
+                    )
+                })
+                .collect();
+            Some(Value::Array(merged))
+        }
+        _ => {
+            conflicts.push(MergeConflict {
+                path: path.to_string(),
+                ancestor: ancestor.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn changes_on_either_side_are_merged() {
+        let result = three_way_merge(
+            &json!({"a": 1, "b": 1, "c": 1}),
+            &json!({"a": 2, "b": 1, "c": 1}),
+            &json!({"a": 1, "b": 3, "d": 4}),
+        );
+        assert_eq!(result.merged, json!({"a": 2, "b": 3, "d": 4}));
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicting_changes_keep_our_version() {
+        let result = three_way_merge(
+            &json!({"a": {"x": 1}}),
+            &json!({"a": {"x": 2}}),
+            &json!({"a": {"x": 3}}),
+        );
+        assert_eq!(result.merged, json!({"a": {"x": 2}}));
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.path, "a.x");
+        assert_eq!(conflict.ancestor, Some(json!(1)));
+        assert_eq!(conflict.ours, Some(json!(2)));
+        assert_eq!(conflict.theirs, Some(json!(3)));
+    }
+
+    #[test]
+    fn removal_against_a_change_is_a_conflict() {
+        let result = three_way_merge(&json!({"a": 1}), &json!({}), &json!({"a": 2}));
+        assert_eq!(result.merged, json!({}));
+        assert_eq!(result.conflicts[0].ours, None);
+        assert_eq!(result.conflicts[0].theirs, Some(json!(2)));
+    }
+
+    #[test]
+    fn arrays_of_equal_length_merge_by_element() {
+        let result = three_way_merge(&json!([1, 2, 3]), &json!([0, 2, 3]), &json!([1, 2, 4]));
+        assert_eq!(result.merged, json!([0, 2, 4]));
+        assert!(result.conflicts.is_empty());
+
+        let result = three_way_merge(&json!([1, 2]), &json!([1, 2, 3]), &json!([0, 2]));
+        assert_eq!(result.merged, json!([1, 2, 3]));
+        assert_eq!(result.conflicts[0].path, "");
+    }
+}
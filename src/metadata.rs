@@ -0,0 +1,74 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The file-level attributes of an input on disk, compared with `--compare-metadata`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub size: u64,
+    /// Modification time as RFC 3339 in UTC, where the platform records one
+    pub modified: Option<String>,
+    /// Permission bits in octal, e.g. `644`; only on Unix
+    pub mode: Option<String>,
+}
+
+impl FileMetadata {
+    pub fn read(path: &Path) -> io::Result<FileMetadata> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .map(|time| DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true));
+        Ok(FileMetadata {
+            size: metadata.len(),
+            modified,
+            mode: mode(&metadata),
+        })
+    }
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(format!("{:o}", metadata.permissions().mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &fs::Metadata) -> Option<String> {
+    None
+}
+
+/// An attribute that differs between the base and a compare file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataDifference {
+    /// `size`, `modified` or `mode`
+    pub field: String,
+    pub base: String,
+    pub compare: String,
+}
+
+/// The attributes that differ, in the order size, modification time, permissions.
+/// Attributes only one of the files has are left out.
+pub fn metadata_differences(
+    base: &FileMetadata,
+    compare: &FileMetadata,
+) -> Vec<MetadataDifference> {
+    let size = |size: u64| Some(format!("{} bytes", size));
+    [
+        ("size", size(base.size), size(compare.size)),
+        ("modified", base.modified.clone(), compare.modified.clone()),
+        ("mode", base.mode.clone(), compare.mode.clone()),
+    ]
+    .into_iter()
+    .filter_map(|(field, base, compare)| match (base, compare) {
+        (Some(base), Some(compare)) if base != compare => Some(MetadataDifference {
+            field: field.to_string(),
+            base,
+            compare,
+        }),
+        _ => None,
+    })
+    .collect()
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::hash::canonical_hash;
+use crate::json_diff::{child_path, get_value_by_path, parse_path, path_from_parts, PathPart};
+
+/// Whether a value kept its parent and only its key changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveKind {
+    /// Same parent, different key, e.g. `user.name` → `user.fullName`
+    Renamed,
+    /// Different parent, e.g. `name` → `profile.name`
+    Moved,
+}
+
+/// A base value found unchanged at another path of the compare document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovedValue {
+    pub from: String,
+    pub to: String,
+    pub kind: MoveKind,
+}
+
+/// A missing or extra member: its path, parent path and the hash of its value.
+struct Candidate {
+    path: String,
+    parent: String,
+    hash: u64,
+}
+
+/// Pairs missing base members with members only the compare document has when both hold
+/// the same value. A pair is only made when the value is unique among the unpaired
+/// candidates: first among those of the same parent, which are renames, then among all,
+/// which are moves. Missing members are only candidates while their parent still exists
+/// in the compare document, so the members inside a moved value aren't paired again;
+/// members added inside new objects are, e.g. `title` → `profile.title`.
+///
+/// Scalars are only paired when they appear exactly once in each document, so a `true`
+/// or `0` that many members hold isn't taken for a moved value.
+pub fn detect_moves(missing_paths: &[String], base: &Value, compare: &Value) -> Vec<MovedValue> {
+    let (mut base_scalars, mut compare_scalars) = (HashMap::new(), HashMap::new());
+    count_scalars(base, &mut base_scalars);
+    count_scalars(compare, &mut compare_scalars);
+    let distinctive = |value: &Value, hash: u64| {
+        value.is_object()
+            || value.is_array()
+            || (base_scalars.get(&hash) == Some(&1) && compare_scalars.get(&hash) == Some(&1))
+    };
+
+    let missing: Vec<Candidate> = missing_paths
+        .iter()
+        .filter_map(|path| {
+            let mut parts = parse_path(path);
+            let Some(PathPart::Key(_)) = parts.pop() else {
+                return None;
+            };
+            let parent = path_from_parts(&parts);
+            get_value_by_path(compare, &parent)?;
+            let value = get_value_by_path(base, path)?;
+            let hash = canonical_hash(value);
+            distinctive(value, hash).then(|| Candidate {
+                path: path.clone(),
+                parent,
+                hash,
+            })
+        })
+        .collect();
+    if missing.is_empty() {
+        return Vec::new();
+    }
+    let mut extra = Vec::new();
+    collect_extra(compare, Some(base), "", &mut extra);
+    extra.retain(|candidate| {
+        get_value_by_path(compare, &candidate.path)
+            .is_some_and(|value| distinctive(value, candidate.hash))
+    });
+
+    let mut moves = Vec::new();
+    let mut paired_missing = vec![false; missing.len()];
+    let mut paired_extra = vec![false; extra.len()];
+    for same_parent in [true, false] {
+        // candidates grouped by value hash, and by parent for renames
+        let group = |candidate: &Candidate| {
+            let parent = if same_parent {
+                &candidate.parent[..]
+            } else {
+                ""
+            };
+            (parent.to_string(), candidate.hash)
+        };
+        let mut groups: HashMap<(String, u64), (Vec<usize>, Vec<usize>)> = HashMap::new();
+        for (index, candidate) in missing.iter().enumerate() {
+            if !paired_missing[index] {
+                groups.entry(group(candidate)).or_default().0.push(index);
+            }
+        }
+        for (index, candidate) in extra.iter().enumerate() {
+            if !paired_extra[index] {
+                groups.entry(group(candidate)).or_default().1.push(index);
+            }
+        }
+
+        for (missing_indices, extra_indices) in groups.into_values() {
+            if let ([from], [to]) = (&missing_indices[..], &extra_indices[..]) {
+                paired_missing[*from] = true;
+                paired_extra[*to] = true;
+                moves.push(MovedValue {
+                    from: missing[*from].path.clone(),
+                    to: extra[*to].path.clone(),
+                    kind: if missing[*from].parent == extra[*to].parent {
+                        MoveKind::Renamed
+                    } else {
+                        MoveKind::Moved
+                    },
+                });
+            }
+        }
+    }
+    moves.sort_by(|a, b| a.from.cmp(&b.from));
+    moves
+}
+
+/// Collects the object members of `compare` that `base` lacks, including those nested
+/// in them.
+fn collect_extra(compare: &Value, base: Option<&Value>, path: &str, extra: &mut Vec<Candidate>) {
+    match compare {
+        Value::Object(compare_map) => {
+            let base_map = base.and_then(Value::as_object);
+            for (key, child) in compare_map {
+                let child_path = child_path(path, key);
+                let base_child = base_map.and_then(|map| map.get(key));
+                if base_child.is_none() {
+                    extra.push(Candidate {
+                        path: child_path.clone(),
+                        parent: path.to_string(),
+                        hash: canonical_hash(child),
+                    });
+                }
+                collect_extra(child, base_child, &child_path, extra);
+            }
+        }
+        Value::Array(compare_arr) => {
+            let base_arr = base.and_then(Value::as_array);
+            for (index, child) in compare_arr.iter().enumerate() {
+                let base_child = base_arr.and_then(|arr| arr.get(index));
+                collect_extra(child, base_child, &format!("{}[{}]", path, index), extra);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Counts the scalars of a document by their hash.
+fn count_scalars(value: &Value, counts: &mut HashMap<u64, usize>) {
+    match value {
+        Value::Object(map) => map.values().for_each(|child| count_scalars(child, counts)),
+        Value::Array(arr) => arr.iter().for_each(|child| count_scalars(child, counts)),
+        scalar => *counts.entry(canonical_hash(scalar)).or_default() += 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn common_scalars_are_not_paired() {
+        let base = json!({
+            "user": {"name": "Ada Lovelace", "active": true},
+            "settings": {"beta": true}
+        });
+        let compare = json!({
+            "user": {"fullName": "Ada Lovelace", "enabled": true},
+            "settings": {"beta": true}
+        });
+        let missing = ["user.active".to_string(), "user.name".to_string()];
+        let moves = detect_moves(&missing, &base, &compare);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(
+            (moves[0].from.as_str(), moves[0].to.as_str()),
+            ("user.name", "user.fullName")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A single RFC 6902 JSON Patch operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Returns true if the value looks like an RFC 6902 patch document.
+pub fn is_json_patch(value: &Value) -> bool {
+    match value {
+        Value::Array(ops) => ops
+            .iter()
+            .all(|op| op.get("op").is_some_and(Value::is_string)),
+        _ => false,
+    }
+}
+
+pub fn apply_patch(document: &mut Value, operations: &[PatchOperation]) -> Result<()> {
+    for (i, operation) in operations.iter().enumerate() {
+        apply_operation(document, operation)
+            .map_err(|e| anyhow!("Patch operation {} failed: {}", i, e))?;
+    }
+    Ok(())
+}
+
+pub fn apply_operation(document: &mut Value, operation: &PatchOperation) -> Result<()> {
+    match operation {
+        PatchOperation::Add { path, value } => add(document, path, value.clone()),
+        PatchOperation::Remove { path } => remove(document, path).map(|_| ()),
+        PatchOperation::Replace { path, value } => {
+            let target = document
+                .pointer_mut(path)
+                .ok_or_else(|| anyhow!("path {:?} does not exist", path))?;
+            *target = value.clone();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                bail!("cannot move {:?} into its own child {:?}", from, path);
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| anyhow!("path {:?} does not exist", from))?;
+            add(document, path, value)
+        }
+        PatchOperation::Test { path, value } => match document.pointer(path) {
+            Some(actual) if actual == value => Ok(()),
+            Some(actual) => bail!("test failed at {:?}: found {}", path, actual),
+            None => bail!("path {:?} does not exist", path),
+        },
+    }
+}
+
+fn split_pointer(path: &str) -> Result<(&str, String)> {
+    if !path.starts_with('/') {
+        bail!("invalid JSON Pointer {:?}", path);
+    }
+    let (parent, token) = path.rsplit_once('/').unwrap_or(("", path));
+    Ok((parent, token.replace("~1", "/").replace("~0", "~")))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent, token) = split_pointer(path)?;
+    match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+        }
+        Some(Value::Array(arr)) => {
+            let index = if token == "-" {
+                arr.len()
+            } else {
+                token
+                    .parse::<usize>()
+                    .map_err(|_| anyhow!("invalid array index {:?}", token))?
+            };
+            if index > arr.len() {
+                bail!("array index {} out of bounds at {:?}", index, parent);
+            }
+            arr.insert(index, value);
+        }
+        Some(_) => bail!("parent of {:?} is not a container", path),
+        None => bail!("parent of {:?} does not exist", path),
+    }
+    Ok(())
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value> {
+    let (parent, token) = split_pointer(path)?;
+    let removed = match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(arr)) => match token.parse::<usize>() {
+            Ok(index) if index < arr.len() => Some(arr.remove(index)),
+            _ => None,
+        },
+        _ => None,
+    };
+    removed.ok_or_else(|| anyhow!("path {:?} does not exist", path))
+}
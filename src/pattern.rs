@@ -0,0 +1,291 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::json_diff::{child_path, parse_path, PathPart};
+
+/// A path with wildcards: `*` matches any object key, `[*]` any array index and
+/// `**` any number of segments, e.g. `users[*].email` or `**.updatedAt`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPattern {
+    parts: Vec<PatternPart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternPart {
+    Key(String),
+    Index(usize),
+    AnyKey,
+    AnyIndex,
+    AnyDepth,
+}
+
+impl PathPattern {
+    pub fn parse(pattern: &str) -> PathPattern {
+        let mut parts = Vec::new();
+        let mut rest = pattern;
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("[*]") {
+                parts.push(PatternPart::AnyIndex);
+                rest = after;
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix('.') {
+                rest = after;
+                continue;
+            }
+
+            // everything up to the next wildcard-free segment boundary is a plain path
+            let end = segment_end(rest);
+            let segment = &rest[..end];
+            match segment {
+                "*" => parts.push(PatternPart::AnyKey),
+                "**" => parts.push(PatternPart::AnyDepth),
+                _ => parts.extend(parse_path(segment).into_iter().map(|part| match part {
+                    PathPart::Key(key) => PatternPart::Key(key),
+                    PathPart::Index(index) => PatternPart::Index(index),
+                })),
+            }
+            rest = &rest[end..];
+        }
+
+        PathPattern { parts }
+    }
+
+    /// Parses the JSONPath subset that maps onto path patterns: `$`, `.key`, `['key']`,
+    /// `[0]`, `.*`, `[*]` and `..` for any depth, e.g. `$.spec.containers[*]`.
+    pub fn parse_jsonpath(expression: &str) -> Result<PathPattern> {
+        let Some(mut rest) = expression.trim().strip_prefix('$') else {
+            bail!("JSONPath must start with $: {}", expression);
+        };
+        let mut pattern = String::new();
+
+        while let Some(ch) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("..") {
+                pattern.push_str(".**.");
+                rest = after;
+            } else if ch == '.' {
+                pattern.push('.');
+                rest = &rest[1..];
+            } else if let Some(after) = rest.strip_prefix("['").or(rest.strip_prefix("[\"")) {
+                let quote = if rest.starts_with("['") { "']" } else { "\"]" };
+                let Some(end) = after.find(quote) else {
+                    bail!("Unterminated key in JSONPath: {}", expression);
+                };
+                pattern.push_str(&format!("[\"{}\"]", &after[..end]));
+                rest = &after[end + 2..];
+            } else if ch == '[' {
+                let Some(end) = rest.find(']') else {
+                    bail!("Unterminated index in JSONPath: {}", expression);
+                };
+                let index = &rest[1..end];
+                if index != "*" && index.parse::<usize>().is_err() {
+                    bail!(
+                        "Unsupported JSONPath selector [{}] (only keys, indices and wildcards): {}",
+                        index,
+                        expression
+                    );
+                }
+                pattern.push_str(&rest[..=end]);
+                rest = &rest[end + 1..];
+            } else {
+                pattern.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+
+        Ok(PathPattern::parse(&pattern))
+    }
+
+    pub fn has_wildcards(&self) -> bool {
+        self.parts.iter().any(|part| {
+            matches!(
+                part,
+                PatternPart::AnyKey | PatternPart::AnyIndex | PatternPart::AnyDepth
+            )
+        })
+    }
+
+    /// Returns true if the pattern matches the whole path.
+    pub fn matches(&self, path: &str) -> bool {
+        matches_parts(&self.parts, &parse_path(path))
+    }
+
+    /// Returns true if the pattern matches the path or one of its ancestors.
+    pub fn matches_prefix_of(&self, path: &str) -> bool {
+        let path = parse_path(path);
+        (0..=path.len()).any(|len| matches_parts(&self.parts, &path[..len]))
+    }
+
+    /// Returns true if every path the pattern matches is the path or lies within it, as
+    /// the pattern starts with the path's keys and indices.
+    pub fn is_within(&self, path: &str) -> bool {
+        let path = parse_path(path);
+        path.len() <= self.parts.len()
+            && path
+                .iter()
+                .zip(&self.parts)
+                .all(|(path_part, part)| match (part, path_part) {
+                    (PatternPart::Key(key), PathPart::Key(path_key)) => key == path_key,
+                    (PatternPart::Index(index), PathPart::Index(path_index)) => index == path_index,
+                    _ => false,
+                })
+    }
+
+    /// Expands the pattern against a document, returning every matching path and value.
+    pub fn select<'a>(&self, document: &'a Value) -> Vec<(String, &'a Value)> {
+        let mut selected = Vec::new();
+        select_parts(&self.parts, document, String::new(), &mut selected);
+        selected
+    }
+}
+
+impl std::fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            let dot = if i == 0 { "" } else { "." };
+            match part {
+                PatternPart::Key(key) => write!(f, "{}{}", dot, key)?,
+                PatternPart::Index(index) => write!(f, "[{}]", index)?,
+                PatternPart::AnyKey => write!(f, "{}*", dot)?,
+                PatternPart::AnyIndex => write!(f, "[*]")?,
+                PatternPart::AnyDepth => write!(f, "{}**", dot)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Removes every object member matched by one of the patterns from the document. Matched
+/// array elements are replaced by `null` instead, so the positions of their siblings and
+/// therefore their paths don't change.
+pub fn remove_matching(document: &mut Value, patterns: &[PathPattern]) {
+    if !patterns.is_empty() {
+        remove_matching_at(document, String::new(), patterns);
+    }
+}
+
+fn remove_matching_at(value: &mut Value, path: String, patterns: &[PathPattern]) {
+    let is_match = |path: &str| patterns.iter().any(|pattern| pattern.matches(path));
+
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !is_match(&child_path(&path, key)));
+            for (key, child) in map.iter_mut() {
+                remove_matching_at(child, child_path(&path, key), patterns);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, child) in arr.iter_mut().enumerate() {
+                let child_path = format!("{}[{}]", path, i);
+                if is_match(&child_path) {
+                    *child = Value::Null;
+                } else {
+                    remove_matching_at(child, child_path, patterns);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Finds the end of the next segment: a run of plain path syntax, or a single `*`/`**`.
+fn segment_end(rest: &str) -> usize {
+    if rest.starts_with("**") {
+        return 2;
+    }
+    if rest.starts_with('*') {
+        return 1;
+    }
+
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, ch) in rest.char_indices() {
+        if in_quotes {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_quotes = false;
+            }
+        } else if ch == '"' {
+            in_quotes = true;
+        } else if rest[i..].starts_with("[*]") || (ch == '.' && i > 0) {
+            return i;
+        }
+    }
+    rest.len()
+}
+
+fn matches_parts(pattern: &[PatternPart], path: &[PathPart]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(PatternPart::AnyDepth), _) => {
+            (0..=path.len()).any(|skip| matches_parts(&pattern[1..], &path[skip..]))
+        }
+        (Some(part), Some(path_part)) => {
+            let matched = match (part, path_part) {
+                (PatternPart::Key(a), PathPart::Key(b)) => a == b,
+                (PatternPart::Index(a), PathPart::Index(b)) => a == b,
+                (PatternPart::AnyKey, PathPart::Key(_)) => true,
+                (PatternPart::AnyIndex, PathPart::Index(_)) => true,
+                _ => false,
+            };
+            matched && matches_parts(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn select_parts<'a>(
+    pattern: &[PatternPart],
+    value: &'a Value,
+    path: String,
+    selected: &mut Vec<(String, &'a Value)>,
+) {
+    let Some((part, rest)) = pattern.split_first() else {
+        selected.push((path, value));
+        return;
+    };
+
+    match (part, value) {
+        (PatternPart::Key(key), Value::Object(map)) => {
+            if let Some(child) = map.get(key) {
+                select_parts(rest, child, child_path(&path, key), selected);
+            }
+        }
+        (PatternPart::Index(index), Value::Array(arr)) => {
+            if let Some(child) = arr.get(*index) {
+                select_parts(rest, child, format!("{}[{}]", path, index), selected);
+            }
+        }
+        (PatternPart::AnyKey, Value::Object(map)) => {
+            for (key, child) in map {
+                select_parts(rest, child, child_path(&path, key), selected);
+            }
+        }
+        (PatternPart::AnyIndex, Value::Array(arr)) => {
+            for (i, child) in arr.iter().enumerate() {
+                select_parts(rest, child, format!("{}[{}]", path, i), selected);
+            }
+        }
+        (PatternPart::AnyDepth, _) => {
+            select_parts(rest, value, path.clone(), selected);
+            match value {
+                Value::Object(map) => {
+                    for (key, child) in map {
+                        select_parts(pattern, child, child_path(&path, key), selected);
+                    }
+                }
+                Value::Array(arr) => {
+                    for (i, child) in arr.iter().enumerate() {
+                        select_parts(pattern, child, format!("{}[{}]", path, i), selected);
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
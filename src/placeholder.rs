@@ -0,0 +1,95 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// How `${VAR}` and `{{ var }}` placeholders in string values are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PlaceholderMode {
+    /// A placeholder matches any text, and a string that is only a placeholder any
+    /// scalar, such as the number a template renders to
+    Match,
+    /// Placeholders are replaced by the environment variable they name before comparing;
+    /// `${VAR:-default}` falls back to its default, others without a variable stay as
+    /// they are
+    Resolve,
+}
+
+fn placeholder_regex() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| {
+        Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::?-([^}]*))?\}|\{\{\s*([^{}]*?)\s*\}\}")
+            .expect("valid placeholder regex")
+    })
+}
+
+/// Whether `template` and `value` are equal with the placeholders in `template` standing
+/// for any text. `None` when `template` isn't a string with placeholders.
+fn matches_template(template: &Value, value: &Value) -> Option<bool> {
+    let template = template.as_str()?;
+    let placeholder = placeholder_regex();
+    let first = placeholder.find(template)?;
+    if first.range() == (0..template.len()) {
+        return Some(!value.is_array() && !value.is_object());
+    }
+    let value = value.as_str()?;
+
+    let mut pattern = String::from("^");
+    let mut end = 0;
+    for found in placeholder.find_iter(template) {
+        pattern.push_str(&regex::escape(&template[end..found.start()]));
+        pattern.push_str("(?s:.*?)");
+        end = found.end();
+    }
+    pattern.push_str(&regex::escape(&template[end..]));
+    pattern.push('$');
+    Some(Regex::new(&pattern).is_ok_and(|pattern| pattern.is_match(value)))
+}
+
+/// Whether two values are equal as templated and rendered text, with either of them
+/// holding the placeholders. `None` when neither is a string with placeholders.
+pub fn placeholders_match(a: &Value, b: &Value) -> Option<bool> {
+    matches_template(a, b).or_else(|| matches_template(b, a))
+}
+
+/// Replaces the placeholders in every string of `value` with what `lookup` finds for
+/// their name. A string that is only a placeholder becomes the number, boolean or null
+/// its replacement spells, like a rendered template would.
+pub fn resolve_placeholders(value: &mut Value, lookup: &impl Fn(&str) -> Option<String>) {
+    match value {
+        Value::String(s) => {
+            let placeholder = placeholder_regex();
+            let whole = placeholder
+                .find(s)
+                .is_some_and(|found| found.range() == (0..s.len()));
+            let resolved = placeholder.replace_all(s, |captures: &regex::Captures| {
+                let (name, default) = match captures.get(1) {
+                    Some(name) => (name.as_str(), captures.get(2)),
+                    None => (&captures[3], None),
+                };
+                lookup(name)
+                    .or_else(|| default.map(|default| default.as_str().to_string()))
+                    .unwrap_or_else(|| captures[0].to_string())
+            });
+            if resolved == *s {
+                return;
+            }
+            let resolved = resolved.into_owned();
+            *value = match serde_json::from_str::<Value>(&resolved) {
+                Ok(scalar @ (Value::Number(_) | Value::Bool(_) | Value::Null)) if whole => scalar,
+                _ => Value::String(resolved),
+            };
+        }
+        Value::Array(arr) => {
+            for item in arr {
+                resolve_placeholders(item, lookup);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                resolve_placeholders(item, lookup);
+            }
+        }
+        _ => {}
+    }
+}
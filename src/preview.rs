@@ -0,0 +1,159 @@
+//! The path patterns of the options and the rules file, and `--preview-rules`, which
+//! lists the base file paths each of them matches without comparing, so patterns can be
+//! checked before a green run is trusted.
+
+use anyhow::Result;
+use colored::*;
+use serde_json::Value;
+use std::process::ExitCode;
+
+use crate::{load_json, load_rules, Args, PathStyle};
+use json_diff_checker::json_diff::path_to_pointer;
+use json_diff_checker::pattern::PathPattern;
+use json_diff_checker::rules::Rules;
+
+/// A path pattern in effect, with the option or rule it comes from.
+pub struct Pattern {
+    /// The option or rule, e.g. `--ignore` or `rules tolerances[0]`
+    pub source: String,
+    pub text: String,
+    pub pattern: PathPattern,
+    /// Whether the pattern removes paths from the comparison, rather than being applied
+    /// to the paths that are compared
+    pub ignores: bool,
+}
+
+impl Pattern {
+    fn new(source: impl Into<String>, text: impl Into<String>, pattern: PathPattern) -> Pattern {
+        Pattern {
+            source: source.into(),
+            text: text.into(),
+            pattern,
+            ignores: false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.source, self.text)
+    }
+}
+
+/// The path patterns of the options and rules, in the order they're listed. Fails on a
+/// `--select` expression that isn't supported JSONPath.
+pub fn patterns(args: &Args, rules: Option<&Rules>) -> Result<Vec<Pattern>> {
+    let mut patterns = Vec::new();
+    for text in &args.ignore {
+        patterns.push(Pattern {
+            ignores: true,
+            ..Pattern::new("--ignore", text, PathPattern::parse(text))
+        });
+    }
+    for key in &args.ignore_key {
+        patterns.push(Pattern {
+            ignores: true,
+            ..Pattern::new(
+                "--ignore-key",
+                key,
+                PathPattern::parse(&format!("**.{}", key)),
+            )
+        });
+    }
+    for text in &args.select {
+        patterns.push(Pattern::new(
+            "--select",
+            text,
+            PathPattern::parse_jsonpath(text)?,
+        ));
+    }
+    for (source, texts) in [
+        ("--distribution", &args.distribution),
+        ("--text-diff", &args.text_diff),
+    ] {
+        for text in texts {
+            patterns.push(Pattern::new(source, text, PathPattern::parse(text)));
+        }
+    }
+    for key in &args.array_key {
+        patterns.push(Pattern::new(
+            "--array-key",
+            format!("{}={}", key.pattern, key.field),
+            key.pattern.clone(),
+        ));
+    }
+    for (i, text) in rules
+        .iter()
+        .flat_map(|rules| rules.ignore.iter().enumerate())
+    {
+        patterns.push(Pattern {
+            ignores: true,
+            ..Pattern::new(
+                format!("rules ignore[{}]", i),
+                text,
+                PathPattern::parse(text),
+            )
+        });
+    }
+    for (source, text) in rules.iter().flat_map(|rules| rules.paths()) {
+        patterns.push(Pattern::new(
+            format!("rules {}", source),
+            text,
+            PathPattern::parse(text),
+        ));
+    }
+    Ok(patterns)
+}
+
+/// Whether one of the ignoring patterns removes `path`, or one of its ancestors, from
+/// the comparison.
+pub fn is_ignored(patterns: &[Pattern], path: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| pattern.ignores && pattern.pattern.matches_prefix_of(path))
+}
+
+/// Lists the base file paths every pattern matches, marking those that are ignored.
+pub fn run(args: &Args) -> Result<ExitCode> {
+    let rules = args.rules.as_ref().map(load_rules).transpose()?;
+    let patterns = patterns(args, rules.as_ref())?;
+    let base_path = args.base_file();
+    let mut base: Value = load_json(base_path, &args.load_options(base_path))?;
+    args.normalize(&mut base);
+
+    println!("{}", "Rule preview".bold());
+    println!("Base file: {}", crate::display_name(base_path).yellow());
+    if patterns.is_empty() {
+        println!(
+            "\n{}",
+            "No --ignore, --select or rules file patterns".dimmed()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for pattern in &patterns {
+        let matched = pattern.pattern.select(&base);
+        println!(
+            "\n{}: {}",
+            pattern.label().bold(),
+            if matched.is_empty() {
+                "matches nothing".yellow()
+            } else {
+                format!("{} path(s)", matched.len()).cyan()
+            }
+        );
+        if args.summary {
+            continue;
+        }
+        for (path, _) in matched {
+            let shown = match args.path_style {
+                PathStyle::Dotted => path.clone(),
+                PathStyle::Pointer => path_to_pointer(&path),
+            };
+            if !pattern.ignores && is_ignored(&patterns, &path) {
+                println!("  └ {} {}", shown, "(ignored)".dimmed());
+            } else {
+                println!("  └ {}", shown);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
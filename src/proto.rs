@@ -0,0 +1,64 @@
+use serde_json::{Number, Value};
+use std::collections::HashMap;
+
+/// Options for normalizing protobuf JSON (proto3 JSON mapping) before it is compared.
+#[derive(Debug, Clone, Default)]
+pub struct ProtoJsonOptions {
+    /// Enum value names and their numbers, e.g. `STATUS_ACTIVE` → 1. Protobuf style
+    /// prefixes enum values with their enum name, so one map serves all enums.
+    pub enum_values: HashMap<String, i64>,
+}
+
+/// Rewrites protobuf JSON so the encodings the proto3 JSON mapping allows for the same
+/// message compare equal:
+///
+/// - int64 values written as strings (`"42"`) become numbers
+/// - known enum value names become their numbers
+/// - fields holding their default value (`0`, `""`, `false`, `null`, `[]`, `{}`) are
+///   removed, the same as a serializer that omits them
+pub fn normalize_proto_json(value: &mut Value, options: &ProtoJsonOptions) {
+    match value {
+        Value::String(s) => {
+            if let Some(number) = options.enum_values.get(s.as_str()) {
+                *value = Value::from(*number);
+            } else if let Some(number) = integer_string(s) {
+                *value = Value::Number(number);
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                normalize_proto_json(child, options);
+            }
+        }
+        Value::Object(map) => {
+            for child in map.values_mut() {
+                normalize_proto_json(child, options);
+            }
+            map.retain(|_, child| !is_default(child));
+        }
+        _ => {}
+    }
+}
+
+/// Parses strings that are exactly the canonical form of an integer, so values like
+/// `"007"` or `"+1"` stay strings.
+fn integer_string(s: &str) -> Option<Number> {
+    if let Ok(i) = s.parse::<i64>() {
+        (i.to_string() == s).then(|| Number::from(i))
+    } else if let Ok(u) = s.parse::<u64>() {
+        (u.to_string() == s).then(|| Number::from(u))
+    } else {
+        None
+    }
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(b) => !b,
+        Value::Number(n) => n.as_f64() == Some(0.0),
+        Value::String(s) => s.is_empty(),
+        Value::Array(arr) => arr.is_empty(),
+        Value::Object(map) => map.is_empty(),
+    }
+}
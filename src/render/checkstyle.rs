@@ -0,0 +1,44 @@
+use super::{annotations, xml_escape, FileReport, Level};
+
+/// Escapes an attribute value, keeping its line breaks, which XML would otherwise
+/// normalize to spaces.
+fn attribute(text: &str) -> String {
+    xml_escape(text)
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+}
+
+/// A Checkstyle XML report with a file element per compare file and an error per
+/// reported entry in it. Paths aren't mapped to lines, so errors are on line 1.
+pub fn report(reports: &[FileReport]) -> String {
+    let annotations = annotations(reports);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<checkstyle version=\"4.3\">\n");
+    for report in reports {
+        let errors: Vec<_> = annotations
+            .iter()
+            .filter(|annotation| std::ptr::eq(annotation.report, report))
+            .collect();
+        if errors.is_empty() {
+            xml.push_str(&format!("  <file name=\"{}\"/>\n", attribute(&report.path)));
+            continue;
+        }
+        xml.push_str(&format!("  <file name=\"{}\">\n", attribute(&report.path)));
+        for annotation in errors {
+            let severity = match annotation.level() {
+                Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Note => "info",
+            };
+            xml.push_str(&format!(
+                "    <error line=\"1\" severity=\"{}\" message=\"{}\" source=\"json_diff_checker.{}\"/>\n",
+                severity,
+                attribute(&annotation.message()),
+                annotation.category.key()
+            ));
+        }
+        xml.push_str("  </file>\n");
+    }
+    xml.push_str("</checkstyle>\n");
+    xml
+}
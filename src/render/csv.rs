@@ -0,0 +1,149 @@
+use serde_json::Value;
+
+use crate::{ComparisonResult, DiffFingerprint, PathStyle};
+use json_diff_checker::json_diff::get_value_type;
+
+const COLUMNS: [&str; 8] = [
+    "compare_file",
+    "path",
+    "kind",
+    "base_value",
+    "compare_value",
+    "base_type",
+    "compare_type",
+    "id",
+];
+
+/// Quotes a field that holds a separator, quote or line break, doubling its quotes.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// A row's value and type columns; values are written as compact JSON.
+fn value(value: Option<&Value>) -> (String, String) {
+    match value {
+        Some(value) => (value.to_string(), get_value_type(value)),
+        None => (String::new(), String::new()),
+    }
+}
+
+struct Rows<'a> {
+    csv: String,
+    file: &'a str,
+    fingerprints: &'a [DiffFingerprint],
+}
+
+impl Rows<'_> {
+    fn push(&mut self, path: &str, kind: &str, base: Option<&Value>, compare: Option<&Value>) {
+        let (base_value, base_type) = value(base);
+        let (compare_value, compare_type) = value(compare);
+        let id = self
+            .fingerprints
+            .iter()
+            .find(|fingerprint| fingerprint.kind == kind && fingerprint.path == path)
+            .map_or("", |fingerprint| fingerprint.id.as_str());
+        let cells = [
+            self.file,
+            path,
+            kind,
+            &base_value,
+            &compare_value,
+            &base_type,
+            &compare_type,
+            id,
+        ];
+        let cells: Vec<String> = cells.iter().map(|cell| field(cell)).collect();
+        self.csv.push_str(&cells.join(","));
+        self.csv.push('\n');
+    }
+}
+
+/// The differences with values as CSV rows, one per path: missing and extra paths,
+/// unmatched array elements, value differences, type mismatches, nullability changes,
+/// text diffs and template violations (with the template as the base value). Columns
+/// without a value, such as the compare value of a missing path, are left empty, as is
+/// the id of differences without a fingerprint.
+pub fn entries(results: &[ComparisonResult]) -> String {
+    let mut rows = Rows {
+        csv: format!("{}\n", COLUMNS.join(",")),
+        file: "",
+        fingerprints: &[],
+    };
+    for result in results {
+        rows.file = &result.compare_file;
+        rows.fingerprints = &result.fingerprints;
+        for path in &result.missing_paths {
+            rows.push(path, "missing", None, None);
+        }
+        for path in &result.extra_paths {
+            rows.push(path, "extra", None, None);
+        }
+        let element_path = |array: &str, index: usize| match result.path_style {
+            PathStyle::Dotted => format!("{}[{}]", array, index),
+            PathStyle::Pointer => format!("{}/{}", array, index),
+        };
+        for array in &result.array_elements {
+            for element in &array.missing {
+                let path = element_path(&array.path, element.index);
+                rows.push(&path, "unmatched_missing", Some(&element.value), None);
+            }
+            for element in &array.extra {
+                let path = element_path(&array.path, element.index);
+                rows.push(&path, "unmatched_extra", None, Some(&element.value));
+            }
+        }
+        for diff in &result.different_values {
+            rows.push(
+                &diff.path,
+                "different",
+                Some(&diff.base_value),
+                Some(&diff.compare_value),
+            );
+        }
+        for mismatch in &result.type_mismatches {
+            rows.push(
+                &mismatch.path,
+                "type_mismatch",
+                Some(&mismatch.base_value),
+                Some(&mismatch.compare_value),
+            );
+        }
+        for change in &result.nullability_changes {
+            rows.push(
+                &change.path,
+                "nullability",
+                Some(&change.base_value),
+                Some(&change.compare_value),
+            );
+        }
+        for change in &result.optionality_changes {
+            rows.push(
+                &change.path,
+                "optionality",
+                Some(&Value::from(change.base.name())),
+                Some(&Value::from(change.compare.name())),
+            );
+        }
+        for diff in &result.text_diffs {
+            rows.push(
+                &diff.path,
+                "text_diff",
+                Some(&diff.base_value),
+                Some(&diff.compare_value),
+            );
+        }
+        for violation in &result.template_violations {
+            rows.push(
+                &violation.path,
+                "template",
+                Some(&Value::from(violation.template.as_str())),
+                Some(&violation.compare_value),
+            );
+        }
+    }
+    rows.csv
+}
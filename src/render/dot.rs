@@ -0,0 +1,170 @@
+use serde_json::Value;
+
+use super::html::category_color;
+use super::Category;
+use crate::{ComparisonResult, PathStyle};
+use json_diff_checker::json_diff::{parse_path, parts_from_pointer, path_from_parts, PathPart};
+
+/// A path with differences at or below it.
+struct Node {
+    label: String,
+    path: String,
+    categories: Vec<Category>,
+    /// In the order the differences were found
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: String, path: String) -> Node {
+        Node {
+            label,
+            path,
+            categories: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, parts: &[PathPart], category: Category) {
+        let Some((part, rest)) = parts.split_first() else {
+            if !self.categories.contains(&category) {
+                self.categories.push(category);
+            }
+            return;
+        };
+        let label = match part {
+            PathPart::Key(key) => key.clone(),
+            PathPart::Index(index) => format!("[{}]", index),
+        };
+        let position = match self.children.iter().position(|child| child.label == label) {
+            Some(position) => position,
+            None => {
+                let path = path_from_parts(&[parse_path(&self.path), vec![part.clone()]].concat());
+                self.children.push(Node::new(label, path));
+                self.children.len() - 1
+            }
+        };
+        self.children[position].insert(rest, category);
+    }
+}
+
+/// Escapes text for a double-quoted DOT string.
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn child<'a>(value: Option<&'a Value>, label: &str) -> Option<&'a Value> {
+    match value? {
+        Value::Object(map) => map.get(label),
+        Value::Array(arr) => label
+            .strip_prefix('[')
+            .and_then(|label| label.strip_suffix(']'))
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| arr.get(index)),
+        _ => None,
+    }
+}
+
+struct Graph {
+    dot: String,
+    next_id: usize,
+}
+
+impl Graph {
+    fn id(&mut self) -> String {
+        self.next_id += 1;
+        format!("n{}", self.next_id)
+    }
+
+    /// Writes the node and its subtree; `base` is the node's value in the base file, to
+    /// count the children that have no differences.
+    fn node(&mut self, node: &Node, base: Option<&Value>) -> String {
+        let id = self.id();
+        let path = if node.path.is_empty() {
+            "$"
+        } else {
+            &node.path
+        };
+        let mut attributes = vec![format!("tooltip={}", quote(path))];
+        match node.categories.first() {
+            Some(&category) => {
+                let kinds: Vec<&str> = node.categories.iter().map(|c| c.key()).collect();
+                attributes.push(format!(
+                    "label={}",
+                    quote(&format!("{}\n{}", node.label, kinds.join(", ")))
+                ));
+                attributes.push(format!("fillcolor={}", quote(category_color(category))));
+                attributes.push("fontcolor=\"white\"".to_string());
+            }
+            None => attributes.push(format!("label={}", quote(&node.label))),
+        }
+        self.dot
+            .push_str(&format!("    {} [{}];\n", id, attributes.join(", ")));
+
+        let mut in_base = 0;
+        for item in &node.children {
+            let value = child(base, &item.label);
+            in_base += usize::from(value.is_some());
+            let child_id = self.node(item, value);
+            self.dot.push_str(&format!("    {} -> {};\n", id, child_id));
+        }
+        let len = match base {
+            Some(Value::Object(map)) => map.len(),
+            Some(Value::Array(arr)) => arr.len(),
+            _ => 0,
+        };
+        // the other children are folded into one node, so large documents stay readable
+        let unchanged = len.saturating_sub(in_base);
+        if unchanged > 0 && !node.children.is_empty() {
+            let unchanged_id = self.id();
+            self.dot.push_str(&format!(
+                "    {} [label=\"{} unchanged\", style=\"rounded,dashed\", fontcolor=\"#6e7781\", color=\"#6e7781\"];\n",
+                unchanged_id, unchanged
+            ));
+            self.dot
+                .push_str(&format!("    {} -> {} [style=dashed];\n", id, unchanged_id));
+        }
+        id
+    }
+}
+
+/// A Graphviz graph with a cluster per compare file: the paths with differences and
+/// their ancestors as a tree, differing nodes filled with their category's color, and
+/// the children without differences folded into an "unchanged" node per parent.
+pub fn graph(base: &Value, results: &[ComparisonResult]) -> String {
+    let mut graph = Graph {
+        dot: String::from("digraph json_diff {\n"),
+        next_id: 0,
+    };
+    graph
+        .dot
+        .push_str("  graph [rankdir=LR, fontname=\"Helvetica\"];\n");
+    graph.dot.push_str(
+        "  node [shape=box, style=\"rounded,filled\", fillcolor=\"white\", fontname=\"Helvetica\"];\n",
+    );
+    graph.dot.push_str("  edge [color=\"#6e7781\"];\n");
+    for (i, result) in results.iter().enumerate() {
+        let mut root = Node::new("$".to_string(), String::new());
+        for (category, path) in result.diff_paths() {
+            let parts = match result.path_style {
+                PathStyle::Dotted => parse_path(path),
+                PathStyle::Pointer => parts_from_pointer(path).unwrap_or_default(),
+            };
+            root.insert(&parts, category);
+        }
+        graph
+            .dot
+            .push_str(&format!("  subgraph cluster_{} {{\n", i));
+        graph
+            .dot
+            .push_str(&format!("    label={};\n", quote(&result.compare_file)));
+        graph.node(&root, Some(base));
+        graph.dot.push_str("  }\n");
+    }
+    graph.dot.push_str("}\n");
+    graph.dot
+}
@@ -0,0 +1,487 @@
+use super::{
+    Category, Entry, Field, FileReport, FileSummary, Item, Line, Renderer, Span, Status, Table,
+    Tone,
+};
+
+/// A standalone HTML page, colored by category like the terminal output.
+pub struct HtmlRenderer;
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; color: #24292f; }
+code, pre { font-family: ui-monospace, monospace; }
+dl { display: grid; grid-template-columns: max-content auto; gap: 0.2em 1em; }
+dt { font-weight: 600; }
+dd { margin: 0; }
+section.file { border-top: 1px solid #d0d7de; margin-top: 1.5em; }
+.section h3 { font-size: 1em; margin: 1em 0 0.3em; }
+.section ul { margin: 0; }
+.section .symbol, .section .subject, .category { color: var(--category); }
+.line { margin-left: 1em; }
+.label, .muted { color: #6e7781; }
+.strong { font-weight: 600; }
+.good { color: #1a7f37; }
+.info { color: #0969da; }
+.notice { color: #9a6700; }
+.base { color: #1a7f37; }
+.compare { color: #cf222e; }
+code.base mark { color: inherit; background: #aceebb; }
+code.compare mark { color: inherit; background: #ffcecb; }
+pre.diff { margin: 0.3em 0 0.3em 1em; }
+.inserted { color: #1a7f37; background: #dafbe1; }
+.deleted { color: #cf222e; background: #ffebe9; }
+table { border-collapse: collapse; margin-left: 1em; }
+th, td { padding: 0.1em 0.8em; text-align: left; }
+td.right, th.right { text-align: right; }
+.status { color: #1a7f37; font-weight: 600; }
+";
+
+pub fn category_color(category: Category) -> &'static str {
+    match category {
+        Category::Missing | Category::Extra | Category::Moved | Category::Unmatched => "#cf222e",
+        Category::Different | Category::TextDiff => "#9a6700",
+        Category::HashOnly | Category::Partial | Category::DuplicateKey => "#bc4c00",
+        Category::TypeMismatch | Category::Schema | Category::Optionality => "#8250df",
+        Category::Nullability | Category::Deltas | Category::Sampled | Category::Metadata => {
+            "#1b7c83"
+        }
+        Category::Template
+        | Category::ArrayLength
+        | Category::Duplicate
+        | Category::Reference
+        | Category::Distribution
+        | Category::Coerced
+        | Category::Similarity => "#0969da",
+        Category::Alias | Category::Baselined | Category::Lossy | Category::Relaxed => "#6e7781",
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// The span's text, with its changed characters in `mark` elements.
+fn marked(span: &Span) -> String {
+    let mut html = String::new();
+    let mut end = 0;
+    for mark in &span.marks {
+        html.push_str(&escape(&span.text[end..mark.start]));
+        html.push_str(&format!(
+            "<mark>{}</mark>",
+            escape(&span.text[mark.clone()])
+        ));
+        end = mark.end;
+    }
+    html.push_str(&escape(&span.text[end..]));
+    html
+}
+
+fn span(span: &Span) -> String {
+    let text = marked(span);
+    let class = match span.tone {
+        Tone::Plain => return text,
+        Tone::Muted => "muted",
+        Tone::Strong => "strong",
+        Tone::Good => "good",
+        Tone::Info => "info",
+        Tone::Notice => "notice",
+        Tone::Base => return format!("<code class=\"base\">{}</code>", text),
+        Tone::Compare => return format!("<code class=\"compare\">{}</code>", text),
+        Tone::Inserted => "inserted",
+        Tone::Deleted => "deleted",
+        Tone::Category(category) => {
+            return format!(
+                "<span class=\"category\" style=\"--category: {}\">{}</span>",
+                category_color(category),
+                text
+            )
+        }
+    };
+    format!("<span class=\"{}\">{}</span>", class, text)
+}
+
+fn spans(spans: &[Span]) -> String {
+    spans.iter().map(span).collect::<Vec<_>>().join(" ")
+}
+
+fn line(line: &Line) -> String {
+    match line.label {
+        Some(label) => format!(
+            "<div class=\"line\"><span class=\"label\">{}:</span> {}</div>",
+            escape(label),
+            spans(&line.spans)
+        ),
+        None => format!("<div class=\"line\">{}</div>", spans(&line.spans)),
+    }
+}
+
+fn entry(entry: &Entry) -> String {
+    let mut html = format!(
+        "<li><code class=\"subject\">{}</code>",
+        escape(&entry.subject)
+    );
+    if !entry.suffix.is_empty() {
+        html.push(' ');
+        html.push_str(&spans(&entry.suffix));
+    }
+    html.push_str(&id(entry));
+    html.push_str(&details(&entry.lines));
+    html.push_str("</li>");
+    html
+}
+
+fn id(entry: &Entry) -> String {
+    entry.id.as_ref().map_or(String::new(), |id| {
+        format!(" <span class=\"muted\">#{}</span>", escape(id))
+    })
+}
+
+fn details(lines: &[Line]) -> String {
+    // unlabeled lines are text, such as a diff, and kept preformatted
+    if !lines.is_empty() && lines.iter().all(|line| line.label.is_none()) {
+        let mut html = String::from("<pre class=\"diff\">");
+        for line in lines {
+            html.push_str(&spans(&line.spans));
+            html.push('\n');
+        }
+        html.push_str("</pre>");
+        html
+    } else {
+        lines.iter().map(line).collect()
+    }
+}
+
+fn table(table: &Table) -> String {
+    let class = |column: usize| {
+        if table.right_aligned[column] {
+            " class=\"right\""
+        } else {
+            ""
+        }
+    };
+    let mut html = String::from("<table><tr>");
+    for (column, cell) in table.header.iter().enumerate() {
+        html.push_str(&format!("<th{}>{}</th>", class(column), escape(cell)));
+    }
+    html.push_str("</tr>");
+    for row in &table.rows {
+        html.push_str("<tr>");
+        for (column, cell) in row.iter().enumerate() {
+            html.push_str(&format!("<td{}>{}</td>", class(column), span(cell)));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn field_list(fields: &[Field]) -> String {
+    let mut html = String::from("<dl>\n");
+    for field in fields {
+        html.push_str(&format!(
+            "<dt>{}</dt><dd>{}</dd>\n",
+            escape(&field.label),
+            span(&field.value)
+        ));
+    }
+    html.push_str("</dl>");
+    html
+}
+
+fn print_fields(fields: &[Field]) {
+    println!("{}", field_list(fields));
+}
+
+fn status(status: &Status) -> String {
+    match status {
+        Status::Identical => "<span class=\"status\">✓ identical</span>".to_string(),
+        Status::Ok => "<span class=\"status\">✓ OK</span>".to_string(),
+        Status::Warnings(count) => format!(
+            "<span class=\"status\">✓ OK ({} nullability warning)</span>",
+            count
+        ),
+        Status::Failed(parts) => parts.iter().map(span).collect::<Vec<_>>().join(", "),
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn header(&mut self, title: &str, fields: &[Field]) {
+        println!("<!DOCTYPE html>");
+        println!("<html lang=\"en\">");
+        println!("<head>");
+        println!("<meta charset=\"utf-8\">");
+        println!("<title>{}</title>", escape(title));
+        println!("<style>\n{}</style>", STYLE);
+        println!("</head>");
+        println!("<body>");
+        println!("<h1>{}</h1>", escape(title));
+        print_fields(fields);
+    }
+
+    fn file(&mut self, report: &FileReport) {
+        println!("<section class=\"file\">");
+        println!("<h2>▶ {}</h2>", escape(&report.name));
+        if let Some(hash) = &report.hash {
+            println!("<p class=\"muted\">sha256:{}</p>", escape(hash));
+        }
+
+        for section in &report.sections {
+            println!(
+                "<div class=\"section {}\" style=\"--category: {}\">",
+                section.category.key(),
+                category_color(section.category)
+            );
+            let title = match section.count {
+                Some(count) => format!("{} ({})", escape(&section.title), count),
+                None => escape(section.title.trim_end_matches(':')),
+            };
+            println!(
+                "<h3><span class=\"symbol\">{}</span> {}</h3>",
+                section.category.symbol(),
+                title
+            );
+            let entries: Vec<String> = section
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Entry(item) => Some(entry(item)),
+                    _ => None,
+                })
+                .collect();
+            if !entries.is_empty() {
+                println!("<ul>\n{}\n</ul>", entries.join("\n"));
+            }
+            for item in &section.items {
+                match item {
+                    Item::Entry(_) => {}
+                    Item::Line(detail) => println!("{}", line(detail)),
+                    Item::Table(item) => println!("{}", table(item)),
+                }
+            }
+            println!("</div>");
+        }
+
+        match report.status {
+            Status::Identical => {
+                println!("<p class=\"status\">✓ Identical (canonical hashes match)</p>")
+            }
+            Status::Ok => println!("<p class=\"status\">✓ All items match!</p>"),
+            _ => {}
+        }
+        println!("</section>");
+    }
+
+    fn file_summary(&mut self, summary: &FileSummary) {
+        let notes: Vec<String> = summary
+            .notes
+            .iter()
+            .map(|note| format!(" <span class=\"muted\">{}</span>", escape(note)))
+            .collect();
+        println!(
+            "<p><code>{}</code> {}{}</p>",
+            escape(&summary.name),
+            status(&summary.status),
+            notes.concat()
+        );
+    }
+
+    fn overall(&mut self, fields: &[Field]) {
+        println!("<h2>Summary</h2>");
+        print_fields(fields);
+    }
+
+    fn heading(&mut self, title: &str) {
+        println!("<h2>{}</h2>", escape(title));
+    }
+
+    fn finish(&mut self) {
+        println!("</body>");
+        println!("</html>");
+    }
+}
+
+const PAGE_STYLE: &str = "\
+.controls { display: flex; flex-wrap: wrap; gap: 0.5em 1em; align-items: center; margin: 1em 0; }
+.controls input[type=search] { padding: 0.3em 0.5em; min-width: 20em; }
+.controls label { color: var(--category); white-space: nowrap; }
+nav.tabs { display: flex; flex-wrap: wrap; gap: 0.3em; border-bottom: 1px solid #d0d7de; }
+nav.tabs button { border: 1px solid #d0d7de; border-bottom: none; background: #f6f8fa; padding: 0.4em 0.8em; cursor: pointer; font: inherit; }
+nav.tabs button.active { background: #fff; font-weight: 600; }
+section.file { border-top: none; }
+details.section { margin: 0.8em 0; }
+details.section > summary { font-weight: 600; cursor: pointer; }
+details.entry > summary { cursor: pointer; }
+li.entry { margin: 0.15em 0; }
+";
+
+const PAGE_SCRIPT: &str = "\
+const tabs = document.querySelectorAll('nav.tabs button');
+const files = document.querySelectorAll('section.file');
+for (const tab of tabs) {
+  tab.addEventListener('click', () => {
+    for (const other of tabs) other.classList.toggle('active', other === tab);
+    for (const file of files) file.hidden = file.dataset.tab !== tab.dataset.tab;
+  });
+}
+const search = document.getElementById('search');
+const filters = document.querySelectorAll('.controls input[type=checkbox]');
+function filter() {
+  const query = search.value.toLowerCase();
+  const hidden = new Set([...filters].filter(f => !f.checked).map(f => f.dataset.category));
+  for (const section of document.querySelectorAll('details.section')) {
+    let shown = 0;
+    for (const entry of section.querySelectorAll('li.entry')) {
+      entry.hidden = query !== '' && !entry.textContent.toLowerCase().includes(query);
+      shown += entry.hidden ? 0 : 1;
+    }
+    section.hidden = hidden.has(section.dataset.category) || (query !== '' && shown === 0);
+    if (query !== '' && shown > 0) section.open = true;
+  }
+}
+search.addEventListener('input', filter);
+for (const checkbox of filters) checkbox.addEventListener('change', filter);
+";
+
+/// An entry whose details fold away under its subject.
+fn collapsible_entry(item: &Entry) -> String {
+    if item.lines.is_empty() {
+        return entry(item).replacen("<li>", "<li class=\"entry\">", 1);
+    }
+    let mut html = format!(
+        "<li class=\"entry\"><details class=\"entry\"><summary><code class=\"subject\">{}</code>",
+        escape(&item.subject)
+    );
+    if !item.suffix.is_empty() {
+        html.push(' ');
+        html.push_str(&spans(&item.suffix));
+    }
+    html.push_str(&id(item));
+    html.push_str("</summary>");
+    html.push_str(&details(&item.lines));
+    html.push_str("</details></li>");
+    html
+}
+
+/// A standalone page for `--export-format html`, meant to be opened from a CI artifact:
+/// a tab per compare file, collapsible sections and entries, and a search box and
+/// category filters that work without a server.
+pub fn page(title: &str, header: &[Field], reports: &[FileReport], overall: &[Field]) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str(&format!("<style>\n{}{}</style>\n", STYLE, PAGE_STYLE));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!(
+        "<h1>{}</h1>\n{}\n",
+        escape(title),
+        field_list(header)
+    ));
+    html.push_str(&format!("<h2>Summary</h2>\n{}\n", field_list(overall)));
+
+    // a filter per category that was reported, in the order of first appearance
+    let mut categories: Vec<(Category, usize)> = Vec::new();
+    for section in reports.iter().flat_map(|report| &report.sections) {
+        let count = section.count.unwrap_or(1);
+        match categories
+            .iter_mut()
+            .find(|(category, _)| *category == section.category)
+        {
+            Some((_, total)) => *total += count,
+            None => categories.push((section.category, count)),
+        }
+    }
+    html.push_str("<div class=\"controls\">\n");
+    html.push_str(
+        "<input type=\"search\" id=\"search\" placeholder=\"Search paths and values\">\n",
+    );
+    for (category, count) in &categories {
+        html.push_str(&format!(
+            "<label style=\"--category: {}\"><input type=\"checkbox\" data-category=\"{}\" checked> {} {} ({})</label>\n",
+            category_color(*category),
+            category.key(),
+            category.symbol(),
+            category.key().replace('_', " "),
+            count
+        ));
+    }
+    html.push_str("</div>\n");
+
+    html.push_str("<nav class=\"tabs\">\n");
+    for (tab, report) in reports.iter().enumerate() {
+        html.push_str(&format!(
+            "<button data-tab=\"{}\" title=\"{}\"{}>{} {}</button>\n",
+            tab,
+            escape(&report.path),
+            if tab == 0 { " class=\"active\"" } else { "" },
+            escape(&report.name),
+            status(&report.status)
+        ));
+    }
+    html.push_str("</nav>\n");
+
+    for (tab, report) in reports.iter().enumerate() {
+        html.push_str(&format!(
+            "<section class=\"file\" data-tab=\"{}\"{}>\n",
+            tab,
+            if tab == 0 { "" } else { " hidden" }
+        ));
+        html.push_str(&format!("<h2>{}</h2>\n", escape(&report.path)));
+        if let Some(hash) = &report.hash {
+            html.push_str(&format!("<p class=\"muted\">sha256:{}</p>\n", escape(hash)));
+        }
+        for section in &report.sections {
+            let title = match section.count {
+                Some(count) => format!("{} ({})", escape(&section.title), count),
+                None => escape(section.title.trim_end_matches(':')),
+            };
+            html.push_str(&format!(
+                "<details class=\"section\" data-category=\"{}\" style=\"--category: {}\" open>\n\
+                 <summary><span class=\"symbol\">{}</span> {}</summary>\n",
+                section.category.key(),
+                category_color(section.category),
+                section.category.symbol(),
+                title
+            ));
+            let entries: Vec<String> = section
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Entry(item) => Some(collapsible_entry(item)),
+                    _ => None,
+                })
+                .collect();
+            if !entries.is_empty() {
+                html.push_str(&format!("<ul>\n{}\n</ul>\n", entries.join("\n")));
+            }
+            for item in &section.items {
+                match item {
+                    Item::Entry(_) => {}
+                    Item::Line(detail) => html.push_str(&format!("{}\n", line(detail))),
+                    Item::Table(item) => html.push_str(&format!("{}\n", table(item))),
+                }
+            }
+            html.push_str("</details>\n");
+        }
+        match report.status {
+            Status::Identical => {
+                html.push_str("<p class=\"status\">✓ Identical (canonical hashes match)</p>\n")
+            }
+            Status::Ok => html.push_str("<p class=\"status\">✓ All items match!</p>\n"),
+            _ => {}
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str(&format!("<script>\n{}</script>\n", PAGE_SCRIPT));
+    html.push_str("</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,125 @@
+use super::{xml_escape as escape, Field, FileReport, Item, Line, Section, Span, Status};
+
+fn spans(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| span.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn line(line: &Line) -> String {
+    match line.label {
+        Some(label) => format!("{}: {}", label, spans(&line.spans)),
+        None => spans(&line.spans),
+    }
+}
+
+/// The sections as plain text, for the body of a failure or the test case's output.
+fn details(sections: &[Section]) -> String {
+    let mut text = String::new();
+    for section in sections {
+        match section.count {
+            Some(count) => text.push_str(&format!("{} ({})\n", section.title, count)),
+            None => text.push_str(&format!("{}\n", section.title)),
+        }
+        for item in &section.items {
+            match item {
+                Item::Entry(entry) => {
+                    text.push_str(&format!("  {}", entry.subject));
+                    if !entry.suffix.is_empty() {
+                        text.push_str(&format!(" {}", spans(&entry.suffix)));
+                    }
+                    if let Some(id) = &entry.id {
+                        text.push_str(&format!(" #{}", id));
+                    }
+                    text.push('\n');
+                    for detail in &entry.lines {
+                        text.push_str(&format!("    {}\n", line(detail)));
+                    }
+                }
+                Item::Line(detail) => text.push_str(&format!("  {}\n", line(detail))),
+                Item::Table(table) => {
+                    text.push_str(&format!("  {}\n", table.header.join(" | ")));
+                    for row in &table.rows {
+                        let cells: Vec<&str> = row.iter().map(|cell| cell.text.as_str()).collect();
+                        text.push_str(&format!("  {}\n", cells.join(" | ")));
+                    }
+                }
+            }
+        }
+    }
+    text
+}
+
+fn testcase(report: &FileReport, classname: &str) -> String {
+    let mut xml = format!(
+        "    <testcase name=\"{}\" classname=\"{}\"",
+        escape(&report.path),
+        escape(classname)
+    );
+    let details = escape(&details(&report.sections));
+    match &report.status {
+        Status::Failed(parts) => {
+            let parts: Vec<&str> = parts.iter().map(|part| part.text.as_str()).collect();
+            xml.push_str(&format!(
+                ">\n      <failure message=\"{}\" type=\"differences\">{}</failure>\n    </testcase>\n",
+                escape(&parts.join(", ")),
+                details
+            ));
+        }
+        // warnings and notices don't fail the test case, but are kept as its output
+        _ if !report.sections.is_empty() => {
+            xml.push_str(&format!(
+                ">\n      <system-out>{}</system-out>\n    </testcase>\n",
+                details
+            ));
+        }
+        _ => xml.push_str("/>\n"),
+    }
+    xml
+}
+
+/// A JUnit XML document with a test suite for the base file and a test case per compare
+/// file, which fails with the file's differences as its details. The header fields are
+/// the suite's properties.
+pub fn report(title: &str, header: &[Field], reports: &[FileReport]) -> String {
+    let failures = reports
+        .iter()
+        .filter(|report| matches!(report.status, Status::Failed(_)))
+        .count();
+    let classname = header
+        .iter()
+        .find(|field| field.label == "Base file")
+        .map_or(title, |field| field.value.text.as_str());
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n",
+        escape(title),
+        reports.len(),
+        failures
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+        escape(classname),
+        reports.len(),
+        failures
+    ));
+    if !header.is_empty() {
+        xml.push_str("    <properties>\n");
+        for field in header {
+            xml.push_str(&format!(
+                "      <property name=\"{}\" value=\"{}\"/>\n",
+                escape(&field.label),
+                escape(&field.value.text)
+            ));
+        }
+        xml.push_str("    </properties>\n");
+    }
+    for report in reports {
+        xml.push_str(&testcase(report, classname));
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
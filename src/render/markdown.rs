@@ -0,0 +1,316 @@
+use super::{
+    Entry, Field, FileReport, FileSummary, Item, Line, Renderer, Span, Status, Table, Tone,
+};
+
+/// Markdown, e.g. for pull request comments. Values are code spans and text diffs are
+/// `diff` code blocks, which renderers such as GitHub's color.
+pub struct MarkdownRenderer;
+
+/// Writes line breaks as `\r` and `\n`, which Markdown would otherwise fold into spaces
+/// or read as the end of a table row, so values differing only in them still differ.
+fn line_breaks(text: &str) -> String {
+    text.replace('\r', "\\r").replace('\n', "\\n")
+}
+
+/// A code span, with a fence longer than any run of backticks in `text`; line breaks
+/// are written visibly.
+fn code(text: &str) -> String {
+    let text = &line_breaks(text);
+    let longest = text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    let padding = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", fence, padding, text, padding, fence)
+}
+
+/// Escapes the characters Markdown would read as formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if "\\`*_[]<>|#".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+fn span(span: &Span) -> String {
+    match span.tone {
+        _ if span.text.is_empty() => String::new(),
+        Tone::Plain => escape(&span.text),
+        Tone::Muted => format!("_{}_", escape(&span.text)),
+        Tone::Strong => format!("**{}**", escape(&span.text)),
+        _ => code(&span.text),
+    }
+}
+
+fn spans(spans: &[Span]) -> String {
+    spans.iter().map(span).collect::<Vec<_>>().join(" ")
+}
+
+fn line(line: &Line) -> String {
+    match line.label {
+        Some(label) => format!("{}: {}", label, spans(&line.spans)),
+        None => spans(&line.spans),
+    }
+}
+
+fn entry(entry: &Entry) -> String {
+    let mut markdown = format!("- {}", code(&entry.subject));
+    if !entry.suffix.is_empty() {
+        markdown.push_str(&format!(" {}", spans(&entry.suffix)));
+    }
+    if let Some(id) = &entry.id {
+        markdown.push_str(&format!(" <sub>#{}</sub>", escape(id)));
+    }
+    markdown.push('\n');
+    // unlabeled lines are text, such as a diff, and kept verbatim in a code block
+    if entry.lines.iter().all(|line| line.label.is_none()) && !entry.lines.is_empty() {
+        let text: Vec<&str> = entry
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter().map(|span| span.text.as_str()))
+            .collect();
+        let fence = if text.iter().any(|line| line.starts_with("```")) {
+            "~~~~"
+        } else {
+            "```"
+        };
+        markdown.push_str(&format!("\n  {}diff\n", fence));
+        for line in text {
+            markdown.push_str(&format!("  {}\n", line));
+        }
+        markdown.push_str(&format!("  {}\n\n", fence));
+        return markdown;
+    }
+    for detail in &entry.lines {
+        markdown.push_str(&format!("  - {}\n", line(detail)));
+    }
+    markdown
+}
+
+/// A table row, with the pipes and line breaks of its cells escaped.
+fn row(cells: impl IntoIterator<Item = String>) -> String {
+    let cells: Vec<String> = cells
+        .into_iter()
+        .map(|cell| line_breaks(&cell.replace('|', "\\|")))
+        .collect();
+    format!("| {} |\n", cells.join(" | "))
+}
+
+fn table(table: &Table) -> String {
+    let mut markdown = row(table.header.iter().map(|cell| escape(cell)));
+    markdown.push_str(&row(table
+        .right_aligned
+        .iter()
+        .map(|right| if *right { "---:" } else { "---" }.to_string())));
+    for cells in &table.rows {
+        markdown.push_str(&row(cells.iter().map(span)));
+    }
+    markdown
+}
+
+fn fields(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("- **{}:** {}\n", escape(&field.label), span(&field.value)))
+        .collect()
+}
+
+fn status(status: &Status) -> String {
+    match status {
+        Status::Identical => "✓ identical".to_string(),
+        Status::Ok => "✓ OK".to_string(),
+        Status::Warnings(count) => format!("✓ OK ({} nullability warning)", count),
+        Status::Failed(parts) => parts
+            .iter()
+            .map(|part| escape(&part.text))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+impl Renderer for MarkdownRenderer {
+    fn header(&mut self, title: &str, fields: &[Field]) {
+        println!("# {}\n", escape(title));
+        println!("{}", self::fields(fields));
+    }
+
+    fn file(&mut self, report: &FileReport) {
+        println!("## ▶ {}\n", escape(&report.name));
+        if let Some(hash) = &report.hash {
+            println!("{}\n", code(&format!("sha256:{}", hash)));
+        }
+
+        for section in &report.sections {
+            let title = match section.count {
+                Some(count) => format!("{} ({})", escape(&section.title), count),
+                None => escape(section.title.trim_end_matches(':')),
+            };
+            println!("### {} {}\n", section.category.symbol(), title);
+            if section.items.is_empty() {
+                continue;
+            }
+            for item in &section.items {
+                match item {
+                    Item::Entry(item) => print!("{}", entry(item)),
+                    Item::Line(detail) => println!("- {}", line(detail)),
+                    Item::Table(item) => print!("{}", table(item)),
+                }
+            }
+            println!();
+        }
+
+        match report.status {
+            Status::Identical => println!("**✓ Identical (canonical hashes match)**\n"),
+            Status::Ok => println!("**✓ All items match!**\n"),
+            _ => {}
+        }
+    }
+
+    fn file_summary(&mut self, summary: &FileSummary) {
+        let notes: Vec<String> = summary
+            .notes
+            .iter()
+            .map(|note| format!("_{}_", escape(note)))
+            .collect();
+        if notes.is_empty() {
+            println!("- {} {}", code(&summary.name), status(&summary.status));
+        } else {
+            println!(
+                "- {} {} {}",
+                code(&summary.name),
+                status(&summary.status),
+                notes.join(" ")
+            );
+        }
+    }
+
+    fn overall(&mut self, fields: &[Field]) {
+        println!("\n## Summary\n");
+        print!("{}", self::fields(fields));
+    }
+
+    fn heading(&mut self, title: &str) {
+        println!("## {}\n", escape(title));
+    }
+}
+
+/// The entries of a section as a table with a column per detail label, such as the
+/// expected and actual values of different values. `None` for entries with text, like
+/// a diff, which only reads as a code block.
+fn entries_table(entries: &[&Entry]) -> Option<String> {
+    let lines = entries.iter().flat_map(|entry| &entry.lines);
+    if lines.clone().any(|line| line.label.is_none()) {
+        return None;
+    }
+    let mut labels: Vec<&str> = Vec::new();
+    for label in lines.filter_map(|line| line.label) {
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    let notes = entries.iter().any(|entry| !entry.suffix.is_empty());
+    let ids = entries.iter().any(|entry| entry.id.is_some());
+
+    let mut header = vec!["Path".to_string()];
+    if notes {
+        header.push("Note".to_string());
+    }
+    header.extend(labels.iter().map(|label| {
+        let mut chars = label.chars();
+        chars.next().map_or(String::new(), |first| {
+            first.to_uppercase().chain(chars).collect::<String>()
+        })
+    }));
+    if ids {
+        header.push("ID".to_string());
+    }
+    let mut markdown = row(header.iter().map(|cell| escape(cell)));
+    markdown.push_str(&row(header.iter().map(|_| "---".to_string())));
+    for entry in entries {
+        let mut cells = vec![code(&entry.subject)];
+        if notes {
+            cells.push(spans(&entry.suffix));
+        }
+        for label in &labels {
+            let values: Vec<String> = entry
+                .lines
+                .iter()
+                .filter(|line| line.label == Some(*label))
+                .map(|line| spans(&line.spans))
+                .collect();
+            cells.push(values.join("<br>"));
+        }
+        if ids {
+            cells.push(entry.id.as_deref().map_or(String::new(), code));
+        }
+        markdown.push_str(&row(cells));
+    }
+    Some(markdown)
+}
+
+/// The report as one Markdown document for `--export-format markdown`, to paste into a
+/// pull request description or post as a comment: a status table of all compare files,
+/// then a section per file with its differences as tables.
+pub fn report(title: &str, header: &[Field], reports: &[FileReport], overall: &[Field]) -> String {
+    let mut markdown = format!("# {}\n\n{}\n", escape(title), fields(header));
+
+    markdown.push_str("## Summary\n\n");
+    markdown.push_str(&row(["File".to_string(), "Status".to_string()]));
+    markdown.push_str(&row(["---".to_string(), "---".to_string()]));
+    for report in reports {
+        markdown.push_str(&row([code(&report.path), status(&report.status)]));
+    }
+    markdown.push_str(&format!("\n{}", fields(overall)));
+
+    for report in reports {
+        markdown.push_str(&format!("\n## {}\n\n", code(&report.path)));
+        if let Some(hash) = &report.hash {
+            markdown.push_str(&format!("{}\n\n", code(&format!("sha256:{}", hash))));
+        }
+        for section in &report.sections {
+            let title = match section.count {
+                Some(count) => format!("{} ({})", escape(&section.title), count),
+                None => escape(section.title.trim_end_matches(':')),
+            };
+            markdown.push_str(&format!("### {} {}\n\n", section.category.symbol(), title));
+            let entries: Vec<&Entry> = section
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Entry(entry) => Some(entry),
+                    _ => None,
+                })
+                .collect();
+            match entries_table(&entries) {
+                Some(table) if !entries.is_empty() => markdown.push_str(&table),
+                _ => {
+                    for item in &entries {
+                        markdown.push_str(&entry(item));
+                    }
+                }
+            }
+            for item in &section.items {
+                match item {
+                    Item::Entry(_) => {}
+                    Item::Line(detail) => markdown.push_str(&format!("- {}\n", line(detail))),
+                    Item::Table(item) => markdown.push_str(&table(item)),
+                }
+            }
+            if !section.items.is_empty() {
+                markdown.push('\n');
+            }
+        }
+        match report.status {
+            Status::Identical => markdown.push_str("**✓ Identical (canonical hashes match)**\n"),
+            Status::Ok => markdown.push_str("**✓ All items match!**\n"),
+            _ => {}
+        }
+    }
+    markdown
+}
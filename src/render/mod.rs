@@ -0,0 +1,2009 @@
+//! Output rendering. Comparison results are turned into sections of categorized entries
+//! once, and every output style renders those sections, so a new kind of difference
+//! shows up the same way in all of them.
+
+mod checkstyle;
+mod csv;
+mod dot;
+mod html;
+mod junit;
+mod markdown;
+mod porcelain;
+mod rdjson;
+mod sarif;
+mod terminal;
+mod tree;
+
+use clap::ValueEnum;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::{format_value, Args, BaseDocument, ComparisonResult, PathStyle, Severity};
+use json_diff_checker::analysis::ValueDistribution;
+use json_diff_checker::arrays::ArrayOrder;
+use json_diff_checker::compare::{self, PrefixGroup};
+use json_diff_checker::json_diff::{
+    parse_path, parts_from_pointer, path_from_parts, pointer_from_parts, PathPart,
+};
+use json_diff_checker::moves::MoveKind;
+use json_diff_checker::schema::SchemaDocument;
+use json_diff_checker::text_diff::changed_ranges;
+
+/// How results are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputStyle {
+    /// Colored text for terminals
+    #[default]
+    Terminal,
+    /// Colored text for terminals with the reported paths as an indented tree
+    Tree,
+    /// Markdown, e.g. for pull request comments
+    Markdown,
+    /// A standalone HTML page
+    Html,
+    /// Tab-separated lines for scripts, one per reported entry
+    Porcelain,
+    /// The results as JSON, as `--export` writes them, e.g. to pipe into jq
+    Json,
+}
+
+impl OutputStyle {
+    /// Whether the style writes colored text, which status messages can follow on stdout.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, OutputStyle::Terminal | OutputStyle::Tree)
+    }
+}
+
+/// Creates the renderer for an output style.
+pub fn renderer(style: OutputStyle) -> Box<dyn Renderer> {
+    match style {
+        OutputStyle::Terminal => Box::new(terminal::TerminalRenderer),
+        OutputStyle::Tree => Box::new(tree::TreeRenderer),
+        OutputStyle::Markdown => Box::new(markdown::MarkdownRenderer),
+        OutputStyle::Html => Box::new(html::HtmlRenderer),
+        OutputStyle::Porcelain => Box::new(porcelain::PorcelainRenderer),
+        OutputStyle::Json => Box::new(SilentRenderer),
+    }
+}
+
+/// Writes nothing, for `--output json`, which is written from the results once they're
+/// all compared.
+struct SilentRenderer;
+
+impl Renderer for SilentRenderer {
+    fn header(&mut self, _title: &str, _fields: &[Field]) {}
+
+    fn file(&mut self, _report: &FileReport) {}
+
+    fn file_summary(&mut self, _summary: &FileSummary) {}
+
+    fn overall(&mut self, _fields: &[Field]) {}
+
+    fn heading(&mut self, _title: &str) {}
+}
+
+/// The reported entries as Checkstyle errors, for `--export-format checkstyle`.
+pub fn checkstyle_report(reports: &[FileReport]) -> String {
+    checkstyle::report(reports)
+}
+
+/// The differences with values as CSV rows, for `--export-format csv`.
+pub fn csv_entries(results: &[ComparisonResult]) -> String {
+    csv::entries(results)
+}
+
+/// The document tree of the differences as a Graphviz graph, for `--export-format dot`.
+pub fn dot_graph(base: &serde_json::Value, results: &[ComparisonResult]) -> String {
+    dot::graph(base, results)
+}
+
+/// The report as a standalone, interactive HTML page, for `--export-format html`.
+pub fn html_page(
+    title: &str,
+    header: &[Field],
+    reports: &[FileReport],
+    overall: &[Field],
+) -> String {
+    html::page(title, header, reports, overall)
+}
+
+/// The report as JUnit XML with a test case per compare file, for `--export-format junit`.
+pub fn junit_report(title: &str, header: &[Field], reports: &[FileReport]) -> String {
+    junit::report(title, header, reports)
+}
+
+/// The reported entries as reviewdog diagnostics, for `--export-format rdjson`.
+pub fn rdjson_report(reports: &[FileReport]) -> String {
+    rdjson::report(reports)
+}
+
+/// The report as a SARIF log with a result per reported entry, for `--export-format sarif`.
+pub fn sarif_report(header: &[Field], reports: &[FileReport]) -> String {
+    sarif::report(header, reports)
+}
+
+/// The report as one Markdown document, for `--export-format markdown`.
+pub fn markdown_report(
+    title: &str,
+    header: &[Field],
+    reports: &[FileReport],
+    overall: &[Field],
+) -> String {
+    markdown::report(title, header, reports, overall)
+}
+
+/// Writes the parts of a comparison report in one output style.
+pub trait Renderer {
+    /// The report title and the base file and options used.
+    fn header(&mut self, title: &str, fields: &[Field]);
+    /// The detailed results of one compare file.
+    fn file(&mut self, report: &FileReport);
+    /// The one-line result of one compare file, with `--summary`.
+    fn file_summary(&mut self, summary: &FileSummary);
+    /// Totals over all compare files.
+    fn overall(&mut self, fields: &[Field]);
+    /// Starts a part of the report, such as a phase of `--two-phase`.
+    fn heading(&mut self, title: &str);
+    /// Closes the report.
+    fn finish(&mut self) {}
+}
+
+/// A kind of reported difference or notice, styled the same way by every renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    HashOnly,
+    Alias,
+    Baselined,
+    Lossy,
+    Metadata,
+    DuplicateKey,
+    Schema,
+    Missing,
+    Extra,
+    Moved,
+    Unmatched,
+    Different,
+    TypeMismatch,
+    Nullability,
+    Optionality,
+    Template,
+    TextDiff,
+    ArrayLength,
+    Duplicate,
+    Reference,
+    Distribution,
+    Deltas,
+    Sampled,
+    Partial,
+    Coerced,
+    Relaxed,
+    Similarity,
+}
+
+impl Category {
+    /// Stable identifier, used for porcelain lines and HTML classes.
+    pub fn key(self) -> &'static str {
+        match self {
+            Category::HashOnly => "hash_only",
+            Category::Alias => "alias",
+            Category::Baselined => "baselined",
+            Category::Lossy => "lossy",
+            Category::Metadata => "metadata",
+            Category::DuplicateKey => "duplicate_key",
+            Category::Schema => "schema",
+            Category::Missing => "missing",
+            Category::Extra => "extra",
+            Category::Moved => "moved",
+            Category::Unmatched => "unmatched",
+            Category::Different => "different",
+            Category::TypeMismatch => "type_mismatch",
+            Category::Nullability => "nullability",
+            Category::Optionality => "optionality",
+            Category::Template => "template",
+            Category::TextDiff => "text_diff",
+            Category::ArrayLength => "array_length",
+            Category::Duplicate => "duplicate",
+            Category::Reference => "reference",
+            Category::Distribution => "distribution",
+            Category::Deltas => "deltas",
+            Category::Sampled => "sampled",
+            Category::Partial => "partial",
+            Category::Coerced => "coerced",
+            Category::Relaxed => "relaxed",
+            Category::Similarity => "similarity",
+        }
+    }
+
+    /// Whether the entries of the category are reported at a path, such as missing
+    /// paths. The others, such as duplicates of a rule's pattern, name something else.
+    pub fn has_path(self) -> bool {
+        matches!(
+            self,
+            Category::Lossy
+                | Category::DuplicateKey
+                | Category::Schema
+                | Category::Missing
+                | Category::Extra
+                | Category::Moved
+                | Category::Unmatched
+                | Category::Different
+                | Category::TypeMismatch
+                | Category::Nullability
+                | Category::Optionality
+                | Category::Template
+                | Category::TextDiff
+                | Category::ArrayLength
+                | Category::Reference
+                | Category::Sampled
+        )
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Category::HashOnly | Category::Partial => "⚠",
+            Category::Alias => "⧉",
+            Category::Baselined => "⊙",
+            Category::Lossy => "�",
+            Category::Metadata => "⚙",
+            Category::DuplicateKey => "≡",
+            Category::Schema => "⚑",
+            Category::Missing => "✗",
+            Category::Extra => "+",
+            Category::Moved => "↪",
+            Category::Unmatched => "⇄",
+            Category::Different | Category::TextDiff => "≠",
+            Category::TypeMismatch => "⚠",
+            Category::Nullability => "∅",
+            Category::Optionality => "?",
+            Category::Template => "⊘",
+            Category::ArrayLength => "↔",
+            Category::Duplicate => "⧉",
+            Category::Reference => "↗",
+            Category::Distribution => "▤",
+            Category::Deltas => "Δ",
+            Category::Sampled | Category::Coerced | Category::Similarity => "≈",
+            Category::Relaxed => "≃",
+        }
+    }
+}
+
+/// The role of a piece of text, which renderers map to colors or markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Plain,
+    Muted,
+    Strong,
+    Good,
+    Info,
+    Notice,
+    /// A value from the base file
+    Base,
+    /// A value from a compare file
+    Compare,
+    /// An added line of a text diff
+    Inserted,
+    /// A removed line of a text diff
+    Deleted,
+    Category(Category),
+}
+
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub text: String,
+    pub tone: Tone,
+    /// Byte ranges of the text that changed, e.g. in a string that differs only in part
+    pub marks: Vec<Range<usize>>,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, tone: Tone) -> Span {
+        Span {
+            text: text.into(),
+            tone,
+            marks: Vec::new(),
+        }
+    }
+}
+
+/// A labeled value of the header or the overall summary, e.g. `Base file: base.json`.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub label: String,
+    pub value: Span,
+}
+
+impl Field {
+    fn new(label: impl Into<String>, value: impl Into<String>, tone: Tone) -> Field {
+        Field {
+            label: label.into(),
+            value: Span::new(value, tone),
+        }
+    }
+}
+
+/// A line of detail, e.g. `expected: "a"`.
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub label: Option<&'static str>,
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    fn labeled(label: &'static str, spans: Vec<Span>) -> Line {
+        Line {
+            label: Some(label),
+            spans,
+        }
+    }
+
+    fn unlabeled(spans: Vec<Span>) -> Line {
+        Line { label: None, spans }
+    }
+}
+
+/// One reported path, or other subject, with its details.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub subject: String,
+    /// Shown after the subject, e.g. where a value moved to
+    pub suffix: Vec<Span>,
+    pub lines: Vec<Line>,
+    /// The fingerprint of the difference, the same in every run that reports it
+    pub id: Option<String>,
+    /// The pretty-printed values to show side by side instead of the lines, when asked to
+    pub columns: Option<Columns>,
+}
+
+/// An expected and an actual value, pretty-printed for `--side-by-side`.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    pub base: String,
+    pub compare: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub header: Vec<&'static str>,
+    pub right_aligned: Vec<bool>,
+    pub rows: Vec<Vec<Span>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Item {
+    Entry(Entry),
+    Line(Line),
+    Table(Table),
+}
+
+/// The entries of one category, e.g. all missing paths of a compare file.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub category: Category,
+    pub title: String,
+    /// Shown after the title when the section lists countable entries
+    pub count: Option<usize>,
+    pub items: Vec<Item>,
+}
+
+impl Section {
+    fn new(category: Category, title: impl Into<String>) -> Section {
+        Section {
+            category,
+            title: title.into(),
+            count: None,
+            items: Vec::new(),
+        }
+    }
+
+    fn counted(category: Category, title: &str, count: usize, items: Vec<Item>) -> Section {
+        Section {
+            category,
+            title: title.to_string(),
+            count: Some(count),
+            items,
+        }
+    }
+}
+
+/// The overall outcome for one compare file.
+#[derive(Debug, Clone)]
+pub enum Status {
+    Identical,
+    Ok,
+    /// No failures, but nullability changes reported as warnings
+    Warnings(usize),
+    /// The failures, e.g. `3 missing`
+    Failed(Vec<Span>),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub name: String,
+    /// The compare file as given, where `name` is only its file name
+    pub path: String,
+    /// How the paths of the entries are written
+    pub path_style: PathStyle,
+    pub hash: Option<String>,
+    pub sections: Vec<Section>,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileSummary {
+    pub name: String,
+    pub path: String,
+    pub status: Status,
+    /// Remarks such as `(sampled)` or the compare file's hash
+    pub notes: Vec<String>,
+}
+
+impl FileReport {
+    /// The parts of the path an entry is reported at, for the categories that have one.
+    pub fn path_parts(&self, entry: &Entry) -> Vec<PathPart> {
+        match (self.path_style, entry.subject.as_str()) {
+            (_, "(root)") => Vec::new(),
+            (PathStyle::Dotted, path) => parse_path(path),
+            (PathStyle::Pointer, path) => parts_from_pointer(path).unwrap_or_default(),
+        }
+    }
+}
+
+/// How much a reported entry matters to the formats that annotate entries one by one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+/// One reported entry with its compare file and section, for the formats that annotate
+/// entries one by one, such as SARIF.
+pub struct Annotation<'a> {
+    pub report: &'a FileReport,
+    pub category: Category,
+    /// The section title, e.g. `Missing paths`
+    pub title: &'a str,
+    pub entry: &'a Entry,
+}
+
+impl Annotation<'_> {
+    /// Differences are errors, nullability changes are warnings when
+    /// `--nullability-severity warning` lets the file pass, and notices such as
+    /// baselined or coerced values are notes.
+    pub fn level(&self) -> Level {
+        match self.category {
+            Category::DuplicateKey
+            | Category::Schema
+            | Category::Missing
+            | Category::Extra
+            | Category::Moved
+            | Category::Unmatched
+            | Category::Different
+            | Category::TypeMismatch
+            | Category::Optionality
+            | Category::Template
+            | Category::TextDiff
+            | Category::ArrayLength
+            | Category::Duplicate
+            | Category::Reference => Level::Error,
+            Category::Nullability => match &self.report.status {
+                Status::Failed(parts)
+                    if parts
+                        .iter()
+                        .any(|part| part.tone == Tone::Category(Category::Nullability)) =>
+                {
+                    Level::Error
+                }
+                _ => Level::Warning,
+            },
+            _ => Level::Note,
+        }
+    }
+
+    /// The entry as plain text: the section title, subject and fingerprint, then a line
+    /// per detail.
+    pub fn message(&self) -> String {
+        let text = |spans: &[Span]| {
+            spans
+                .iter()
+                .map(|span| span.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut message = format!("{}: {}", self.title, self.entry.subject);
+        if !self.entry.suffix.is_empty() {
+            message.push_str(&format!(" {}", text(&self.entry.suffix)));
+        }
+        if let Some(id) = &self.entry.id {
+            message.push_str(&format!(" #{}", id));
+        }
+        for line in &self.entry.lines {
+            match line.label {
+                Some(label) => message.push_str(&format!("\n{}: {}", label, text(&line.spans))),
+                None => message.push_str(&format!("\n{}", text(&line.spans))),
+            }
+        }
+        message
+    }
+}
+
+/// Escapes text for XML attributes and content. Control characters XML 1.0 doesn't
+/// allow are written as `\u{..}` instead.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// The entries of all reports, in report order.
+pub fn annotations(reports: &[FileReport]) -> Vec<Annotation<'_>> {
+    let mut annotations = Vec::new();
+    for report in reports {
+        for section in &report.sections {
+            for item in &section.items {
+                if let Item::Entry(entry) = item {
+                    annotations.push(Annotation {
+                        report,
+                        category: section.category,
+                        title: section.title.trim_end_matches(':'),
+                        entry,
+                    });
+                }
+            }
+        }
+    }
+    annotations
+}
+
+fn file_name(result: &ComparisonResult) -> String {
+    PathBuf::from(&result.compare_file)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// The base file and the options that change what is compared.
+pub fn header_fields(args: &Args, base: &BaseDocument) -> Vec<Field> {
+    let mut fields = vec![Field::new(
+        "Base file",
+        crate::display_name(args.base_file()),
+        Tone::Notice,
+    )];
+    if let Some(hash) = &base.hash {
+        fields.push(Field::new(
+            "Base hash",
+            format!("sha256:{}", hash),
+            Tone::Muted,
+        ));
+    }
+    if let Some(reason) = &base.limit_exceeded {
+        fields.push(Field::new(
+            "Size limit",
+            format!("{}, comparing canonical hashes only", reason),
+            Tone::Notice,
+        ));
+    }
+    if !base.lossy_paths.is_empty() {
+        fields.push(Field::new(
+            "Lossy strings",
+            base.lossy_paths.join(", "),
+            Tone::Muted,
+        ));
+    }
+    if !base.duplicate_keys.is_empty() {
+        fields.push(Field::new(
+            "Duplicate keys",
+            base.duplicate_keys.join(", "),
+            Tone::Notice,
+        ));
+    }
+    fields.push(Field::new(
+        "Total items",
+        base.items.len().to_string(),
+        Tone::Good,
+    ));
+    fields.push(match (args.check_values, args.type_only) {
+        (true, true) => Field::new("Value checking", "Type only", Tone::Info),
+        (true, false) => Field::new("Value checking", "Full comparison", Tone::Good),
+        (false, _) => Field::new("Value checking", "Disabled", Tone::Muted),
+    });
+    if let Some(mode) = args.mode.and_then(|mode| mode.to_possible_value()) {
+        fields.push(Field::new("Mode", mode.get_name(), Tone::Info));
+    }
+    if let Some(schema) = &args.schema {
+        fields.push(Field::new(
+            "Schema",
+            crate::display_name(schema),
+            Tone::Info,
+        ));
+    }
+    if args.template {
+        fields.push(Field::new("Type templates", "Enabled", Tone::Good));
+    }
+    if args.matchers {
+        fields.push(Field::new("Matchers", "Enabled", Tone::Good));
+    }
+    if args.coerce {
+        fields.push(Field::new("Coercion", "Enabled", Tone::Good));
+    }
+    if args.ignore_key_case {
+        fields.push(Field::new("Key case", "Ignored", Tone::Good));
+    }
+    if let Some(order) = Some(args.array_order)
+        .filter(|order| *order != ArrayOrder::Index)
+        .and_then(|order| order.to_possible_value())
+    {
+        fields.push(Field::new("Array order", order.get_name(), Tone::Info));
+    }
+    if let Some(sample) = args.sample {
+        fields.push(Field::new(
+            "Sampling",
+            format!("{} elements of larger arrays", sample),
+            Tone::Info,
+        ));
+    }
+    if !args.normalize_strings.is_empty() {
+        let normalizations: Vec<_> = args
+            .normalize_strings
+            .iter()
+            .filter_map(|normalization| normalization.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        fields.push(Field::new(
+            "Normalized strings",
+            normalizations.join(", "),
+            Tone::Info,
+        ));
+    }
+    if !args.select.is_empty() {
+        fields.push(Field::new("Selected", args.select.join(", "), Tone::Info));
+    }
+    if !args.ignore.is_empty() || !args.ignore_key.is_empty() {
+        let ignored: Vec<String> = args
+            .ignore
+            .iter()
+            .cloned()
+            .chain(args.ignore_key.iter().map(|key| format!("key {}", key)))
+            .collect();
+        fields.push(Field::new(
+            "Ignored",
+            format!(
+                "{} paths matching {}",
+                base.ignored_count,
+                ignored.join(", ")
+            ),
+            Tone::Muted,
+        ));
+    }
+    fields
+}
+
+/// The header of `aggregate`: the exports merged and the base files of their results.
+pub fn aggregate_fields(exports: usize, results: &[ComparisonResult]) -> Vec<Field> {
+    let mut base_files: Vec<&str> = Vec::new();
+    for result in results {
+        if !base_files.contains(&result.base_file.as_str()) {
+            base_files.push(&result.base_file);
+        }
+    }
+    vec![
+        Field::new("Exports merged", exports.to_string(), Tone::Plain),
+        Field::new("Base file", base_files.join(", "), Tone::Notice),
+    ]
+}
+
+fn status(result: &ComparisonResult) -> Status {
+    if result.identical && result.is_perfect_match() {
+        return Status::Identical;
+    } else if result.is_perfect_match() {
+        return Status::Ok;
+    } else if !result.has_failures() {
+        return Status::Warnings(result.nullability_changes.len());
+    }
+
+    let statistics = &result.statistics;
+    let mut parts = vec![];
+    if let Some(coverage) = result.partial_coverage {
+        parts.push(Span::new(
+            format!("partial, {:.1}% covered", coverage),
+            Tone::Category(Category::Partial),
+        ));
+    }
+    if result.limit_exceeded.is_some() && !result.identical {
+        parts.push(Span::new("hash differs", Tone::Compare));
+    }
+    let counts = [
+        (
+            result.duplicate_keys.len(),
+            "duplicate key",
+            Category::DuplicateKey,
+        ),
+        (
+            result.schema_violations.len(),
+            "schema violation",
+            Category::Schema,
+        ),
+        (result.missing_paths.len(), "missing", Category::Missing),
+        (result.extra_paths.len(), "extra", Category::Extra),
+        (result.moved_values.len(), "moved", Category::Moved),
+        (
+            if result.array_elements.is_empty() {
+                0
+            } else {
+                statistics.unmatched_element_count
+            },
+            "unmatched",
+            Category::Unmatched,
+        ),
+        (
+            result.different_values.len(),
+            "different",
+            Category::Different,
+        ),
+        (
+            result.type_mismatches.len(),
+            "type mismatch",
+            Category::TypeMismatch,
+        ),
+        (
+            result.optionality_changes.len(),
+            "optionality change",
+            Category::Optionality,
+        ),
+        (
+            result.template_violations.len(),
+            "template violation",
+            Category::Template,
+        ),
+        (result.text_diffs.len(), "text diff", Category::TextDiff),
+        (
+            result.array_length_violations.len(),
+            "array length violation",
+            Category::ArrayLength,
+        ),
+        (
+            result.uniqueness_violations.len(),
+            "duplicate",
+            Category::Duplicate,
+        ),
+        (
+            result.reference_violations.len(),
+            "broken reference",
+            Category::Reference,
+        ),
+        (
+            result.nullability_changes.len(),
+            "nullability",
+            Category::Nullability,
+        ),
+    ];
+    for (mut count, label, category) in counts {
+        // unmatched elements are counted in the statistics, whatever arrays were left out
+        if category != Category::Unmatched {
+            count += result.truncated_count(category);
+        }
+        if count > 0 {
+            parts.push(Span::new(
+                format!("{} {}", count, label),
+                Tone::Category(category),
+            ));
+        }
+    }
+    Status::Failed(parts)
+}
+
+/// The one-line result of a compare file.
+pub fn file_summary(result: &ComparisonResult) -> FileSummary {
+    let mut notes = Vec::new();
+    if let Some(similarity) = result
+        .statistics
+        .similarity
+        .filter(|_| result.has_failures())
+    {
+        match result.statistics.weighted_similarity {
+            Some(weighted) => notes.push(format!(
+                "({:.1}% similar, {:.1}% weighted)",
+                similarity, weighted
+            )),
+            None => notes.push(format!("({:.1}% similar)", similarity)),
+        }
+    }
+    if !result.sampled_arrays.is_empty() {
+        notes.push("(sampled)".to_string());
+    }
+    if result.limit_exceeded.is_some() {
+        notes.push("(size limit, hash only)".to_string());
+    }
+    if !result.lossy_paths.is_empty() {
+        notes.push(format!("({} lossy)", result.lossy_paths.len()));
+    }
+    if !result.metadata_differences.is_empty() {
+        let fields: Vec<&str> = result
+            .metadata_differences
+            .iter()
+            .map(|difference| difference.field.as_str())
+            .collect();
+        notes.push(format!("({} differ)", fields.join(", ")));
+    }
+    if !result.warnings.is_empty() {
+        notes.push(format!("({} relaxed)", result.warnings.len()));
+    }
+    if result.baselined > 0 {
+        notes.push(format!("({} baselined)", result.baselined));
+    }
+    if !result.aliases.is_empty() {
+        notes.push(format!("(also passed as {})", result.aliases.join(", ")));
+    }
+    if let Some(hash) = &result.compare_hash {
+        notes.push(format!("sha256:{}", hash));
+    }
+    FileSummary {
+        name: file_name(result),
+        path: result.compare_file.clone(),
+        status: status(result),
+        notes,
+    }
+}
+
+fn entry(subject: impl Into<String>, suffix: Vec<Span>, lines: Vec<Line>) -> Item {
+    Item::Entry(Entry {
+        subject: subject.into(),
+        suffix,
+        lines,
+        id: None,
+        columns: None,
+    })
+}
+
+fn value(value: &serde_json::Value, tone: Tone) -> Span {
+    Span::new(format_value(value), tone)
+}
+
+/// The base and compare values of a difference; strings that differ only in part have
+/// the changed characters marked.
+fn changed_values(base: &serde_json::Value, compare: &serde_json::Value) -> (Span, Span) {
+    let (mut base_span, mut compare_span) =
+        (value(base, Tone::Base), value(compare, Tone::Compare));
+    if let (Some(base), Some(compare)) = (base.as_str(), compare.as_str()) {
+        if let Some(ranges) = changed_ranges(base, compare) {
+            // past the opening quote
+            let shift = |marks: Vec<Range<usize>>| {
+                marks
+                    .into_iter()
+                    .map(|mark| mark.start + 1..mark.end + 1)
+                    .collect()
+            };
+            base_span.marks = shift(ranges.base);
+            compare_span.marks = shift(ranges.compare);
+        }
+    }
+    (base_span, compare_span)
+}
+
+/// The detailed results of a compare file, one section per reported category.
+pub fn file_report(result: &ComparisonResult, args: &Args) -> FileReport {
+    let mut sections = Vec::new();
+
+    if let Some(reason) = &result.limit_exceeded {
+        let mut section = Section::new(
+            Category::HashOnly,
+            format!("{}, compared by canonical hash only", reason),
+        );
+        if !result.identical {
+            section.items.push(Item::Line(Line::unlabeled(vec![
+                Span::new("✗", Tone::Compare),
+                Span::new("Documents differ", Tone::Plain),
+            ])));
+        }
+        sections.push(section);
+    }
+
+    if !result.metadata_differences.is_empty() {
+        sections.push(Section::counted(
+            Category::Metadata,
+            "File metadata differences",
+            result.metadata_differences.len(),
+            result
+                .metadata_differences
+                .iter()
+                .map(|difference| {
+                    entry(
+                        &difference.field,
+                        vec![],
+                        vec![
+                            Line::labeled(
+                                "expected",
+                                vec![Span::new(&difference.base, Tone::Base)],
+                            ),
+                            Line::labeled(
+                                "actual",
+                                vec![Span::new(&difference.compare, Tone::Compare)],
+                            ),
+                        ],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if result.baselined > 0 {
+        sections.push(Section::new(
+            Category::Baselined,
+            format!(
+                "{} accepted difference{} left out by the baseline",
+                result.baselined,
+                if result.baselined == 1 { "" } else { "s" }
+            ),
+        ));
+    }
+
+    if !result.aliases.is_empty() {
+        sections.push(Section::counted(
+            Category::Alias,
+            "Also passed as, compared once",
+            result.aliases.len(),
+            result
+                .aliases
+                .iter()
+                .map(|alias| entry(alias, vec![], vec![]))
+                .collect(),
+        ));
+    }
+
+    if !result.lossy_paths.is_empty() {
+        sections.push(Section::counted(
+            Category::Lossy,
+            "Lossy strings, invalid text replaced",
+            result.lossy_paths.len(),
+            result
+                .lossy_paths
+                .iter()
+                .map(|path| entry(path, vec![], vec![]))
+                .collect(),
+        ));
+    }
+
+    if !result.duplicate_keys.is_empty() {
+        sections.push(Section::counted(
+            Category::DuplicateKey,
+            "Duplicate keys, last value kept",
+            result.duplicate_keys.len(),
+            result
+                .duplicate_keys
+                .iter()
+                .map(|path| entry(path, vec![], vec![]))
+                .collect(),
+        ));
+    }
+
+    if !result.schema_violations.is_empty() {
+        sections.push(Section::counted(
+            Category::Schema,
+            "Schema violations",
+            result.schema_violations.len(),
+            result
+                .schema_violations
+                .iter()
+                .map(|violation| {
+                    let suffix = match violation.document {
+                        SchemaDocument::Base => vec![Span::new("(base)", Tone::Muted)],
+                        SchemaDocument::Compare => vec![],
+                    };
+                    let subject = if violation.path.is_empty() {
+                        "(root)"
+                    } else {
+                        &violation.path
+                    };
+                    entry(
+                        subject,
+                        suffix,
+                        vec![Line::labeled(
+                            "error",
+                            vec![Span::new(&violation.message, Tone::Plain)],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.missing_paths.is_empty() {
+        sections.push(Section::counted(
+            Category::Missing,
+            "Missing paths",
+            result.missing_paths.len(),
+            result
+                .missing_paths
+                .iter()
+                .map(|path| entry(path, vec![], vec![]))
+                .collect(),
+        ));
+    }
+
+    if !result.extra_paths.is_empty() {
+        sections.push(Section::counted(
+            Category::Extra,
+            "Extra paths",
+            result.extra_paths.len(),
+            result
+                .extra_paths
+                .iter()
+                .map(|path| entry(path, vec![], vec![]))
+                .collect(),
+        ));
+    }
+
+    if !result.moved_values.is_empty() {
+        sections.push(Section::counted(
+            Category::Moved,
+            "Moved or renamed",
+            result.moved_values.len(),
+            result
+                .moved_values
+                .iter()
+                .map(|moved| {
+                    let kind = match moved.kind {
+                        MoveKind::Renamed => "renamed",
+                        MoveKind::Moved => "moved",
+                    };
+                    entry(
+                        &moved.from,
+                        vec![
+                            Span::new("→", Tone::Plain),
+                            Span::new(&moved.to, Tone::Base),
+                            Span::new(format!("({})", kind), Tone::Muted),
+                        ],
+                        vec![],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.array_elements.is_empty() {
+        sections.push(Section::counted(
+            Category::Unmatched,
+            "Unmatched array elements",
+            result.statistics.unmatched_element_count,
+            result
+                .array_elements
+                .iter()
+                .map(|array| {
+                    let element =
+                        |label, element: &json_diff_checker::arrays::UnmatchedElement, tone| {
+                            Line::labeled(
+                                label,
+                                vec![
+                                    Span::new(format!("[{}]", element.index), Tone::Plain),
+                                    value(&element.value, tone),
+                                ],
+                            )
+                        };
+                    let lines = array
+                        .missing
+                        .iter()
+                        .map(|missing| element("missing", missing, Tone::Base))
+                        .chain(
+                            array
+                                .extra
+                                .iter()
+                                .map(|extra| element("extra", extra, Tone::Compare)),
+                        )
+                        .collect();
+                    entry(&array.path, vec![], lines)
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.different_values.is_empty() {
+        sections.push(Section::counted(
+            Category::Different,
+            "Different values",
+            result.different_values.len(),
+            result
+                .different_values
+                .iter()
+                .map(|diff| {
+                    let (base, compare) = changed_values(&diff.base_value, &diff.compare_value);
+                    let mut item = entry(
+                        &diff.path,
+                        vec![],
+                        vec![
+                            Line::labeled("expected", vec![base]),
+                            Line::labeled("actual", vec![compare]),
+                        ],
+                    );
+                    if let (true, Item::Entry(entry)) = (args.side_by_side, &mut item) {
+                        let pretty = |value| {
+                            serde_json::to_string_pretty(value).expect("JSON values serialize")
+                        };
+                        entry.columns = Some(Columns {
+                            base: pretty(&diff.base_value),
+                            compare: pretty(&diff.compare_value),
+                        });
+                    }
+                    item
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.type_mismatches.is_empty() {
+        sections.push(Section::counted(
+            Category::TypeMismatch,
+            "Type mismatches",
+            result.type_mismatches.len(),
+            result
+                .type_mismatches
+                .iter()
+                .map(|mismatch| {
+                    entry(
+                        &mismatch.path,
+                        vec![],
+                        vec![Line::labeled(
+                            "type",
+                            vec![
+                                Span::new(&mismatch.base_type, Tone::Base),
+                                Span::new("→", Tone::Plain),
+                                Span::new(&mismatch.compare_type, Tone::Compare),
+                            ],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.nullability_changes.is_empty() {
+        sections.push(Section::counted(
+            Category::Nullability,
+            "Nullability changes",
+            result.nullability_changes.len(),
+            result
+                .nullability_changes
+                .iter()
+                .map(|change| {
+                    let suffix = if change.severity == Severity::Warning {
+                        vec![Span::new(
+                            "(warning)",
+                            Tone::Category(Category::Nullability),
+                        )]
+                    } else {
+                        vec![]
+                    };
+                    entry(
+                        &change.path,
+                        suffix,
+                        vec![Line::labeled(
+                            "value",
+                            vec![
+                                value(&change.base_value, Tone::Base),
+                                Span::new("→", Tone::Plain),
+                                value(&change.compare_value, Tone::Compare),
+                            ],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.optionality_changes.is_empty() {
+        sections.push(Section::counted(
+            Category::Optionality,
+            "Optionality changes",
+            result.optionality_changes.len(),
+            result
+                .optionality_changes
+                .iter()
+                .map(|change| {
+                    entry(
+                        &change.path,
+                        vec![],
+                        vec![Line::labeled(
+                            "key",
+                            vec![
+                                Span::new(change.base.name(), Tone::Base),
+                                Span::new("→", Tone::Plain),
+                                Span::new(change.compare.name(), Tone::Compare),
+                            ],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.template_violations.is_empty() {
+        sections.push(Section::counted(
+            Category::Template,
+            "Template violations",
+            result.template_violations.len(),
+            result
+                .template_violations
+                .iter()
+                .map(|violation| {
+                    entry(
+                        &violation.path,
+                        vec![],
+                        vec![
+                            Line::labeled(
+                                "template",
+                                vec![
+                                    Span::new(&violation.template, Tone::Base),
+                                    Span::new(format!("({})", violation.reason), Tone::Muted),
+                                ],
+                            ),
+                            Line::labeled(
+                                "actual",
+                                vec![value(&violation.compare_value, Tone::Compare)],
+                            ),
+                        ],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.text_diffs.is_empty() {
+        sections.push(Section::counted(
+            Category::TextDiff,
+            "Text diffs",
+            result.text_diffs.len(),
+            result
+                .text_diffs
+                .iter()
+                .map(|diff| {
+                    let lines = diff
+                        .diff
+                        .lines()
+                        .map(|line| {
+                            let tone = if line.starts_with("@@") {
+                                Tone::Info
+                            } else if line.starts_with('+') {
+                                Tone::Inserted
+                            } else if line.starts_with('-') {
+                                Tone::Deleted
+                            } else {
+                                Tone::Plain
+                            };
+                            Line::unlabeled(vec![Span::new(line, tone)])
+                        })
+                        .collect();
+                    entry(&diff.path, vec![], lines)
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.array_length_violations.is_empty() {
+        sections.push(Section::counted(
+            Category::ArrayLength,
+            "Array length violations",
+            result.array_length_violations.len(),
+            result
+                .array_length_violations
+                .iter()
+                .map(|violation| {
+                    let length = violation
+                        .actual_length
+                        .map(|len| format!("{} items, ", len))
+                        .unwrap_or_default();
+                    entry(
+                        &violation.path,
+                        vec![Span::new(
+                            format!("({}{})", length, violation.reason),
+                            Tone::Muted,
+                        )],
+                        vec![],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.uniqueness_violations.is_empty() {
+        sections.push(Section::counted(
+            Category::Duplicate,
+            "Duplicate values",
+            result.uniqueness_violations.len(),
+            result
+                .uniqueness_violations
+                .iter()
+                .map(|violation| {
+                    entry(
+                        &violation.rule,
+                        vec![value(&violation.value, Tone::Compare)],
+                        vec![Line::labeled(
+                            "at",
+                            vec![Span::new(violation.paths.join(", "), Tone::Plain)],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if !result.reference_violations.is_empty() {
+        sections.push(Section::counted(
+            Category::Reference,
+            "Broken references",
+            result.reference_violations.len(),
+            result
+                .reference_violations
+                .iter()
+                .map(|violation| {
+                    entry(
+                        &violation.path,
+                        vec![value(&violation.value, Tone::Compare)],
+                        vec![Line::labeled(
+                            "not found in",
+                            vec![Span::new(&violation.target, Tone::Plain)],
+                        )],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    sections.extend(result.distributions.iter().map(distribution_section));
+
+    if let Some(deltas) = &result.delta_statistics {
+        let mut section = Section::new(
+            Category::Deltas,
+            format!(
+                "Numeric deltas: {} compared, {} changed",
+                deltas.compared, deltas.changed
+            ),
+        );
+        if let Some(path) = &deltas.max_abs_delta_path {
+            section.items = vec![
+                Item::Line(Line::labeled(
+                    "max |Δ|",
+                    vec![
+                        Span::new(format!("{:.3e}", deltas.max_abs_delta), Tone::Plain),
+                        Span::new(format!("at {}", path), Tone::Muted),
+                    ],
+                )),
+                Item::Line(Line::labeled(
+                    "mean |Δ|",
+                    vec![Span::new(
+                        format!("{:.3e}", deltas.mean_abs_delta),
+                        Tone::Plain,
+                    )],
+                )),
+                Item::Line(Line::labeled(
+                    "max relative",
+                    vec![Span::new(
+                        format!("{:.3e}", deltas.max_rel_delta),
+                        Tone::Plain,
+                    )],
+                )),
+            ];
+        }
+        sections.push(section);
+    }
+
+    if !result.sampled_arrays.is_empty() {
+        sections.push(Section::counted(
+            Category::Sampled,
+            "Sampled arrays",
+            result.sampled_arrays.len(),
+            result
+                .sampled_arrays
+                .iter()
+                .map(|array| {
+                    entry(
+                        &array.path,
+                        vec![Span::new(
+                            format!(
+                                "({} of {} elements compared, {} differing, ≈{:.1}% ≈ {} elements)",
+                                array.sampled,
+                                array.length,
+                                array.differing,
+                                array.estimated_difference_rate * 100.0,
+                                array.estimated_differing
+                            ),
+                            Tone::Muted,
+                        )],
+                        vec![],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if let Some(coverage) = result.partial_coverage {
+        sections.push(Section::new(
+            Category::Partial,
+            format!(
+                "Partial report: the {:?} deadline passed after {} paths ({:.1}% covered)",
+                args.deadline.unwrap_or_default(),
+                result.statistics.total_paths_checked,
+                coverage
+            ),
+        ));
+    }
+
+    if result.statistics.coerced_match_count > 0 {
+        sections.push(Section::new(
+            Category::Coerced,
+            format!(
+                "{} stringified values matched after coercion",
+                result.statistics.coerced_match_count
+            ),
+        ));
+    }
+
+    if !result.warnings.is_empty() {
+        sections.push(Section::counted(
+            Category::Relaxed,
+            "Relaxed comparisons",
+            result.warnings.len(),
+            result
+                .warnings
+                .iter()
+                .map(|warning| {
+                    let suffix = match &warning.path {
+                        Some(path) => vec![Span::new(format!("at {}", path), Tone::Muted)],
+                        None => vec![],
+                    };
+                    entry(
+                        warning.kind.key(),
+                        suffix,
+                        vec![Line::unlabeled(vec![Span::new(
+                            &warning.message,
+                            Tone::Plain,
+                        )])],
+                    )
+                })
+                .collect(),
+        ));
+    }
+
+    if let Some(similarity) = result
+        .statistics
+        .similarity
+        .filter(|_| result.has_failures())
+    {
+        let title = match result.statistics.weighted_similarity {
+            Some(weighted) => format!("Similarity: {:.1}% ({:.1}% weighted)", similarity, weighted),
+            None => format!("Similarity: {:.1}%", similarity),
+        };
+        sections.push(Section::new(Category::Similarity, title));
+    }
+
+    for section in &mut sections {
+        let more = result.truncated_count(section.category);
+        if more == 0 {
+            continue;
+        }
+        // the unmatched elements are counted in the statistics already
+        match &mut section.count {
+            Some(count) if section.category != Category::Unmatched => *count += more,
+            _ => {}
+        }
+        section.items.push(more_line(more));
+    }
+
+    annotate_schema_violations(&mut sections, result);
+    assign_fingerprints(&mut sections, result);
+
+    FileReport {
+        name: file_name(result),
+        path: result.compare_file.clone(),
+        path_style: result.path_style,
+        hash: result.compare_hash.clone(),
+        sections,
+        status: status(result),
+    }
+}
+
+/// Writes a count with commas between groups of three digits, e.g. `4,212`.
+fn thousands(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// The line standing in for entries that aren't listed, e.g. `… and 4,212 more`.
+fn more_line(more: usize) -> Item {
+    Item::Line(Line::unlabeled(vec![Span::new(
+        format!("… and {} more", thousands(more)),
+        Tone::Muted,
+    )]))
+}
+
+/// Lists at most `max` entries per section, and how many more there are after them.
+pub fn limit_entries(report: &mut FileReport, max: usize) {
+    for section in &mut report.sections {
+        let mut listed = 0;
+        let mut more = 0;
+        section.items.retain(|item| {
+            if !matches!(item, Item::Entry(_)) {
+                return true;
+            }
+            listed += 1;
+            more += usize::from(listed > max);
+            listed <= max
+        });
+        if more > 0 {
+            section.items.push(more_line(more));
+        }
+    }
+}
+
+/// Replaces the entries reported at paths with one per prefix of `depth` path segments,
+/// counting the entries at and below it, e.g. `settings.notifications.* 37 missing`.
+/// Prefixes are listed in the order their first entries were.
+pub fn group_by_prefix(report: &mut FileReport, depth: usize) {
+    let mut sections = std::mem::take(&mut report.sections);
+    for section in sections.iter_mut().filter(|s| s.category.has_path()) {
+        // entries are grouped in dotted form, as the differences of a DiffReport are
+        let mut paths = Vec::new();
+        let mut others = Vec::new();
+        for item in section.items.drain(..) {
+            match &item {
+                Item::Entry(entry) => paths.push(path_from_parts(&report.path_parts(entry))),
+                _ => others.push(item),
+            }
+        }
+        let kind = section.category.key().replace('_', " ");
+        let mut items: Vec<Item> = compare::group_by_prefix(paths, depth)
+            .into_iter()
+            .map(
+                |PrefixGroup {
+                     prefix,
+                     below,
+                     count,
+                 }| {
+                    let prefix = match report.path_style {
+                        PathStyle::Dotted => prefix,
+                        PathStyle::Pointer => pointer_from_parts(&parse_path(&prefix)),
+                    };
+                    let subject = match (below, report.path_style) {
+                        (false, _) if prefix.is_empty() => "(root)".to_string(),
+                        (false, _) => prefix,
+                        (true, PathStyle::Dotted) => format!("{}.*", prefix),
+                        (true, PathStyle::Pointer) => format!("{}/*", prefix),
+                    };
+                    entry(
+                        subject,
+                        vec![Span::new(
+                            format!("{} {}", count, kind),
+                            Tone::Category(section.category),
+                        )],
+                        vec![],
+                    )
+                },
+            )
+            .collect();
+        items.extend(others);
+        section.items = items;
+    }
+    report.sections = sections;
+}
+
+/// Gives the entries of the reported differences their fingerprints. Entries with the
+/// same subject, such as the duplicates of one rule, are listed in the same order as
+/// their fingerprints.
+fn assign_fingerprints(sections: &mut [Section], result: &ComparisonResult) {
+    let mut ids: HashMap<(&str, &str), VecDeque<&str>> = HashMap::new();
+    for fingerprint in &result.fingerprints {
+        ids.entry((&fingerprint.kind, &fingerprint.path))
+            .or_default()
+            .push_back(&fingerprint.id);
+    }
+    for section in sections {
+        let kind = section.category.key();
+        for item in &mut section.items {
+            if let Item::Entry(entry) = item {
+                entry.id = ids
+                    .get_mut(&(kind, entry.subject.as_str()))
+                    .and_then(VecDeque::pop_front)
+                    .map(str::to_string);
+            }
+        }
+    }
+}
+
+/// Marks the reported entries at paths where the compare file violates the schema.
+fn annotate_schema_violations(sections: &mut [Section], result: &ComparisonResult) {
+    let violated: Vec<&str> = result
+        .schema_violations
+        .iter()
+        .filter(|violation| violation.document == SchemaDocument::Compare)
+        .map(|violation| violation.path.as_str())
+        .collect();
+    if violated.is_empty() {
+        return;
+    }
+    for section in sections
+        .iter_mut()
+        .filter(|section| section.category != Category::Schema)
+    {
+        for item in &mut section.items {
+            if let Item::Entry(entry) = item {
+                if violated.contains(&entry.subject.as_str()) {
+                    entry.suffix.push(Span::new(
+                        "(violates schema)",
+                        Tone::Category(Category::Schema),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn distribution_section(distribution: &ValueDistribution) -> Section {
+    let mut section = Section::new(
+        Category::Distribution,
+        format!("Value distribution of {}:", distribution.path),
+    );
+    if distribution.values.is_empty() {
+        section
+            .items
+            .push(Item::Line(Line::unlabeled(vec![Span::new(
+                "(no values)",
+                Tone::Muted,
+            )])));
+        return section;
+    }
+
+    let rows = distribution
+        .values
+        .iter()
+        .map(|entry| {
+            let marker = if entry.base_count == 0 {
+                Span::new("new", Tone::Inserted)
+            } else if entry.compare_count == 0 {
+                Span::new("vanished", Tone::Deleted)
+            } else if entry.base_count != entry.compare_count {
+                Span::new("changed", Tone::Category(Category::Different))
+            } else {
+                Span::new("", Tone::Plain)
+            };
+            vec![
+                Span::new(format_value(&entry.value), Tone::Plain),
+                Span::new(entry.base_count.to_string(), Tone::Plain),
+                Span::new(entry.compare_count.to_string(), Tone::Plain),
+                marker,
+            ]
+        })
+        .collect();
+    section.items.push(Item::Table(Table {
+        header: vec!["value", "base", "compare", ""],
+        right_aligned: vec![false, true, true, false],
+        rows,
+    }));
+    section
+}
+
+/// The parts of a `--two-phase` report: the structure of the documents first, their
+/// values second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Structure,
+    Values,
+}
+
+impl Phase {
+    pub fn title(self) -> &'static str {
+        match self {
+            Phase::Structure => "Phase 1: Structure",
+            Phase::Values => "Phase 2: Values",
+        }
+    }
+
+    /// Whether the differences of a category are reported in this phase; notices such
+    /// as the similarity score come with the values.
+    pub fn includes(self, category: Category) -> bool {
+        let structural = matches!(
+            category,
+            Category::Missing
+                | Category::Extra
+                | Category::Moved
+                | Category::Unmatched
+                | Category::TypeMismatch
+                | Category::Optionality
+        );
+        structural == (self == Phase::Structure)
+    }
+
+    /// The status of a file in this phase, counting only the phase's differences.
+    fn status(self, status: Status) -> Status {
+        match status {
+            Status::Failed(parts) => {
+                let parts: Vec<Span> = parts
+                    .into_iter()
+                    .filter(|part| matches!(part.tone, Tone::Category(category) if self.includes(category)))
+                    .collect();
+                if parts.is_empty() {
+                    Status::Ok
+                } else {
+                    Status::Failed(parts)
+                }
+            }
+            Status::Warnings(_) if self == Phase::Structure => Status::Ok,
+            status => status,
+        }
+    }
+}
+
+/// The detailed results of a compare file limited to one phase.
+pub fn phase_report(result: &ComparisonResult, args: &Args, phase: Phase) -> FileReport {
+    let mut report = file_report(result, args);
+    report
+        .sections
+        .retain(|section| phase.includes(section.category));
+    report.status = phase.status(report.status);
+    report
+}
+
+/// The one-line result of a compare file in one phase.
+pub fn phase_summary(result: &ComparisonResult, phase: Phase) -> FileSummary {
+    let mut summary = file_summary(result);
+    summary.status = phase.status(summary.status);
+    summary
+}
+
+/// Whether a phase kept within its threshold, given the differences it found in all
+/// compare files.
+pub fn phase_field(phase: Phase, count: usize, threshold: Option<usize>) -> Field {
+    let differences = format!("{} difference{}", count, if count == 1 { "" } else { "s" });
+    let label = match phase {
+        Phase::Structure => "Structure phase",
+        Phase::Values => "Value phase",
+    };
+    match threshold {
+        None => Field::new(label, format!("{}, informational", differences), Tone::Info),
+        Some(threshold) if count <= threshold => Field::new(
+            label,
+            format!("{}, within threshold {}", differences, threshold),
+            Tone::Good,
+        ),
+        Some(threshold) => Field::new(
+            label,
+            format!("{}, over threshold {}", differences, threshold),
+            Tone::Compare,
+        ),
+    }
+}
+
+type FileFilter = fn(&ComparisonResult) -> bool;
+
+/// Totals over all compare files.
+pub fn overall_fields(results: &[ComparisonResult]) -> Vec<Field> {
+    let files_with = |has: FileFilter| results.iter().filter(|r| has(r)).count();
+    let mut fields = vec![
+        Field::new(
+            "Total files checked",
+            results.len().to_string(),
+            Tone::Strong,
+        ),
+        Field::new(
+            "Perfect matches",
+            files_with(|r| !r.has_failures()).to_string(),
+            Tone::Good,
+        ),
+    ];
+    let baselined: usize = results.iter().map(|r| r.baselined).sum();
+    if baselined > 0 {
+        fields.push(Field::new(
+            "Baselined differences",
+            baselined.to_string(),
+            Tone::Muted,
+        ));
+    }
+    let aliases: usize = results.iter().map(|r| r.aliases.len()).sum();
+    if aliases > 0 {
+        fields.push(Field::new(
+            "Duplicate inputs skipped",
+            aliases.to_string(),
+            Tone::Muted,
+        ));
+    }
+    let similarities: Vec<f64> = results
+        .iter()
+        .filter_map(|r| r.statistics.similarity)
+        .collect();
+    if !similarities.is_empty() {
+        fields.push(Field::new(
+            "Average similarity",
+            format!(
+                "{:.1}%",
+                similarities.iter().sum::<f64>() / similarities.len() as f64
+            ),
+            Tone::Strong,
+        ));
+    }
+    let weighted: Vec<f64> = results
+        .iter()
+        .filter_map(|r| r.statistics.weighted_similarity)
+        .collect();
+    if !weighted.is_empty() {
+        fields.push(Field::new(
+            "Average weighted similarity",
+            format!(
+                "{:.1}%",
+                weighted.iter().sum::<f64>() / weighted.len() as f64
+            ),
+            Tone::Strong,
+        ));
+    }
+
+    let counts: [(&str, FileFilter, Category); 16] = [
+        (
+            "Files with duplicate keys",
+            |r| !r.duplicate_keys.is_empty(),
+            Category::DuplicateKey,
+        ),
+        (
+            "Files with schema violations",
+            |r| !r.schema_violations.is_empty(),
+            Category::Schema,
+        ),
+        (
+            "Files with missing paths",
+            |r| !r.missing_paths.is_empty(),
+            Category::Missing,
+        ),
+        (
+            "Files with extra paths",
+            |r| !r.extra_paths.is_empty(),
+            Category::Extra,
+        ),
+        (
+            "Files with moved values",
+            |r| !r.moved_values.is_empty(),
+            Category::Moved,
+        ),
+        (
+            "Files with unmatched array elements",
+            |r| !r.array_elements.is_empty(),
+            Category::Unmatched,
+        ),
+        (
+            "Files with different values",
+            |r| !r.different_values.is_empty(),
+            Category::Different,
+        ),
+        (
+            "Files with type mismatches",
+            |r| !r.type_mismatches.is_empty(),
+            Category::TypeMismatch,
+        ),
+        (
+            "Files with optionality changes",
+            |r| !r.optionality_changes.is_empty(),
+            Category::Optionality,
+        ),
+        (
+            "Files with template violations",
+            |r| !r.template_violations.is_empty(),
+            Category::Template,
+        ),
+        (
+            "Files with text diffs",
+            |r| !r.text_diffs.is_empty(),
+            Category::TextDiff,
+        ),
+        (
+            "Files with array length violations",
+            |r| !r.array_length_violations.is_empty(),
+            Category::ArrayLength,
+        ),
+        (
+            "Files with duplicate values",
+            |r| !r.uniqueness_violations.is_empty(),
+            Category::Duplicate,
+        ),
+        (
+            "Files with broken references",
+            |r| !r.reference_violations.is_empty(),
+            Category::Reference,
+        ),
+        (
+            "Files with nullability changes",
+            |r| !r.nullability_changes.is_empty(),
+            Category::Nullability,
+        ),
+        (
+            "Files with metadata differences",
+            |r| !r.metadata_differences.is_empty(),
+            Category::Metadata,
+        ),
+    ];
+    for (label, has, category) in counts {
+        let count = files_with(has);
+        if count > 0 {
+            fields.push(Field::new(
+                label,
+                count.to_string(),
+                Tone::Category(category),
+            ));
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use serde_json::json;
+
+    fn titles(report: &FileReport) -> Vec<&str> {
+        report
+            .sections
+            .iter()
+            .map(|section| section.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn two_phase_lists_type_mismatches_and_values_in_their_phases() {
+        let args = Args::parse_from([
+            "json_diff_checker",
+            "-v",
+            "--two-phase",
+            "base.json",
+            "compare.json",
+        ]);
+        let result: ComparisonResult = serde_json::from_value(json!({
+            "base_file": "base.json",
+            "compare_file": "compare.json",
+            "missing_paths": [],
+            "different_values": [
+                {"path": "name", "base_value": "a", "compare_value": "b"},
+            ],
+            "type_mismatches": [
+                {
+                    "path": "age",
+                    "base_type": "number",
+                    "compare_type": "string",
+                    "base_value": 3,
+                    "compare_value": "3",
+                },
+            ],
+            "statistics": {
+                "total_paths_checked": 2,
+                "missing_count": 0,
+                "different_count": 1,
+                "type_mismatch_count": 1,
+                "nullability_change_count": 0,
+                "match_count": 0,
+            },
+        }))
+        .unwrap();
+
+        let structure = phase_report(&result, &args, Phase::Structure);
+        assert_eq!(titles(&structure), ["Type mismatches"]);
+        let Item::Entry(entry) = &structure.sections[0].items[0] else {
+            panic!("type mismatches are listed as entries");
+        };
+        assert_eq!(entry.subject, "age");
+        let values = phase_report(&result, &args, Phase::Values);
+        assert_eq!(titles(&values), ["Different values"]);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Field, FileReport, FileSummary, Item, Line, Renderer, Span, Status};
+
+/// Tab-separated lines for scripts: the category, the compare file, then the subject and
+/// its details. Headers and totals are left out, and the format stays stable across
+/// versions.
+pub struct PorcelainRenderer;
+
+/// Escapes tabs, line breaks and backslashes so every entry stays on one line.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn spans(spans: &[Span]) -> String {
+    spans
+        .iter()
+        .map(|span| escape(&span.text))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn line(line: &Line) -> String {
+    match line.label {
+        Some(label) => format!("{}={}", label, spans(&line.spans)),
+        None => spans(&line.spans),
+    }
+}
+
+fn status(status: &Status) -> String {
+    match status {
+        Status::Identical => "identical".to_string(),
+        Status::Ok => "ok".to_string(),
+        Status::Warnings(_) => "warning".to_string(),
+        Status::Failed(parts) => {
+            let parts: Vec<String> = parts.iter().map(|part| escape(&part.text)).collect();
+            format!("failed\t{}", parts.join(", "))
+        }
+    }
+}
+
+impl Renderer for PorcelainRenderer {
+    fn header(&mut self, _title: &str, _fields: &[Field]) {}
+
+    fn file(&mut self, report: &FileReport) {
+        let file = escape(&report.path);
+        for section in &report.sections {
+            let key = section.category.key();
+            if section.items.is_empty() {
+                println!("{}\t{}\t{}", key, file, escape(&section.title));
+            }
+            for item in &section.items {
+                let fields: Vec<String> = match item {
+                    Item::Entry(entry) => std::iter::once(escape(&entry.subject))
+                        .chain((!entry.suffix.is_empty()).then(|| spans(&entry.suffix)))
+                        .chain(entry.lines.iter().map(line))
+                        .chain(entry.id.iter().map(|id| format!("id={}", id)))
+                        .collect(),
+                    Item::Line(detail) => vec![line(detail)],
+                    Item::Table(table) => {
+                        for row in &table.rows {
+                            let cells: Vec<String> =
+                                row.iter().map(|cell| escape(&cell.text)).collect();
+                            println!("{}\t{}\t{}", key, file, cells.join("\t"));
+                        }
+                        continue;
+                    }
+                };
+                println!("{}\t{}\t{}", key, file, fields.join("\t"));
+            }
+        }
+        println!("status\t{}\t{}", file, status(&report.status));
+    }
+
+    fn file_summary(&mut self, summary: &FileSummary) {
+        println!(
+            "status\t{}\t{}",
+            escape(&summary.path),
+            status(&summary.status)
+        );
+    }
+
+    fn overall(&mut self, _fields: &[Field]) {}
+
+    fn heading(&mut self, title: &str) {
+        println!("heading\t{}", escape(title));
+    }
+}
@@ -0,0 +1,34 @@
+use serde_json::{json, Value};
+
+use super::{annotations, Annotation, FileReport, Level};
+
+fn severity(level: Level) -> &'static str {
+    match level {
+        Level::Error => "ERROR",
+        Level::Warning => "WARNING",
+        Level::Note => "INFO",
+    }
+}
+
+fn diagnostic(annotation: &Annotation) -> Value {
+    json!({
+        "message": annotation.message(),
+        // paths aren't mapped to lines, so diagnostics point at the start of the file
+        "location": {
+            "path": annotation.report.path,
+            "range": { "start": { "line": 1 } },
+        },
+        "severity": severity(annotation.level()),
+        "code": { "value": annotation.category.key() },
+    })
+}
+
+/// A reviewdog diagnostic result (rdjson) with a diagnostic per reported entry, located
+/// in its compare file and coded by its kind.
+pub fn report(reports: &[FileReport]) -> String {
+    let result = json!({
+        "source": { "name": "json_diff_checker" },
+        "diagnostics": annotations(reports).iter().map(diagnostic).collect::<Vec<_>>(),
+    });
+    serde_json::to_string_pretty(&result).expect("rdjson results serialize")
+}
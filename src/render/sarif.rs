@@ -0,0 +1,84 @@
+use serde_json::{json, Map, Value};
+
+use super::{annotations, Annotation, Field, FileReport, Level};
+
+/// The compare file as a relative URI reference.
+fn uri(path: &str) -> String {
+    path.replace('\\', "/")
+        .replace('%', "%25")
+        .replace(' ', "%20")
+}
+
+fn result(annotation: &Annotation) -> Value {
+    let level = match annotation.level() {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note => "note",
+    };
+    json!({
+        "ruleId": annotation.category.key(),
+        "partialFingerprints": annotation
+            .entry
+            .id
+            .as_ref()
+            .map(|id| json!({ "jsonDiffEntry/v1": id }))
+            .unwrap_or_else(|| json!({})),
+        "level": level,
+        "message": { "text": annotation.message() },
+        "locations": [{
+            // paths aren't mapped to lines, so results point at the start of the file
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri(&annotation.report.path) },
+                "region": { "startLine": 1 },
+            },
+            "logicalLocations": [{
+                "fullyQualifiedName": annotation.entry.subject,
+                "kind": "member",
+            }],
+        }],
+    })
+}
+
+/// A SARIF 2.1.0 log with one run: a result per reported entry, with the compare file as
+/// its artifact location and the JSON path as its logical location, and a rule per kind
+/// of entry. The header fields are the run's properties.
+pub fn report(header: &[Field], reports: &[FileReport]) -> String {
+    let annotations = annotations(reports);
+    let mut rules: Vec<Value> = Vec::new();
+    let mut rule_ids: Vec<&str> = Vec::new();
+    for annotation in &annotations {
+        let id = annotation.category.key();
+        if !rule_ids.contains(&id) {
+            rule_ids.push(id);
+            rules.push(json!({
+                "id": id,
+                "shortDescription": { "text": annotation.title },
+            }));
+        }
+    }
+
+    let properties: Map<String, Value> = header
+        .iter()
+        .map(|field| (field.label.clone(), Value::from(field.value.text.clone())))
+        .collect();
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "json_diff_checker",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "artifacts": reports
+                .iter()
+                .map(|report| json!({ "location": { "uri": uri(&report.path) } }))
+                .collect::<Vec<_>>(),
+            "results": annotations.iter().map(result).collect::<Vec<_>>(),
+            "properties": properties,
+        }],
+    });
+    serde_json::to_string_pretty(&log).expect("SARIF logs serialize")
+}
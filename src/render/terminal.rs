@@ -0,0 +1,398 @@
+use colored::*;
+use similar::{Algorithm, DiffTag};
+
+use super::{
+    Category, Columns, Entry, Field, FileReport, FileSummary, Item, Line, Renderer, Section, Span,
+    Status, Table, Tone,
+};
+
+/// Between the columns of `--side-by-side`.
+const SEPARATOR: &str = " │ ";
+
+/// Narrower columns than this are too cramped to compare, so values are listed instead.
+const MIN_COLUMN_WIDTH: usize = 16;
+
+/// How many unchanged characters are kept around each change in a marked span.
+const CONTEXT: usize = 24;
+
+/// Colored text for terminals.
+pub struct TerminalRenderer;
+
+fn category_color(category: Category) -> Color {
+    match category {
+        Category::Missing | Category::Extra | Category::Moved | Category::Unmatched => {
+            Color::BrightRed
+        }
+        Category::Different | Category::TextDiff => Color::BrightYellow,
+        Category::HashOnly | Category::Partial | Category::DuplicateKey => Color::BrightYellow,
+        Category::TypeMismatch | Category::Schema | Category::Optionality => Color::BrightMagenta,
+        Category::Nullability | Category::Deltas | Category::Sampled | Category::Metadata => {
+            Color::BrightCyan
+        }
+        Category::Template
+        | Category::ArrayLength
+        | Category::Duplicate
+        | Category::Reference
+        | Category::Distribution
+        | Category::Coerced
+        | Category::Similarity => Color::BrightBlue,
+        Category::Alias | Category::Baselined | Category::Lossy | Category::Relaxed => {
+            Color::BrightBlack
+        }
+    }
+}
+
+pub(super) fn paint(text: &str, tone: Tone) -> ColoredString {
+    match tone {
+        Tone::Plain => text.normal(),
+        Tone::Muted => text.bright_black(),
+        Tone::Strong => text.bright_white(),
+        Tone::Good => text.bright_green(),
+        Tone::Info => text.bright_cyan(),
+        Tone::Notice => text.bright_yellow(),
+        Tone::Base | Tone::Inserted => text.green(),
+        Tone::Compare | Tone::Deleted => text.red(),
+        Tone::Category(category) => text.color(category_color(category)),
+    }
+}
+
+/// Unchanged text of a marked span, with the middle of long runs left out: the end of
+/// the run before the first change, the start of the one after the last, and both
+/// ends of those in between.
+fn unchanged(text: &str, tone: Tone, before_change: bool, after_change: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let keep_start = if after_change { CONTEXT } else { 0 };
+    let keep_end = if before_change { CONTEXT } else { 0 };
+    // shortening has to leave out enough to be worth the ellipsis
+    if chars.len() <= keep_start + keep_end + CONTEXT {
+        return paint(text, tone).to_string();
+    }
+    let part = |chars: &[char]| match chars {
+        [] => String::new(),
+        chars => paint(&chars.iter().collect::<String>(), tone).to_string(),
+    };
+    format!(
+        "{}{}{}",
+        part(&chars[..keep_start]),
+        "…".bright_black(),
+        part(&chars[chars.len() - keep_end..])
+    )
+}
+
+/// A span, with its changed characters highlighted and long unchanged runs shortened
+/// around them.
+fn paint_span(span: &Span) -> String {
+    // without colors the marks wouldn't show, so the whole text is kept
+    if span.marks.is_empty() || !control::SHOULD_COLORIZE.should_colorize() {
+        return paint(&span.text, span.tone).to_string();
+    }
+    let mut painted = String::new();
+    let mut end = 0;
+    // the marks are ascending and don't overlap, but may start at 0 or end at the end
+    for (i, mark) in span.marks.iter().enumerate() {
+        painted.push_str(&unchanged(
+            &span.text[end..mark.start],
+            span.tone,
+            true,
+            i > 0,
+        ));
+        painted.push_str(
+            &paint(&span.text[mark.clone()], span.tone)
+                .bold()
+                .reversed()
+                .to_string(),
+        );
+        end = mark.end;
+    }
+    painted.push_str(&unchanged(&span.text[end..], span.tone, false, true));
+    painted
+}
+
+pub(super) fn spans(spans: &[Span]) -> String {
+    spans.iter().map(paint_span).collect::<Vec<_>>().join(" ")
+}
+
+/// Prints a line of detail, with its label padded to `width`.
+pub(super) fn print_line(indent: &str, line: &Line, width: usize) {
+    match line.label {
+        Some(label) => println!(
+            "{}{} {}",
+            indent,
+            format!("{:<width$}", format!("{}:", label), width = width).bright_black(),
+            spans(&line.spans)
+        ),
+        None => println!("{}{}", indent, spans(&line.spans)),
+    }
+}
+
+/// The width of the terminal: `COLUMNS` if it's set, else the width of the terminal
+/// stdout is connected to, else 80.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .or_else(window_width)
+        .unwrap_or(80)
+}
+
+#[cfg(unix)]
+fn window_width() -> Option<usize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes the window size into the struct it's given
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some(usize::from(size.ws_col))
+}
+
+#[cfg(not(unix))]
+fn window_width() -> Option<usize> {
+    None
+}
+
+/// Splits a line into pieces of at most `width` characters.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(width)
+        .map(|piece| piece.iter().collect())
+        .collect()
+}
+
+/// Prints the values line by line in two columns of `width`, aligning the lines they have
+/// in common and coloring the others, wrapped where they're too long.
+fn print_columns(indent: &str, columns: &Columns, width: usize) {
+    let base: Vec<&str> = columns.base.lines().collect();
+    let compare: Vec<&str> = columns.compare.lines().collect();
+    println!(
+        "{}{}{}{}",
+        indent,
+        format!("{:<width$}", "expected", width = width).bright_black(),
+        SEPARATOR.bright_black(),
+        "actual".bright_black()
+    );
+    for op in similar::capture_diff_slices(Algorithm::Myers, &base, &compare) {
+        let (tag, old, new) = op.as_tag_tuple();
+        let (old, new) = (&base[old], &compare[new]);
+        let (base_tone, compare_tone) = match tag {
+            DiffTag::Equal => (Tone::Plain, Tone::Plain),
+            _ => (Tone::Base, Tone::Compare),
+        };
+        for row in 0..old.len().max(new.len()) {
+            let left = old.get(row).map_or_else(Vec::new, |line| wrap(line, width));
+            let right = new.get(row).map_or_else(Vec::new, |line| wrap(line, width));
+            for piece in 0..left.len().max(right.len()) {
+                let left = left.get(piece).map_or("", String::as_str);
+                let right = right.get(piece).map_or("", String::as_str);
+                let padding = " ".repeat(width - left.chars().count());
+                // rows end with the last character of the actual value, not in spaces
+                let separator = if right.is_empty() {
+                    SEPARATOR.trim_end()
+                } else {
+                    SEPARATOR
+                };
+                println!(
+                    "{}{}{}{}{}",
+                    indent,
+                    paint(left, base_tone),
+                    padding,
+                    separator.bright_black(),
+                    paint(right, compare_tone)
+                );
+            }
+        }
+    }
+}
+
+fn print_entry(entry: &Entry, category: Category) {
+    let mut subject = paint(&entry.subject, Tone::Category(category)).to_string();
+    if !entry.suffix.is_empty() {
+        subject = format!("{} {}", subject, spans(&entry.suffix));
+    }
+    match &entry.id {
+        Some(id) => println!(
+            "    {} {} {}",
+            "└".bright_black(),
+            subject,
+            format!("#{}", id).dimmed()
+        ),
+        None => println!("    {} {}", "└".bright_black(), subject),
+    }
+    if let Some(columns) = &entry.columns {
+        let width = terminal_width().saturating_sub(6 + SEPARATOR.chars().count()) / 2;
+        if width >= MIN_COLUMN_WIDTH {
+            print_columns("      ", columns, width);
+            return;
+        }
+    }
+    let width = entry
+        .lines
+        .iter()
+        .filter_map(|line| line.label)
+        .map(|label| label.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+    for line in &entry.lines {
+        print_line("      ", line, width);
+    }
+}
+
+fn print_table(table: &Table) {
+    let mut widths: Vec<usize> = table
+        .header
+        .iter()
+        .map(|cell| cell.chars().count())
+        .collect();
+    for row in &table.rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.text.chars().count());
+        }
+    }
+    // cells as their width and painted text; empty cells at the end of a row are left
+    // out and the last one isn't padded, so rows don't end in spaces
+    let print_row = |mut cells: Vec<(usize, ColoredString)>| {
+        while cells.last().is_some_and(|(len, _)| *len == 0) {
+            cells.pop();
+        }
+        let count = cells.len();
+        let row: Vec<String> = cells
+            .into_iter()
+            .enumerate()
+            .map(|(column, (len, painted))| {
+                let padding = " ".repeat(widths[column].saturating_sub(len));
+                if table.right_aligned[column] {
+                    format!("{}{}", padding, painted)
+                } else if column + 1 == count {
+                    painted.to_string()
+                } else {
+                    format!("{}{}", painted, padding)
+                }
+            })
+            .collect();
+        println!("    {}", row.join("  "));
+    };
+
+    print_row(
+        table
+            .header
+            .iter()
+            .map(|cell| (cell.chars().count(), cell.bright_black()))
+            .collect(),
+    );
+    for row in &table.rows {
+        print_row(
+            row.iter()
+                .map(|span| (span.text.chars().count(), paint(&span.text, span.tone)))
+                .collect(),
+        );
+    }
+}
+
+fn print_fields(fields: &[Field]) {
+    for field in fields {
+        println!(
+            "{}: {}",
+            field.label,
+            paint(&field.value.text, field.value.tone)
+        );
+    }
+}
+
+/// The compare file's name, and its hash when it was asked for.
+pub(super) fn print_file_name(report: &FileReport) {
+    println!(
+        "{} {}",
+        "▶".bright_blue(),
+        report.name.bright_white().bold()
+    );
+    if let Some(hash) = &report.hash {
+        println!("  {}", format!("sha256:{}", hash).bright_black());
+    }
+}
+
+pub(super) fn print_section(section: &Section) {
+    let symbol = paint(section.category.symbol(), Tone::Category(section.category));
+    match section.count {
+        Some(count) => println!("\n  {} {} ({}):", symbol, section.title, count),
+        None => println!("\n  {} {}", symbol, section.title),
+    }
+    for item in &section.items {
+        match item {
+            Item::Entry(entry) => print_entry(entry, section.category),
+            Item::Line(line) => {
+                let width = line.label.map_or(0, |label| label.chars().count() + 1);
+                print_line("    ", line, width);
+            }
+            Item::Table(table) => print_table(table),
+        }
+    }
+}
+
+/// The line closing a file that matches, followed by a blank line.
+pub(super) fn print_status(status: &Status) {
+    match status {
+        Status::Identical => println!(
+            "  {} Identical (canonical hashes match)",
+            "✓".bright_green()
+        ),
+        Status::Ok => println!("  {} All items match!", "✓".bright_green()),
+        _ => {}
+    }
+    println!();
+}
+
+impl Renderer for TerminalRenderer {
+    fn header(&mut self, title: &str, fields: &[Field]) {
+        println!("{}", "═".repeat(80).bright_blue());
+        println!("{}", title.bright_white().bold());
+        println!("{}", "═".repeat(80).bright_blue());
+        print_fields(fields);
+        println!("{}\n", "─".repeat(80).bright_black());
+    }
+
+    fn file(&mut self, report: &FileReport) {
+        print_file_name(report);
+        for section in &report.sections {
+            print_section(section);
+        }
+        print_status(&report.status);
+    }
+
+    fn file_summary(&mut self, summary: &FileSummary) {
+        let status = match &summary.status {
+            Status::Identical => "✓ identical".bright_green().to_string(),
+            Status::Ok => "✓ OK".bright_green().to_string(),
+            Status::Warnings(count) => format!("✓ OK ({} nullability warning)", count)
+                .bright_green()
+                .to_string(),
+            Status::Failed(parts) => parts
+                .iter()
+                .map(|part| paint(&part.text, part.tone).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        let mut line = format!("{:<30} {}", summary.name, status);
+        for note in &summary.notes {
+            line = format!("{} {}", line, note.bright_black());
+        }
+        println!("{}", line);
+    }
+
+    fn overall(&mut self, fields: &[Field]) {
+        println!("{}", "─".repeat(80).bright_black());
+        println!("{}", "Summary".bright_white().bold());
+        println!("{}", "─".repeat(80).bright_black());
+        print_fields(fields);
+    }
+
+    fn heading(&mut self, title: &str) {
+        println!("{}", title.bright_white().bold().underline());
+        println!();
+    }
+}
@@ -0,0 +1,203 @@
+use colored::*;
+
+use super::terminal::{
+    paint, print_file_name, print_line, print_section, print_status, spans, TerminalRenderer,
+};
+use super::{Category, Entry, Field, FileReport, FileSummary, Item, Renderer, Tone};
+use json_diff_checker::json_diff::PathPart;
+
+/// Colored text for terminals like the terminal style, with the entries reported at
+/// paths as a tree mirroring the documents instead of a list per category.
+pub struct TreeRenderer;
+
+/// A path with entries at or below it.
+struct Node<'a> {
+    part: Option<PathPart>,
+    entries: Vec<(Category, &'a Entry)>,
+    children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn new(part: Option<PathPart>) -> Node<'a> {
+        Node {
+            part,
+            entries: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, parts: &[PathPart], category: Category, entry: &'a Entry) {
+        let Some((part, rest)) = parts.split_first() else {
+            self.entries.push((category, entry));
+            return;
+        };
+        let position = match self
+            .children
+            .iter()
+            .position(|child| child.part.as_ref() == Some(part))
+        {
+            Some(position) => position,
+            None => {
+                self.children.push(Node::new(Some(part.clone())));
+                self.children.len() - 1
+            }
+        };
+        self.children[position].insert(rest, category, entry);
+    }
+
+    /// Puts array elements in index order; object keys keep the order they were
+    /// reported in.
+    fn sort(&mut self) {
+        self.children.sort_by_key(|child| match child.part {
+            Some(PathPart::Index(index)) => Some(index),
+            _ => None,
+        });
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.part {
+            None => "$".to_string(),
+            Some(PathPart::Key(key)) => key.clone(),
+            Some(PathPart::Index(index)) => format!("[{}]", index),
+        }
+    }
+}
+
+/// The kind of an entry with its suffix and fingerprint, e.g. `✗ missing #f1521335`.
+fn marker(category: Category, entry: &Entry) -> String {
+    let tone = Tone::Category(category);
+    let mut marker = format!(
+        "{} {}",
+        paint(category.symbol(), tone),
+        paint(&category.key().replace('_', " "), tone)
+    );
+    if !entry.suffix.is_empty() {
+        marker = format!("{} {}", marker, spans(&entry.suffix));
+    }
+    if let Some(id) = &entry.id {
+        marker = format!("{} {}", marker, format!("#{}", id).dimmed());
+    }
+    marker
+}
+
+/// Prints a node after `lead`, the prefix and connector of its line, then its subtree.
+/// `prefix` continues the lines of its parent's later siblings.
+fn print_node(node: &Node, lead: &str, prefix: &str) {
+    let label = match node.entries.first() {
+        Some(&(category, _)) => paint(&node.label(), Tone::Category(category)).bold(),
+        None => node.label().normal(),
+    };
+    match node.entries.first() {
+        Some(&(category, entry)) => {
+            println!(
+                "{}{}  {}",
+                lead.bright_black(),
+                label,
+                marker(category, entry)
+            )
+        }
+        None => println!("{}{}", lead.bright_black(), label),
+    }
+
+    // details hang below the label, left of the line down to the children
+    let indent = if node.children.is_empty() {
+        format!("{}    ", prefix)
+    } else {
+        format!("{}│   ", prefix)
+    };
+    let painted_indent = indent.bright_black().to_string();
+    for (i, &(category, entry)) in node.entries.iter().enumerate() {
+        if i > 0 {
+            println!("{}{}", painted_indent, marker(category, entry));
+        }
+        let width = entry
+            .lines
+            .iter()
+            .filter_map(|line| line.label)
+            .map(|label| label.chars().count() + 1)
+            .max()
+            .unwrap_or(0);
+        for line in &entry.lines {
+            print_line(&format!("{}  ", painted_indent), line, width);
+        }
+    }
+
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let (connector, continuation) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        print_node(
+            child,
+            &format!("{}{}", prefix, connector),
+            &format!("{}{}", prefix, continuation),
+        );
+    }
+}
+
+impl Renderer for TreeRenderer {
+    fn header(&mut self, title: &str, fields: &[Field]) {
+        TerminalRenderer.header(title, fields);
+    }
+
+    fn file(&mut self, report: &FileReport) {
+        print_file_name(report);
+
+        let mut root = Node::new(None);
+        for section in report.sections.iter().filter(|s| s.category.has_path()) {
+            for item in &section.items {
+                let Item::Entry(entry) = item else {
+                    continue;
+                };
+                root.insert(&report.path_parts(entry), section.category, entry);
+            }
+        }
+
+        root.sort();
+
+        // notices such as the baseline's come before the tree, statistics after it
+        let split = report
+            .sections
+            .iter()
+            .position(|section| section.category.has_path())
+            .unwrap_or(report.sections.len());
+        let (before, after) = report.sections.split_at(split);
+        for section in before {
+            print_section(section);
+        }
+        if !root.entries.is_empty() || !root.children.is_empty() {
+            println!();
+            print_node(&root, "  ", "  ");
+        }
+        // entries that aren't listed, e.g. past --max-diffs, are counted below the tree
+        for section in report.sections.iter().filter(|s| s.category.has_path()) {
+            for item in &section.items {
+                if let Item::Line(line) = item {
+                    let symbol = paint(section.category.symbol(), Tone::Category(section.category));
+                    print_line(&format!("  {} ", symbol), line, 0);
+                }
+            }
+        }
+        for section in after.iter().filter(|s| !s.category.has_path()) {
+            print_section(section);
+        }
+        print_status(&report.status);
+    }
+
+    fn file_summary(&mut self, summary: &FileSummary) {
+        TerminalRenderer.file_summary(summary);
+    }
+
+    fn overall(&mut self, fields: &[Field]) {
+        TerminalRenderer.overall(fields);
+    }
+
+    fn heading(&mut self, title: &str) {
+        TerminalRenderer.heading(title);
+    }
+}
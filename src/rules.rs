@@ -0,0 +1,374 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::analysis::PathWeight;
+use crate::arrays::{ArrayOrder, PathArrayOrder};
+use crate::collation::{Collation, CollationStrength};
+use crate::comparators::Comparator;
+use crate::json_diff::{
+    get_value_by_path, PathCollation, PathComparator, PathTimestamps, PathTolerance,
+};
+use crate::pattern::PathPattern;
+
+/// The top-level keys of a rules file, in the order `Rules` declares them.
+pub const RULE_KEYS: &[&str] = &[
+    "ignore",
+    "array_lengths",
+    "unique",
+    "references",
+    "tolerances",
+    "timestamps",
+    "array_order",
+    "collate",
+    "comparators",
+    "weights",
+];
+
+/// Contract rules checked against every compare file, loaded from a `--rules` file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    /// Paths left out of the comparison, as with `--ignore`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub array_lengths: Vec<ArrayLengthRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unique: Vec<UniqueRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<ReferenceRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tolerances: Vec<ToleranceRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timestamps: Vec<TimestampRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub array_order: Vec<ArrayOrderRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collate: Vec<CollateRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comparators: Vec<ComparatorRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<WeightRule>,
+}
+
+impl Rules {
+    /// The path of every rule with the kind and position of the rule it belongs to, e.g.
+    /// `("tolerances[0]", "metrics.**")`; references contribute their target as well.
+    pub fn paths(&self) -> Vec<(String, &str)> {
+        fn paths_of<'a, T>(
+            kind: &str,
+            rules: &'a [T],
+            path: fn(&T) -> &str,
+        ) -> Vec<(String, &'a str)> {
+            rules
+                .iter()
+                .enumerate()
+                .map(|(i, rule)| (format!("{}[{}]", kind, i), path(rule)))
+                .collect()
+        }
+
+        let mut paths: Vec<(String, &str)> = Vec::new();
+        paths.extend(paths_of("array_lengths", &self.array_lengths, |r| &r.path));
+        paths.extend(paths_of("unique", &self.unique, |r| &r.path));
+        for (i, rule) in self.references.iter().enumerate() {
+            paths.push((format!("references[{}]", i), &rule.path));
+            paths.push((format!("references[{}].target", i), &rule.target));
+        }
+        paths.extend(paths_of("tolerances", &self.tolerances, |r| &r.path));
+        paths.extend(paths_of("timestamps", &self.timestamps, |r| &r.path));
+        paths.extend(paths_of("array_order", &self.array_order, |r| &r.path));
+        paths.extend(paths_of("collate", &self.collate, |r| &r.path));
+        paths.extend(paths_of("comparators", &self.comparators, |r| &r.path));
+        paths.extend(paths_of("weights", &self.weights, |r| &r.path));
+        paths
+    }
+
+    /// Rules that can't take effect as written: a rule overridden by a later one of the
+    /// same kind for the same path, since the last matching rule applies, and array
+    /// lengths no array can have.
+    pub fn conflicts(&self) -> Vec<String> {
+        fn overridden<T>(kind: &str, rules: &[T], path: fn(&T) -> &str) -> Vec<String> {
+            let mut conflicts = Vec::new();
+            for (i, rule) in rules.iter().enumerate() {
+                if let Some(later) = (i + 1..rules.len()).find(|&j| path(&rules[j]) == path(rule)) {
+                    conflicts.push(format!(
+                        "{kind}[{i}] is overridden by {kind}[{later}] for the same path {:?}",
+                        path(rule)
+                    ));
+                }
+            }
+            conflicts
+        }
+
+        let mut conflicts = Vec::new();
+        for (i, rule) in self.array_lengths.iter().enumerate() {
+            let min = rule.min_items.unwrap_or(0);
+            let max = rule.max_items.unwrap_or(usize::MAX);
+            let impossible = match rule.exact {
+                Some(exact) => exact < min || exact > max,
+                None => min > max,
+            };
+            if impossible {
+                conflicts.push(format!(
+                    "array_lengths[{}] at {:?} can't hold for any array: its exact, min_items \
+                     and max_items contradict each other",
+                    i, rule.path
+                ));
+            }
+        }
+        conflicts.extend(overridden("tolerances", &self.tolerances, |r| &r.path));
+        conflicts.extend(overridden("timestamps", &self.timestamps, |r| &r.path));
+        conflicts.extend(overridden("array_order", &self.array_order, |r| &r.path));
+        conflicts.extend(overridden("collate", &self.collate, |r| &r.path));
+        conflicts.extend(overridden("comparators", &self.comparators, |r| &r.path));
+        conflicts.extend(overridden("weights", &self.weights, |r| &r.path));
+        conflicts
+    }
+}
+
+/// Asserts the number of elements of the array at `path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArrayLengthRule {
+    pub path: String,
+    pub min_items: Option<usize>,
+    pub max_items: Option<usize>,
+    pub exact: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrayLengthViolation {
+    pub path: String,
+    pub actual_length: Option<usize>,
+    pub reason: String,
+}
+
+impl ArrayLengthRule {
+    /// Checks the rule against a document, returning a violation if it does not hold.
+    pub fn check(&self, document: &Value) -> Option<ArrayLengthViolation> {
+        let violation = |actual_length, reason| {
+            Some(ArrayLengthViolation {
+                path: self.path.clone(),
+                actual_length,
+                reason,
+            })
+        };
+
+        let len = match get_value_by_path(document, &self.path) {
+            Some(Value::Array(arr)) => arr.len(),
+            Some(_) => return violation(None, "not an array".to_string()),
+            None => return violation(None, "array is missing".to_string()),
+        };
+
+        match (self.exact, self.min_items, self.max_items) {
+            (Some(exact), _, _) if len != exact => {
+                violation(Some(len), format!("expected exactly {} items", exact))
+            }
+            (_, Some(min), _) if len < min => {
+                violation(Some(len), format!("expected at least {} items", min))
+            }
+            (_, _, Some(max)) if len > max => {
+                violation(Some(len), format!("expected at most {} items", max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Asserts that the values selected by a wildcard path are unique within a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UniqueRule {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniquenessViolation {
+    pub rule: String,
+    pub value: Value,
+    pub paths: Vec<String>,
+}
+
+impl UniqueRule {
+    /// Returns one violation per value that occurs more than once, with the paths
+    /// of all its occurrences.
+    pub fn check(&self, document: &Value) -> Vec<UniquenessViolation> {
+        let mut occurrences: Vec<(&Value, Vec<String>)> = Vec::new();
+        let mut positions = HashMap::new();
+
+        for (path, value) in PathPattern::parse(&self.path).select(document) {
+            // serde_json keeps object keys sorted, so the serialized form is canonical
+            let position = *positions.entry(value.to_string()).or_insert_with(|| {
+                occurrences.push((value, Vec::new()));
+                occurrences.len() - 1
+            });
+            occurrences[position].1.push(path);
+        }
+
+        occurrences
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(value, paths)| UniquenessViolation {
+                rule: self.path.clone(),
+                value: value.clone(),
+                paths,
+            })
+            .collect()
+    }
+}
+
+/// Asserts that every value selected by `path` also occurs among the values
+/// selected by `target`, e.g. `orders[*].customerId` in `customers[*].id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReferenceRule {
+    pub path: String,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceViolation {
+    pub path: String,
+    pub value: Value,
+    pub target: String,
+}
+
+impl ReferenceRule {
+    /// Returns one violation per reference whose value is not found in the target set.
+    pub fn check(&self, document: &Value) -> Vec<ReferenceViolation> {
+        let targets: HashSet<String> = PathPattern::parse(&self.target)
+            .select(document)
+            .into_iter()
+            .map(|(_, value)| value.to_string())
+            .collect();
+
+        PathPattern::parse(&self.path)
+            .select(document)
+            .into_iter()
+            .filter(|(_, value)| !targets.contains(&value.to_string()))
+            .map(|(path, value)| ReferenceViolation {
+                path,
+                value: value.clone(),
+                target: self.target.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Numeric tolerances for the values matched by `path`, e.g. `metrics.**`, replacing
+/// the global `--abs-tol` and `--rel-tol`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToleranceRule {
+    pub path: String,
+    #[serde(default)]
+    pub absolute: f64,
+    #[serde(default)]
+    pub relative: f64,
+}
+
+impl ToleranceRule {
+    pub fn to_path_tolerance(&self) -> PathTolerance {
+        PathTolerance {
+            pattern: PathPattern::parse(&self.path),
+            absolute: self.absolute,
+            relative: self.relative,
+        }
+    }
+}
+
+/// Compares the values matched by `path` as instants: ISO 8601 strings with each other
+/// and with epoch numbers, equal when at most `tolerance` (e.g. `"1s"`) apart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimestampRule {
+    pub path: String,
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_duration")]
+    pub tolerance: Duration,
+}
+
+impl TimestampRule {
+    pub fn to_path_timestamps(&self) -> PathTimestamps {
+        PathTimestamps {
+            pattern: PathPattern::parse(&self.path),
+            tolerance: self.tolerance,
+        }
+    }
+}
+
+/// Pairs the elements of the arrays matched by `path` by index or, with `ignore`, as a
+/// multiset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArrayOrderRule {
+    pub path: String,
+    pub order: ArrayOrder,
+}
+
+impl ArrayOrderRule {
+    pub fn to_path_array_order(&self) -> PathArrayOrder {
+        PathArrayOrder {
+            pattern: PathPattern::parse(&self.path),
+            order: self.order,
+        }
+    }
+}
+
+/// Compares the strings matched by `path` by the collation rules of `locale`, e.g. `de`,
+/// so strings the locale treats as equivalent at `strength` are equal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CollateRule {
+    pub path: String,
+    pub locale: String,
+    #[serde(default)]
+    pub strength: CollationStrength,
+}
+
+impl CollateRule {
+    pub fn to_path_collation(&self) -> Result<PathCollation> {
+        Ok(PathCollation {
+            pattern: PathPattern::parse(&self.path),
+            collation: Collation::new(&self.locale, self.strength)?,
+        })
+    }
+}
+
+/// Compares the values matched by `path` with a built-in comparator, e.g.
+/// `{"type": "email", "strip_plus_tags": true}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComparatorRule {
+    pub path: String,
+    pub comparator: Comparator,
+}
+
+impl ComparatorRule {
+    pub fn to_path_comparator(&self) -> PathComparator {
+        PathComparator {
+            pattern: PathPattern::parse(&self.path),
+            comparator: self.comparator.clone(),
+        }
+    }
+}
+
+/// Counts the paths matched by `path` by `weight` in the weighted similarity score, so
+/// a difference there costs more, or less, than elsewhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightRule {
+    pub path: String,
+    pub weight: f64,
+}
+
+impl WeightRule {
+    pub fn to_path_weight(&self) -> PathWeight {
+        PathWeight {
+            pattern: PathPattern::parse(&self.path),
+            weight: self.weight,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::json_diff::{child_path, pointer_to_path};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Which of the compared documents a schema violation was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaDocument {
+    Base,
+    Compare,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    pub document: SchemaDocument,
+    /// The value that violates the schema; for a missing required property, the path
+    /// the property is missing at
+    pub path: String,
+    pub message: String,
+}
+
+/// A compiled JSON Schema, with the draft taken from its `$schema` keyword.
+pub struct Schema {
+    validator: jsonschema::Validator,
+}
+
+impl Schema {
+    pub fn new(schema: &Value) -> Result<Schema> {
+        let validator = jsonschema::validator_for(schema).map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(Schema { validator })
+    }
+
+    /// Returns every violation of the schema in `value`, in document order.
+    pub fn validate(&self, value: &Value, document: SchemaDocument) -> Vec<SchemaViolation> {
+        self.validator
+            .iter_errors(value)
+            .map(|error| {
+                let pointer = error.instance_path().as_str();
+                let mut path = pointer_to_path(pointer).unwrap_or_else(|_| pointer.to_string());
+                if let jsonschema::error::ValidationErrorKind::Required {
+                    property: Value::String(property),
+                } = error.kind()
+                {
+                    path = child_path(&path, property);
+                }
+                SchemaViolation {
+                    document,
+                    path,
+                    message: error.to_string(),
+                }
+            })
+            .collect()
+    }
+}
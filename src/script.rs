@@ -0,0 +1,128 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What a comparator script decided for the values at a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Equal,
+    Different,
+    /// Neither reported nor counted as a difference, including anything below the path
+    Ignore,
+}
+
+/// A Rhai script defining `fn compare(path, expected, actual)`, called with the values
+/// found at a path in the base and compare file. It returns `"equal"`, `"different"` or
+/// `"ignore"`, or `()` to leave the values to the built-in comparison.
+pub struct ComparatorScript {
+    path: PathBuf,
+    #[cfg(feature = "scripting")]
+    engine: rhai::Engine,
+    #[cfg(feature = "scripting")]
+    ast: rhai::AST,
+    /// The first error a call ran into; comparisons can't fail, so it's reported once
+    /// the file has been compared
+    error: Mutex<Option<String>>,
+}
+
+impl ComparatorScript {
+    /// Compiles the script at `path`. Fails when it doesn't parse or has no
+    /// three-parameter `compare` function, or without the `scripting` feature.
+    #[cfg(feature = "scripting")]
+    pub fn load(path: &Path) -> Result<ComparatorScript> {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| anyhow::anyhow!("Invalid comparator script {:?}: {}", path, e))?;
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == "compare" && function.params.len() == 3)
+        {
+            anyhow::bail!(
+                "Comparator script {:?} doesn't define fn compare(path, expected, actual)",
+                path
+            );
+        }
+        Ok(ComparatorScript {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+            error: Mutex::new(None),
+        })
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    pub fn load(path: &Path) -> Result<ComparatorScript> {
+        anyhow::bail!(
+            "Comparator script {:?} needs Rhai, which this build leaves out; rebuild with --features scripting",
+            path
+        )
+    }
+
+    /// Asks the script about the values at `path`. `None` when it leaves them to the
+    /// built-in comparison, or when the call failed, which `take_error` then reports.
+    pub fn verdict(&self, path: &str, expected: &Value, actual: &Value) -> Option<Verdict> {
+        match self.call(path, expected, actual) {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                let mut error = self.error.lock().unwrap();
+                if error.is_none() {
+                    *error = Some(format!("at path {}: {}", path, e));
+                }
+                None
+            }
+        }
+    }
+
+    /// The first error a call to the script ran into since the last call.
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.error
+            .lock()
+            .unwrap()
+            .take()
+            .map(|error| anyhow::anyhow!("Comparator script {:?} failed {}", self.path, error))
+    }
+
+    #[cfg(feature = "scripting")]
+    fn call(&self, path: &str, expected: &Value, actual: &Value) -> Result<Option<Verdict>> {
+        let expected = rhai::serde::to_dynamic(expected)?;
+        let actual = rhai::serde::to_dynamic(actual)?;
+        let result: rhai::Dynamic = self.engine.call_fn(
+            &mut rhai::Scope::new(),
+            &self.ast,
+            "compare",
+            (path.to_string(), expected, actual),
+        )?;
+        if result.is_unit() {
+            return Ok(None);
+        }
+        match result.into_string().as_deref() {
+            Ok("equal") => Ok(Some(Verdict::Equal)),
+            Ok("different") => Ok(Some(Verdict::Different)),
+            Ok("ignore") => Ok(Some(Verdict::Ignore)),
+            Ok(other) => anyhow::bail!(
+                "compare returned {:?}; expected \"equal\", \"different\", \"ignore\" or ()",
+                other
+            ),
+            Err(type_name) => anyhow::bail!(
+                "compare returned {}; expected \"equal\", \"different\", \"ignore\" or ()",
+                type_name
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn call(&self, _path: &str, _expected: &Value, _actual: &Value) -> Result<Option<Verdict>> {
+        Ok(None)
+    }
+}
+
+impl fmt::Debug for ComparatorScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComparatorScript")
+            .field("path", &self.path)
+            .finish()
+    }
+}
@@ -0,0 +1,185 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::json_diff::child_path;
+
+/// The structural schema of a document, as inferred by `infer_shape`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferredShape {
+    /// The schema, compared instead of the document
+    pub schema: Value,
+    /// Paths within the schema of the keys that only some of the merged objects have
+    pub optional_paths: BTreeSet<String>,
+}
+
+/// Infers the structural schema of a document, so that two documents can be compared by
+/// shape instead of by value. Scalars become their type name (`null`, `boolean`,
+/// `integer`, `number` or `string`), objects keep their keys with the shape of each
+/// member, and arrays become a single element with the shape all their elements merge
+/// to, or stay empty.
+///
+/// Keys missing from some of the merged objects are listed in `optional_paths`, kept
+/// apart from the schema so a key that became optional isn't a changed value, and values
+/// of different types become a union such as `"integer|string"`; integers and other
+/// numbers merge to `number`.
+pub fn infer_shape(value: &Value) -> InferredShape {
+    let mut optional_paths = BTreeSet::new();
+    let schema = infer(value).into_schema("", &mut optional_paths);
+    InferredShape {
+        schema,
+        optional_paths,
+    }
+}
+
+/// An inferred shape, with the optionality of object members alongside it.
+enum Shape {
+    /// The type names of the values merged here, more than one for a union
+    Types(BTreeSet<&'static str>),
+    Object(BTreeMap<String, Member>),
+    /// The shape all elements merge to, `None` for an empty array
+    Array(Option<Box<Shape>>),
+}
+
+struct Member {
+    shape: Shape,
+    /// Only some of the merged objects have the key
+    optional: bool,
+}
+
+fn infer(value: &Value) -> Shape {
+    match value {
+        Value::Object(map) => Shape::Object(
+            map.iter()
+                .map(|(key, child)| {
+                    let member = Member {
+                        shape: infer(child),
+                        optional: false,
+                    };
+                    (key.clone(), member)
+                })
+                .collect(),
+        ),
+        Value::Array(arr) => Shape::Array(arr.iter().map(infer).reduce(merge).map(Box::new)),
+        scalar => Shape::Types(BTreeSet::from([type_name(scalar)])),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+impl Shape {
+    fn type_names(self) -> BTreeSet<&'static str> {
+        match self {
+            Shape::Types(types) => types,
+            Shape::Object(_) => BTreeSet::from(["object"]),
+            Shape::Array(_) => BTreeSet::from(["array"]),
+        }
+    }
+
+    /// The shape as a schema value, adding the paths of its optional keys, below `path`,
+    /// to `optional_paths`.
+    fn into_schema(self, path: &str, optional_paths: &mut BTreeSet<String>) -> Value {
+        match self {
+            Shape::Types(types) => Value::String(types.into_iter().collect::<Vec<_>>().join("|")),
+            Shape::Object(members) => Value::Object(
+                members
+                    .into_iter()
+                    .map(|(key, member)| {
+                        let path = child_path(path, &key);
+                        let schema = member.shape.into_schema(&path, optional_paths);
+                        if member.optional {
+                            optional_paths.insert(path);
+                        }
+                        (key, schema)
+                    })
+                    .collect(),
+            ),
+            Shape::Array(element) => Value::Array(
+                element
+                    .map(|element| element.into_schema(&format!("{}[0]", path), optional_paths))
+                    .into_iter()
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Merges two inferred shapes into one that describes values of either.
+fn merge(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (Shape::Object(a), Shape::Object(b)) => Shape::Object(merge_objects(a, b)),
+        (Shape::Array(a), Shape::Array(b)) => Shape::Array(match (a, b) {
+            (Some(a), Some(b)) => Some(Box::new(merge(*a, *b))),
+            (a, b) => a.or(b),
+        }),
+        (a, b) => {
+            let mut types = a.type_names();
+            types.extend(b.type_names());
+            if types.contains("number") {
+                types.remove("integer");
+            }
+            Shape::Types(types)
+        }
+    }
+}
+
+fn merge_objects(
+    a: BTreeMap<String, Member>,
+    mut b: BTreeMap<String, Member>,
+) -> BTreeMap<String, Member> {
+    let mut merged = BTreeMap::new();
+    for (key, member) in a {
+        let member = match b.remove(&key) {
+            Some(other) => Member {
+                shape: merge(member.shape, other.shape),
+                optional: member.optional || other.optional,
+            },
+            None => Member {
+                optional: true,
+                ..member
+            },
+        };
+        merged.insert(key, member);
+    }
+    for (key, member) in b {
+        merged.insert(
+            key,
+            Member {
+                optional: true,
+                ..member
+            },
+        );
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn optional_keys_are_kept_apart_from_the_schema() {
+        let shape = infer_shape(&json!({
+            "items": [{"id": 1, "debug": "x"}, {"id": 2.5}],
+            "$optional": true
+        }));
+        assert_eq!(
+            shape.schema,
+            json!({"items": [{"id": "number", "debug": "string"}], "$optional": "boolean"})
+        );
+        assert_eq!(
+            shape.optional_paths,
+            BTreeSet::from(["items[0].debug".to_string()])
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use clap::ValueEnum;
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+
+/// A rewrite applied to every string value before comparing, so strings that differ
+/// only in the rewritten aspect compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StringNormalization {
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Compare case-insensitively by lowercasing
+    Case,
+    /// Unicode canonical composition (NFC), so precomposed and decomposed accents match
+    UnicodeNfc,
+}
+
+/// Applies the normalizations, in the order given, to every string value. Object keys
+/// are left as they are, so no two members collapse into one.
+pub fn normalize_strings(value: &mut Value, normalizations: &[StringNormalization]) {
+    if normalizations.is_empty() {
+        return;
+    }
+
+    match value {
+        Value::String(s) => {
+            for normalization in normalizations {
+                *s = match normalization {
+                    StringNormalization::Trim => s.trim().to_string(),
+                    StringNormalization::Case => s.to_lowercase(),
+                    StringNormalization::UnicodeNfc => s.nfc().collect(),
+                };
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                normalize_strings(child, normalizations);
+            }
+        }
+        Value::Object(map) => {
+            for child in map.values_mut() {
+                normalize_strings(child, normalizations);
+            }
+        }
+        _ => {}
+    }
+}
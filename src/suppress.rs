@@ -0,0 +1,63 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+use crate::json_diff::child_path;
+
+/// The member that declares suppressions for the object it's in, e.g.
+/// `"__jsondiff": {"ignore": ["legacyField"]}`.
+pub const SUPPRESSION_KEY: &str = "__jsondiff";
+
+/// A pattern relative to the object at `path` as a pattern from the document root.
+fn absolute(path: &str, pattern: &str) -> String {
+    if path.is_empty() || pattern.starts_with('[') {
+        format!("{}{}", path, pattern)
+    } else {
+        format!("{}.{}", path, pattern)
+    }
+}
+
+fn take(value: &mut Value, path: &str, ignores: &mut Vec<String>) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            if let Some(suppression) = map.remove(SUPPRESSION_KEY) {
+                let at = child_path(path, SUPPRESSION_KEY);
+                let Value::Object(suppression) = suppression else {
+                    bail!("{} must be an object, e.g. {{\"ignore\": [\"field\"]}}", at);
+                };
+                for (key, value) in suppression {
+                    match (key.as_str(), value) {
+                        ("ignore", Value::Array(patterns)) => {
+                            for pattern in patterns {
+                                let Value::String(pattern) = pattern else {
+                                    bail!("{}.ignore must be a list of path patterns", at);
+                                };
+                                ignores.push(absolute(path, &pattern));
+                            }
+                        }
+                        ("ignore", _) => bail!("{}.ignore must be a list of path patterns", at),
+                        (key, _) => bail!("Unknown key {:?} in {}, expected \"ignore\"", key, at),
+                    }
+                }
+            }
+            for (key, child) in map.iter_mut() {
+                take(child, &child_path(path, key), ignores)?;
+            }
+        }
+        Value::Array(arr) => {
+            for (i, child) in arr.iter_mut().enumerate() {
+                take(child, &format!("{}[{}]", path, i), ignores)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Removes every `__jsondiff` member from the document and returns the ignore patterns
+/// they declare, relative to their object, as patterns from the root: `"legacyField"`
+/// in a `__jsondiff` member of `config` is `config.legacyField`.
+pub fn take_inline_ignores(value: &mut Value) -> Result<Vec<String>> {
+    let mut ignores = Vec::new();
+    take(value, "", &mut ignores)?;
+    Ok(ignores)
+}
@@ -0,0 +1,261 @@
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::json_diff::get_value_type;
+use crate::timestamp::parse_iso8601;
+
+/// A type placeholder in the base document, written as `"<<type>>"` or
+/// `"<<type:key=value,key=value>>"`.
+///
+/// Supported types are the JSON types (`null`, `boolean`, `number`, `string`,
+/// `array`, `object`) plus `integer` and `any`. Strings accept `minlen`, `maxlen`
+/// and `pattern` refinements, numbers accept `min` and `max`. A `pattern` takes the
+/// rest of the template, so it must come last.
+#[derive(Debug, Clone)]
+pub struct TypeTemplate {
+    pub value_type: String,
+    pub constraints: Vec<Constraint>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    MinLen(usize),
+    MaxLen(usize),
+    Pattern(Regex),
+    Min(f64),
+    Max(f64),
+    /// An ISO 8601 date or date-time
+    Timestamp,
+}
+
+const TEMPLATE_TYPES: [&str; 8] = [
+    "any", "null", "boolean", "number", "integer", "string", "array", "object",
+];
+
+/// Parses a base value as a type template. Returns `Ok(None)` for ordinary values.
+pub fn parse_template(value: &Value) -> Result<Option<TypeTemplate>> {
+    let Some(inner) = value
+        .as_str()
+        .and_then(|s| s.strip_prefix("<<"))
+        .and_then(|s| s.strip_suffix(">>"))
+    else {
+        return Ok(None);
+    };
+
+    let (value_type, refinements) = match inner.split_once(':') {
+        Some((value_type, refinements)) => (value_type.trim(), refinements),
+        None => (inner.trim(), ""),
+    };
+    if !TEMPLATE_TYPES.contains(&value_type) {
+        bail!("unknown template type {:?}", value_type);
+    }
+
+    let mut constraints = Vec::new();
+    let mut rest = refinements;
+    while !rest.is_empty() {
+        let (key, value) = rest
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected key=value in template refinement {:?}", rest))?;
+        let key = key.trim();
+        let value = if key == "pattern" {
+            rest = "";
+            value
+        } else {
+            let (value, remainder) = value.split_once(',').unwrap_or((value, ""));
+            rest = remainder;
+            value.trim()
+        };
+
+        let constraint = match (key, value_type) {
+            ("minlen", "string") => Constraint::MinLen(parse_number(key, value)?),
+            ("maxlen", "string") => Constraint::MaxLen(parse_number(key, value)?),
+            ("pattern", "string") => Constraint::Pattern(
+                Regex::new(value).map_err(|e| anyhow!("invalid pattern {:?}: {}", value, e))?,
+            ),
+            ("min", "number" | "integer") => Constraint::Min(parse_number(key, value)?),
+            ("max", "number" | "integer") => Constraint::Max(parse_number(key, value)?),
+            _ => bail!("refinement {:?} is not supported for {}", key, value_type),
+        };
+        constraints.push(constraint);
+    }
+
+    Ok(Some(TypeTemplate {
+        value_type: value_type.to_string(),
+        constraints,
+    }))
+}
+
+/// Parses a base value as a `$` matcher, which matches values by shape: `"$any"`,
+/// `"$string"`, `"$number"`, `"$integer"`, `"$boolean"`, `"$uuid"`, `"$iso8601"` or
+/// `"$regex:<pattern>"`. Returns `Ok(None)` for ordinary values, including other strings
+/// starting with `$`.
+pub fn parse_matcher(value: &Value) -> Result<Option<TypeTemplate>> {
+    let Some(matcher) = value.as_str().and_then(|s| s.strip_prefix('$')) else {
+        return Ok(None);
+    };
+
+    let template = |value_type: &str, constraints| TypeTemplate {
+        value_type: value_type.to_string(),
+        constraints,
+    };
+    Ok(Some(match matcher {
+        "any" | "string" | "number" | "integer" => template(matcher, Vec::new()),
+        "boolean" => template("boolean", Vec::new()),
+        "uuid" => template(
+            "string",
+            vec![Constraint::Pattern(Regex::new(
+                "^(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$",
+            )?)],
+        ),
+        "iso8601" => template("string", vec![Constraint::Timestamp]),
+        _ => match matcher.strip_prefix("regex:") {
+            Some(pattern) => template(
+                "string",
+                vec![Constraint::Pattern(Regex::new(pattern).map_err(|e| {
+                    anyhow!("invalid pattern {:?}: {}", pattern, e)
+                })?)],
+            ),
+            None => return Ok(None),
+        },
+    }))
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("invalid value {:?} for {}", value, key))
+}
+
+impl TypeTemplate {
+    /// Checks a value against the template, returning the reason it does not match.
+    pub fn check(&self, value: &Value) -> Option<String> {
+        let type_matches = match self.value_type.as_str() {
+            "any" => true,
+            "integer" => value.is_i64() || value.is_u64(),
+            expected => get_value_type(value) == expected,
+        };
+        if !type_matches {
+            return Some(format!(
+                "expected {}, found {}",
+                self.value_type,
+                get_value_type(value)
+            ));
+        }
+
+        self.constraints
+            .iter()
+            .find_map(|constraint| match (constraint, value) {
+                (Constraint::MinLen(min), Value::String(s)) if s.chars().count() < *min => Some(
+                    format!("length {} is below minlen {}", s.chars().count(), min),
+                ),
+                (Constraint::MaxLen(max), Value::String(s)) if s.chars().count() > *max => Some(
+                    format!("length {} exceeds maxlen {}", s.chars().count(), max),
+                ),
+                (Constraint::Pattern(re), Value::String(s)) if !re.is_match(s) => {
+                    Some(format!("does not match pattern {}", re.as_str()))
+                }
+                (Constraint::Min(min), Value::Number(n)) if n.as_f64()? < *min => {
+                    Some(format!("{} is below min {}", n, min))
+                }
+                (Constraint::Max(max), Value::Number(n)) if n.as_f64()? > *max => {
+                    Some(format!("{} exceeds max {}", n, max))
+                }
+                (Constraint::Timestamp, Value::String(s)) if parse_iso8601(s).is_none() => {
+                    Some("is not an ISO 8601 timestamp".to_string())
+                }
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn template(value: Value) -> TypeTemplate {
+        parse_template(&value).unwrap().unwrap()
+    }
+
+    fn matcher(value: &str) -> TypeTemplate {
+        parse_matcher(&json!(value)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn ordinary_values_are_not_templates() {
+        for value in [json!("string"), json!("<<string"), json!(1), json!(null)] {
+            assert!(parse_template(&value).unwrap().is_none());
+        }
+        assert!(parse_matcher(&json!("$ref")).unwrap().is_none());
+        assert!(parse_matcher(&json!(1)).unwrap().is_none());
+    }
+
+    #[test]
+    fn templates_check_types_and_refinements() {
+        let name = template(json!("<<string:minlen=2,maxlen=4>>"));
+        assert_eq!(name.check(&json!("abc")), None);
+        assert_eq!(
+            name.check(&json!("a")).unwrap(),
+            "length 1 is below minlen 2"
+        );
+        assert_eq!(
+            name.check(&json!(1)).unwrap(),
+            "expected string, found number"
+        );
+
+        let count = template(json!("<<integer:min=0,max=10>>"));
+        assert_eq!(count.check(&json!(10)), None);
+        assert_eq!(count.check(&json!(11)).unwrap(), "11 exceeds max 10");
+        assert!(count.check(&json!(1.5)).is_some());
+        assert_eq!(template(json!("<<any>>")).check(&json!([1])), None);
+    }
+
+    #[test]
+    fn pattern_takes_the_rest_of_the_template() {
+        let code = template(json!("<<string:minlen=1,pattern=^[a,b]+$>>"));
+        assert_eq!(code.check(&json!("a,b")), None);
+        assert_eq!(
+            code.check(&json!("c")).unwrap(),
+            "does not match pattern ^[a,b]+$"
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_errors() {
+        for value in [
+            "<<text>>",
+            "<<number:minlen=1>>",
+            "<<string:minlen>>",
+            "<<string:minlen=x>>",
+            "<<string:pattern=(>>",
+        ] {
+            assert!(parse_template(&json!(value)).is_err(), "{}", value);
+        }
+        assert!(parse_matcher(&json!("$regex:(")).is_err());
+    }
+
+    #[test]
+    fn matchers_check_value_shapes() {
+        let uuid = matcher("$uuid");
+        assert_eq!(
+            uuid.check(&json!("123E4567-e89b-12d3-a456-426614174000")),
+            None
+        );
+        assert!(uuid.check(&json!("123")).is_some());
+
+        let timestamp = matcher("$iso8601");
+        assert_eq!(timestamp.check(&json!("2024-01-31T12:00:00Z")), None);
+        assert_eq!(
+            timestamp.check(&json!("yesterday")).unwrap(),
+            "is not an ISO 8601 timestamp"
+        );
+
+        assert_eq!(matcher("$regex:^v\\d+$").check(&json!("v2")), None);
+        assert_eq!(matcher("$boolean").check(&json!(true)), None);
+        assert_eq!(
+            matcher("$integer").check(&json!("1")).unwrap(),
+            "expected integer, found string"
+        );
+    }
+}
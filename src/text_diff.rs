@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::ChangeTag;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Strings that have less in common than this are changed as a whole.
+const MIN_STRING_SIMILARITY: f32 = 0.5;
+
+/// Shorter unchanged runs between two changes are marked as changed too, so a changed
+/// word is one mark rather than a scatter of single characters.
+const MIN_UNCHANGED_RUN: usize = 3;
+
+/// The parts of two strings that differ, as byte ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedRanges {
+    pub base: Vec<Range<usize>>,
+    pub compare: Vec<Range<usize>>,
+}
+
+/// A subtree compared as pretty-printed text rather than path by path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextDiff {
+    pub path: String,
+    /// Line-based unified diff of the pretty-printed base and compare values
+    pub diff: String,
+    pub base_value: Value,
+    pub compare_value: Value,
+}
+
+/// Renders both values as pretty-printed JSON and diffs them line by line, returning
+/// `None` when the renderings are identical.
+pub fn text_diff(path: &str, base: &Value, compare: &Value) -> Option<TextDiff> {
+    let base_text = pretty(base);
+    let compare_text = pretty(compare);
+    if base_text == compare_text {
+        return None;
+    }
+
+    let diff = similar::TextDiff::from_lines(&base_text, &compare_text)
+        .unified_diff()
+        .context_radius(3)
+        .header("base", "compare")
+        .to_string();
+
+    Some(TextDiff {
+        path: path.to_string(),
+        diff,
+        base_value: base.clone(),
+        compare_value: compare.clone(),
+    })
+}
+
+/// Appends a range, merging it into the last one when they touch.
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+/// The byte ranges of `base` and `compare` a character diff finds changed, e.g. the
+/// version in two URLs, or `None` when the strings have too little in common for the
+/// changes to be worth pointing out.
+pub fn changed_ranges(base: &str, compare: &str) -> Option<ChangedRanges> {
+    // long strings fall back to a coarser diff rather than taking quadratic time
+    let diff = similar::TextDiff::configure()
+        .timeout(Duration::from_millis(50))
+        .diff_chars(base, compare);
+    if diff.ratio() < MIN_STRING_SIMILARITY {
+        return None;
+    }
+    // runs of changes with the same tag, as their tag, bytes and characters
+    let mut runs: Vec<(ChangeTag, usize, usize)> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let len = change.value().len();
+        match runs.last_mut() {
+            Some(run) if run.0 == change.tag() => {
+                run.1 += len;
+                run.2 += 1;
+            }
+            _ => runs.push((change.tag(), len, 1)),
+        }
+    }
+    let mut ranges = ChangedRanges::default();
+    let (mut old, mut new) = (0, 0);
+    for (i, &(tag, len, chars)) in runs.iter().enumerate() {
+        match tag {
+            ChangeTag::Equal if chars < MIN_UNCHANGED_RUN && i > 0 && i + 1 < runs.len() => {
+                push_range(&mut ranges.base, old..old + len);
+                push_range(&mut ranges.compare, new..new + len);
+                old += len;
+                new += len;
+            }
+            ChangeTag::Equal => {
+                old += len;
+                new += len;
+            }
+            ChangeTag::Delete => {
+                push_range(&mut ranges.base, old..old + len);
+                old += len;
+            }
+            ChangeTag::Insert => {
+                push_range(&mut ranges.compare, new..new + len);
+                new += len;
+            }
+        }
+    }
+    Some(ranges)
+}
+
+fn pretty(value: &Value) -> String {
+    // a trailing newline keeps the last line from being reported as changed
+    let mut text = serde_json::to_string_pretty(value).unwrap_or_default();
+    text.push('\n');
+    text
+}
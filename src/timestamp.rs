@@ -0,0 +1,104 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Epoch numbers at or above this are read as milliseconds; as seconds they would lie
+/// after the year 5000.
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+/// Parses an ISO 8601 / RFC 3339 string into nanoseconds since the Unix epoch. Times
+/// without an offset, and plain dates, are taken as UTC.
+pub fn parse_iso8601(s: &str) -> Option<i128> {
+    let s = s.trim();
+    let datetime = DateTime::parse_from_rfc3339(s)
+        .map(|datetime| datetime.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .ok()?;
+    let utc = datetime.and_utc();
+    Some(i128::from(utc.timestamp()) * 1_000_000_000 + i128::from(utc.timestamp_subsec_nanos()))
+}
+
+/// Reads an epoch number as nanoseconds since the Unix epoch: seconds, or milliseconds
+/// for large values.
+fn epoch_nanos(n: f64) -> i128 {
+    if n.abs() >= EPOCH_MILLIS_THRESHOLD {
+        (n * 1e6) as i128
+    } else {
+        (n * 1e9) as i128
+    }
+}
+
+/// Compares two values as instants when at least one is an ISO 8601 string and the
+/// other an ISO 8601 string or an epoch number, returning `None` otherwise. Two numbers
+/// are left to the numeric comparison, so counters don't get a time tolerance.
+pub fn timestamps_equal(a: &Value, b: &Value, tolerance: Duration) -> Option<bool> {
+    let instant = |value: &Value| match value {
+        Value::String(s) => parse_iso8601(s),
+        Value::Number(n) => n.as_f64().map(epoch_nanos),
+        _ => None,
+    };
+    if !a.is_string() && !b.is_string() {
+        return None;
+    }
+
+    let (a, b) = (instant(a)?, instant(b)?);
+    Some(a.abs_diff(b) <= tolerance.as_nanos())
+}
+
+/// Parses durations such as `500ms`, `1s`, `1.5s`, `2m`, `1h` or `1d`; a plain number
+/// is taken as seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let Ok(number) = number.parse::<f64>() else {
+        bail!(
+            "invalid duration {:?}, expected e.g. 500ms, 1s, 2m or 1h",
+            s
+        );
+    };
+
+    let seconds = match unit.trim() {
+        "ns" => number / 1e9,
+        "us" | "µs" => number / 1e6,
+        "ms" => number / 1e3,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => bail!(
+            "invalid duration unit {:?} in {:?}, expected ns, us, ms, s, m, h or d",
+            unit,
+            s
+        ),
+    };
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| anyhow::anyhow!("duration out of range: {:?}", s))
+}
+
+/// Deserializes a duration written like `parse_duration` accepts, or as a number of
+/// seconds.
+pub fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => parse_duration(&s).map_err(serde::de::Error::custom),
+        Value::Number(n) => n
+            .as_f64()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration: {}", n))),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid duration {}, expected e.g. \"500ms\" or \"1s\"",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,942 @@
+//! The interactive results browser of `--tui`: the entries of every compare file as a
+//! tree of the paths they're reported at, which can be expanded down to the values,
+//! filtered by kind and searched.
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line as TextLine, Span as TextSpan};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::render::{Category, Entry, Field, FileReport, Item, Line, Span, Status, Tone};
+use json_diff_checker::json_diff::PathPart;
+
+/// A compare file, a path with entries at or below it, or a section of entries that
+/// aren't reported at paths.
+struct Node {
+    /// The path segment of a path node
+    part: Option<PathPart>,
+    label: Vec<Span>,
+    children: Vec<usize>,
+    diffs: Vec<Diff>,
+    expanded: bool,
+}
+
+impl Node {
+    fn new(part: Option<PathPart>, label: Vec<Span>) -> Node {
+        Node {
+            part,
+            label,
+            children: Vec::new(),
+            diffs: Vec::new(),
+            expanded: false,
+        }
+    }
+}
+
+/// One entry of a report, with the lines it expands to.
+struct Diff {
+    category: Category,
+    label: Vec<Span>,
+    /// The path or other subject the entry is reported at, which searches look in
+    subject: String,
+    lines: Vec<Line>,
+    expanded: bool,
+}
+
+impl Diff {
+    /// An entry reported at a path is shown at the path's node, so it's labeled with
+    /// its kind; other entries with their subject.
+    fn new(category: Category, entry: Entry) -> Diff {
+        let tone = Tone::Category(category);
+        let mut label = vec![Span::new(category.symbol(), tone)];
+        if category.has_path() {
+            label.push(Span::new(category.key().replace('_', " "), tone));
+        } else {
+            label.push(Span::new(&entry.subject, Tone::Strong));
+        }
+        label.extend(entry.suffix);
+        if let Some(id) = entry.id {
+            label.push(Span::new(format!("#{}", id), Tone::Muted));
+        }
+        // values are expanded pretty-printed, a line of the listing per line
+        let lines = match entry.columns {
+            Some(columns) => pretty_lines("expected", &columns.base, Tone::Base)
+                .chain(pretty_lines("actual", &columns.compare, Tone::Compare))
+                .collect(),
+            None => entry.lines,
+        };
+        Diff {
+            category,
+            label,
+            subject: entry.subject,
+            lines,
+            expanded: false,
+        }
+    }
+
+    fn text(spans: &[Span]) -> impl Iterator<Item = &str> {
+        spans.iter().map(|span| span.text.as_str())
+    }
+
+    /// Whether the subject or one of the lines contains the lowercase query.
+    fn contains(&self, query: &str) -> bool {
+        self.subject.to_lowercase().contains(query)
+            || self
+                .lines
+                .iter()
+                .any(|line| Diff::text(&line.spans).any(|text| text.to_lowercase().contains(query)))
+    }
+}
+
+fn pretty_lines<'a>(
+    label: &'static str,
+    value: &'a str,
+    tone: Tone,
+) -> impl Iterator<Item = Line> + 'a {
+    value.lines().enumerate().map(move |(i, text)| Line {
+        label: (i == 0).then_some(label),
+        spans: vec![Span::new(text, tone)],
+    })
+}
+
+/// A visible line of the tree.
+#[derive(Clone, Copy)]
+enum Row {
+    Node {
+        node: usize,
+        depth: usize,
+    },
+    Diff {
+        node: usize,
+        diff: usize,
+        depth: usize,
+    },
+    Detail {
+        node: usize,
+        diff: usize,
+        line: usize,
+        depth: usize,
+    },
+}
+
+impl Row {
+    fn depth(self) -> usize {
+        match self {
+            Row::Node { depth, .. } | Row::Diff { depth, .. } | Row::Detail { depth, .. } => depth,
+        }
+    }
+}
+
+struct Browser {
+    title: String,
+    overall: Vec<Field>,
+    nodes: Vec<Node>,
+    /// The node of each compare file
+    files: Vec<usize>,
+    /// The kinds of entries the reports have, which the filter cycles through
+    categories: Vec<Category>,
+    filter: Option<Category>,
+    query: String,
+    /// The query being typed, until it's applied
+    input: Option<String>,
+    rows: Vec<Row>,
+    state: ListState,
+    /// Rows of the list on screen, for paging
+    height: usize,
+}
+
+impl Browser {
+    fn new(title: &str, reports: Vec<FileReport>, overall: Vec<Field>) -> Browser {
+        let mut browser = Browser {
+            title: title.to_string(),
+            overall,
+            nodes: Vec::new(),
+            files: Vec::new(),
+            categories: Vec::new(),
+            filter: None,
+            query: String::new(),
+            input: None,
+            rows: Vec::new(),
+            state: ListState::default(),
+            height: 0,
+        };
+        for report in reports {
+            browser.add_report(report);
+        }
+        // with a single compare file its top-level paths are shown right away
+        if let [file] = browser.files[..] {
+            browser.nodes[file].expanded = true;
+        }
+        browser.rebuild();
+        browser.state.select(Some(0));
+        browser
+    }
+
+    fn add_report(&mut self, report: FileReport) {
+        let mut label = vec![Span::new(&report.name, Tone::Strong)];
+        label.extend(status(&report.status));
+        let file = self.push(None, label);
+        self.files.push(file);
+        for section in &report.sections {
+            if !self.categories.contains(&section.category) {
+                self.categories.push(section.category);
+            }
+            // a section without entries, such as the similarity, is shown by its title
+            if section.items.is_empty() {
+                let entry = text_entry(&[Span::new(&section.title, Tone::Plain)], None);
+                self.nodes[file]
+                    .diffs
+                    .push(Diff::new(section.category, entry));
+                continue;
+            }
+            // entries at paths go to the tree of paths, everything else to a node of
+            // its section
+            let section_node = (!section.category.has_path()).then(|| {
+                let node = self.push(
+                    None,
+                    vec![Span::new(&section.title, Tone::Category(section.category))],
+                );
+                self.nodes[file].children.push(node);
+                node
+            });
+            for item in &section.items {
+                let (node, diff) = match item {
+                    Item::Entry(entry) => {
+                        let node = match section_node {
+                            Some(node) => node,
+                            None => self.path_node(file, &report.path_parts(entry)),
+                        };
+                        (node, Diff::new(section.category, entry.clone()))
+                    }
+                    Item::Line(line) => {
+                        let entry = text_entry(&line.spans, line.label);
+                        (
+                            section_node.unwrap_or(file),
+                            Diff::new(section.category, entry),
+                        )
+                    }
+                    Item::Table(table) => {
+                        for row in &table.rows {
+                            let entry = text_entry(row, None);
+                            let node = section_node.unwrap_or(file);
+                            self.nodes[node]
+                                .diffs
+                                .push(Diff::new(section.category, entry));
+                        }
+                        continue;
+                    }
+                };
+                self.nodes[node].diffs.push(diff);
+            }
+        }
+        self.sort(file);
+    }
+
+    fn push(&mut self, part: Option<PathPart>, label: Vec<Span>) -> usize {
+        self.nodes.push(Node::new(part, label));
+        self.nodes.len() - 1
+    }
+
+    /// The node of a path below a file's node, added as needed.
+    fn path_node(&mut self, file: usize, parts: &[PathPart]) -> usize {
+        let mut node = file;
+        for part in parts {
+            let existing = self.nodes[node]
+                .children
+                .iter()
+                .copied()
+                .find(|&child| self.nodes[child].part.as_ref() == Some(part));
+            node = match existing {
+                Some(child) => child,
+                None => {
+                    let label = match part {
+                        PathPart::Key(key) => key.clone(),
+                        PathPart::Index(index) => format!("[{}]", index),
+                    };
+                    let child = self.push(Some(part.clone()), vec![Span::new(label, Tone::Plain)]);
+                    self.nodes[node].children.push(child);
+                    child
+                }
+            };
+        }
+        node
+    }
+
+    /// Puts array elements in index order; object keys keep the order they were
+    /// reported in, as the tree output does.
+    fn sort(&mut self, node: usize) {
+        let mut children = std::mem::take(&mut self.nodes[node].children);
+        children.sort_by_key(|&child| match self.nodes[child].part {
+            Some(PathPart::Index(index)) => Some(index),
+            _ => None,
+        });
+        for &child in &children {
+            self.sort(child);
+        }
+        self.nodes[node].children = children;
+    }
+
+    fn shows(&self, diff: &Diff) -> bool {
+        self.filter.is_none_or(|category| diff.category == category)
+            && (self.query.is_empty() || diff.contains(&self.query.to_lowercase()))
+    }
+
+    /// The number of shown entries at or below every node.
+    fn counts(&self) -> Vec<usize> {
+        fn count(browser: &Browser, node: usize, counts: &mut Vec<usize>) -> usize {
+            let own = browser.nodes[node]
+                .diffs
+                .iter()
+                .filter(|diff| browser.shows(diff))
+                .count();
+            let below: usize = browser.nodes[node]
+                .children
+                .iter()
+                .map(|&child| count(browser, child, counts))
+                .sum();
+            counts[node] = own + below;
+            own + below
+        }
+        let mut counts = vec![0; self.nodes.len()];
+        for &file in &self.files {
+            count(self, file, &mut counts);
+        }
+        counts
+    }
+
+    /// Lists the visible rows again, keeping the selection on the same row if it's
+    /// still visible.
+    fn rebuild(&mut self) {
+        fn push_rows(
+            browser: &Browser,
+            node: usize,
+            depth: usize,
+            counts: &[usize],
+            rows: &mut Vec<Row>,
+        ) {
+            rows.push(Row::Node { node, depth });
+            let node_ref = &browser.nodes[node];
+            if !node_ref.expanded {
+                return;
+            }
+            for (diff, entry) in node_ref.diffs.iter().enumerate() {
+                if !browser.shows(entry) {
+                    continue;
+                }
+                rows.push(Row::Diff {
+                    node,
+                    diff,
+                    depth: depth + 1,
+                });
+                if entry.expanded {
+                    rows.extend((0..entry.lines.len()).map(|line| Row::Detail {
+                        node,
+                        diff,
+                        line,
+                        depth: depth + 2,
+                    }));
+                }
+            }
+            for &child in &node_ref.children {
+                if counts[child] > 0 {
+                    push_rows(browser, child, depth + 1, counts, rows);
+                }
+            }
+        }
+
+        let selected = self.selected_row();
+        let counts = self.counts();
+        let mut new_rows = Vec::new();
+        for &file in &self.files {
+            push_rows(self, file, 0, &counts, &mut new_rows);
+        }
+        self.rows = new_rows;
+        let index = selected
+            .and_then(|selected| self.rows.iter().position(|row| same_row(*row, selected)))
+            .unwrap_or(0);
+        self.state
+            .select(Some(index.min(self.rows.len().saturating_sub(1))));
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.state
+            .selected()
+            .and_then(|index| self.rows.get(index).copied())
+    }
+
+    /// Expands every node with shown entries, so the matches of a filter or search are
+    /// all in view.
+    fn expand_matches(&mut self) {
+        let counts = self.counts();
+        for (node, count) in self.nodes.iter_mut().zip(counts) {
+            node.expanded = count > 0;
+        }
+    }
+
+    fn set_expanded(&mut self, row: Row, expanded: bool) {
+        match row {
+            Row::Node { node, .. } => self.nodes[node].expanded = expanded,
+            Row::Diff { node, diff, .. } => self.nodes[node].diffs[diff].expanded = expanded,
+            Row::Detail { .. } => {}
+        }
+        self.rebuild();
+    }
+
+    fn is_expanded(&self, row: Row) -> bool {
+        match row {
+            Row::Node { node, .. } => self.nodes[node].expanded,
+            Row::Diff { node, diff, .. } => self.nodes[node].diffs[diff].expanded,
+            Row::Detail { .. } => false,
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        let index = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(index.saturating_add_signed(delta).min(last)));
+    }
+
+    /// Selects the row of the node or entry the selected row is in.
+    fn select_parent(&mut self) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        let depth = self.rows[index].depth();
+        if let Some(parent) = self.rows[..index]
+            .iter()
+            .rposition(|row| row.depth() < depth)
+        {
+            self.state.select(Some(parent));
+        }
+    }
+
+    fn cycle_filter(&mut self, forward: bool) {
+        let position = self
+            .filter
+            .and_then(|filter| self.categories.iter().position(|&c| c == filter));
+        // after the last kind and before the first, all kinds are shown
+        self.filter = match (position, forward) {
+            (None, true) => self.categories.first().copied(),
+            (None, false) => self.categories.last().copied(),
+            (Some(position), true) => self.categories.get(position + 1).copied(),
+            (Some(position), false) => position
+                .checked_sub(1)
+                .map(|position| self.categories[position]),
+        };
+        self.expand_matches();
+        self.rebuild();
+    }
+
+    /// Handles a key, returning false once the browser should close.
+    fn key(&mut self, key: KeyEvent) -> bool {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    self.query = self.input.take().unwrap_or_default();
+                    self.expand_matches();
+                    self.rebuild();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        let page = self.height.max(1) as isize;
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Esc if self.filter.is_none() && self.query.is_empty() => return false,
+            KeyCode::Esc => {
+                self.filter = None;
+                self.query.clear();
+                self.rebuild();
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-page),
+            KeyCode::PageDown => self.move_by(page),
+            KeyCode::Home | KeyCode::Char('g') => self.state.select(Some(0)),
+            KeyCode::End | KeyCode::Char('G') => {
+                self.state.select(Some(self.rows.len().saturating_sub(1)))
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(row) = self.selected_row() {
+                    if self.is_expanded(row) {
+                        self.move_by(1);
+                    } else {
+                        self.set_expanded(row, true);
+                    }
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => match self.selected_row() {
+                Some(row) if self.is_expanded(row) => self.set_expanded(row, false),
+                _ => self.select_parent(),
+            },
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(row) = self.selected_row() {
+                    let expanded = self.is_expanded(row);
+                    self.set_expanded(row, !expanded);
+                }
+            }
+            KeyCode::Char('e') => {
+                self.expand_matches();
+                self.rebuild();
+            }
+            KeyCode::Char('c') => {
+                for node in &mut self.nodes {
+                    node.expanded = false;
+                }
+                self.rebuild();
+            }
+            KeyCode::Char('f') => self.cycle_filter(true),
+            KeyCode::Char('F') => self.cycle_filter(false),
+            KeyCode::Char('/') => self.input = Some(self.query.clone()),
+            _ => {}
+        }
+        true
+    }
+
+    fn row_line(&self, row: Row, counts: &[usize]) -> TextLine<'static> {
+        let indent = |depth: usize| TextSpan::raw("  ".repeat(depth));
+        match row {
+            Row::Node { node, depth } => {
+                let count = counts[node];
+                let node = &self.nodes[node];
+                let marker = if node.expanded { "▾ " } else { "▸ " };
+                let mut line = vec![indent(depth), TextSpan::raw(marker)];
+                line.extend(spans(&node.label));
+                if count > 0 {
+                    line.push(TextSpan::styled(
+                        format!(" ({})", count),
+                        style(Tone::Muted),
+                    ));
+                }
+                TextLine::from(line)
+            }
+            Row::Diff { node, diff, depth } => {
+                let diff = &self.nodes[node].diffs[diff];
+                let marker = match (diff.lines.is_empty(), diff.expanded) {
+                    (true, _) => "  ",
+                    (false, true) => "▾ ",
+                    (false, false) => "▸ ",
+                };
+                let mut line = vec![indent(depth), TextSpan::raw(marker)];
+                line.extend(spans(&diff.label));
+                TextLine::from(line)
+            }
+            Row::Detail {
+                node,
+                diff,
+                line,
+                depth,
+            } => {
+                let detail = &self.nodes[node].diffs[diff].lines[line];
+                let label = match detail.label {
+                    Some(label) => format!("{}:", label),
+                    None => String::new(),
+                };
+                let mut line = vec![
+                    indent(depth),
+                    TextSpan::styled(format!("{:<10}", label), style(Tone::Muted)),
+                ];
+                line.extend(spans(&detail.spans));
+                TextLine::from(line)
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, list, footer] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        self.height = list.height as usize;
+
+        let mut title = vec![TextSpan::styled(
+            self.title.clone(),
+            style(Tone::Strong).add_modifier(Modifier::BOLD),
+        )];
+        for field in &self.overall {
+            title.push(TextSpan::styled(
+                format!("  {}: ", field.label),
+                style(Tone::Muted),
+            ));
+            title.extend(spans(std::slice::from_ref(&field.value)));
+        }
+        let mut filters = vec![TextSpan::styled("Kind: ", style(Tone::Muted))];
+        filters.push(match self.filter {
+            Some(category) => TextSpan::styled(
+                category.key().replace('_', " "),
+                style(Tone::Category(category)),
+            ),
+            None => TextSpan::raw("all"),
+        });
+        if !self.query.is_empty() {
+            filters.push(TextSpan::styled("  Search: ", style(Tone::Muted)));
+            filters.push(TextSpan::raw(self.query.clone()));
+        }
+        let counts = self.counts();
+        let filtered = self.filter.is_some() || !self.query.is_empty();
+        if filtered && self.files.iter().all(|&file| counts[file] == 0) {
+            filters.push(TextSpan::styled(
+                "  (no matching entries)",
+                style(Tone::Muted),
+            ));
+        }
+        frame.render_widget(
+            Paragraph::new(vec![TextLine::from(title), TextLine::from(filters)]),
+            header,
+        );
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|&row| ListItem::new(self.row_line(row, &counts)))
+            .collect();
+        let list_widget =
+            List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list_widget, list, &mut self.state);
+
+        let footer_line = match &self.input {
+            Some(input) => TextLine::from(vec![
+                TextSpan::styled("/", style(Tone::Info)),
+                TextSpan::raw(input.clone()),
+                TextSpan::styled("█", style(Tone::Muted)),
+            ]),
+            None => TextLine::from(TextSpan::styled(
+                "↑↓ move  ←→ collapse/expand  e/c expand/collapse all  f/F kind  / search  \
+                 esc clear  q quit",
+                style(Tone::Muted),
+            )),
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Whether two rows show the same node, entry or line, wherever they are.
+fn same_row(a: Row, b: Row) -> bool {
+    match (a, b) {
+        (Row::Node { node: a, .. }, Row::Node { node: b, .. }) => a == b,
+        (
+            Row::Diff { node, diff, .. },
+            Row::Diff {
+                node: n, diff: d, ..
+            },
+        ) => node == n && diff == d,
+        (
+            Row::Detail {
+                node, diff, line, ..
+            },
+            Row::Detail {
+                node: n,
+                diff: d,
+                line: l,
+                ..
+            },
+        ) => node == n && diff == d && line == l,
+        _ => false,
+    }
+}
+
+/// An entry for a line or table row of a section, which has no subject of its own.
+fn text_entry(spans: &[Span], label: Option<&'static str>) -> Entry {
+    let text: Vec<&str> = Diff::text(spans).collect();
+    let subject = match label {
+        Some(label) => format!("{}: {}", label, text.join(" ")),
+        None => text.join("  "),
+    };
+    Entry {
+        subject,
+        suffix: Vec::new(),
+        lines: Vec::new(),
+        id: None,
+        columns: None,
+    }
+}
+
+fn status(status: &Status) -> Vec<Span> {
+    match status {
+        Status::Identical => vec![Span::new("✓ identical", Tone::Good)],
+        Status::Ok => vec![Span::new("✓ OK", Tone::Good)],
+        Status::Warnings(count) => vec![Span::new(
+            format!("✓ OK ({} nullability warning)", count),
+            Tone::Good,
+        )],
+        // the failures are listed comma-separated, as in the summary output
+        Status::Failed(parts) => parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| match parts.len() - i {
+                1 => part.clone(),
+                _ => Span::new(format!("{},", part.text), part.tone),
+            })
+            .collect(),
+    }
+}
+
+fn category_color(category: Category) -> Color {
+    match category {
+        Category::Missing | Category::Extra | Category::Moved | Category::Unmatched => {
+            Color::LightRed
+        }
+        Category::Different | Category::TextDiff => Color::LightYellow,
+        Category::HashOnly | Category::Partial | Category::DuplicateKey => Color::LightYellow,
+        Category::TypeMismatch | Category::Schema | Category::Optionality => Color::LightMagenta,
+        Category::Nullability | Category::Deltas | Category::Sampled | Category::Metadata => {
+            Color::LightCyan
+        }
+        Category::Template
+        | Category::ArrayLength
+        | Category::Duplicate
+        | Category::Reference
+        | Category::Distribution
+        | Category::Coerced
+        | Category::Similarity => Color::LightBlue,
+        Category::Alias | Category::Baselined | Category::Lossy | Category::Relaxed => {
+            Color::DarkGray
+        }
+    }
+}
+
+fn style(tone: Tone) -> Style {
+    let color = match tone {
+        Tone::Plain => return Style::default(),
+        Tone::Muted => Color::DarkGray,
+        Tone::Strong => Color::White,
+        Tone::Good => Color::LightGreen,
+        Tone::Info => Color::LightCyan,
+        Tone::Notice => Color::LightYellow,
+        Tone::Base | Tone::Inserted => Color::Green,
+        Tone::Compare | Tone::Deleted => Color::Red,
+        Tone::Category(category) => category_color(category),
+    };
+    Style::default().fg(color)
+}
+
+/// Spans separated by spaces, with the changed characters of marked spans highlighted.
+fn spans(spans: &[Span]) -> Vec<TextSpan<'static>> {
+    let mut styled = Vec::new();
+    for (i, span) in spans.iter().enumerate() {
+        if i > 0 {
+            styled.push(TextSpan::raw(" "));
+        }
+        let tone = style(span.tone);
+        let mut end = 0;
+        for mark in &span.marks {
+            styled.push(TextSpan::styled(
+                span.text[end..mark.start].to_string(),
+                tone,
+            ));
+            styled.push(TextSpan::styled(
+                span.text[mark.clone()].to_string(),
+                tone.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            ));
+            end = mark.end;
+        }
+        styled.push(TextSpan::styled(span.text[end..].to_string(), tone));
+    }
+    styled
+}
+
+/// Browses the reports until the user quits, restoring the terminal afterwards.
+pub fn browse(title: &str, reports: Vec<FileReport>, overall: Vec<Field>) -> Result<()> {
+    let mut browser = Browser::new(title, reports, overall);
+    let mut terminal = ratatui::init();
+    let result = browser.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Section;
+    use crate::PathStyle;
+
+    fn entry(subject: &str, lines: Vec<Line>) -> Item {
+        Item::Entry(Entry {
+            subject: subject.to_string(),
+            suffix: Vec::new(),
+            lines,
+            id: None,
+            columns: None,
+        })
+    }
+
+    fn section(category: Category, title: &str, items: Vec<Item>) -> Section {
+        Section {
+            category,
+            title: title.to_string(),
+            count: Some(items.len()),
+            items,
+        }
+    }
+
+    fn browser() -> Browser {
+        let expected = Line {
+            label: Some("expected"),
+            spans: vec![Span::new("\"1.0\"", Tone::Base)],
+        };
+        let report = FileReport {
+            name: "compare.json".to_string(),
+            path: "compare.json".to_string(),
+            path_style: PathStyle::Dotted,
+            hash: None,
+            sections: vec![
+                section(
+                    Category::Missing,
+                    "Missing paths",
+                    vec![
+                        entry("user.name", Vec::new()),
+                        entry("user.email", Vec::new()),
+                        entry("items[1]", Vec::new()),
+                    ],
+                ),
+                section(
+                    Category::Different,
+                    "Different values",
+                    vec![entry("version", vec![expected])],
+                ),
+            ],
+            status: Status::Failed(vec![Span::new("3 missing", Tone::Plain)]),
+        };
+        Browser::new("JSON Diff Checker", vec![report], Vec::new())
+    }
+
+    /// The visible rows: nodes by their label, entries by their subject, indented by
+    /// depth.
+    fn rows(browser: &Browser) -> Vec<String> {
+        browser
+            .rows
+            .iter()
+            .map(|&row| {
+                let text = match row {
+                    Row::Node { node, .. } => browser.nodes[node].label[0].text.clone(),
+                    Row::Diff { node, diff, .. } => browser.nodes[node].diffs[diff].subject.clone(),
+                    Row::Detail {
+                        node, diff, line, ..
+                    } => {
+                        let line = &browser.nodes[node].diffs[diff].lines[line];
+                        Diff::text(&line.spans).collect()
+                    }
+                };
+                format!("{}{}", "  ".repeat(row.depth()), text)
+            })
+            .collect()
+    }
+
+    fn press(browser: &mut Browser, keys: &str) -> bool {
+        keys.chars()
+            .all(|c| browser.key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)))
+    }
+
+    fn press_code(browser: &mut Browser, code: KeyCode) -> bool {
+        browser.key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn single_file_opens_at_its_top_level_paths() {
+        let browser = browser();
+        assert_eq!(
+            rows(&browser),
+            vec!["compare.json", "  user", "  items", "  version"]
+        );
+        assert_eq!(browser.state.selected(), Some(0));
+    }
+
+    #[test]
+    fn right_expands_and_left_collapses_or_selects_the_parent() {
+        let mut browser = browser();
+        press_code(&mut browser, KeyCode::Down);
+        press_code(&mut browser, KeyCode::Right);
+        assert_eq!(
+            rows(&browser),
+            vec![
+                "compare.json",
+                "  user",
+                "    name",
+                "    email",
+                "  items",
+                "  version",
+            ]
+        );
+        // on an expanded node, right moves into it
+        press_code(&mut browser, KeyCode::Right);
+        assert_eq!(browser.state.selected(), Some(2));
+        press_code(&mut browser, KeyCode::Left);
+        assert_eq!(browser.state.selected(), Some(1));
+        press_code(&mut browser, KeyCode::Left);
+        assert_eq!(rows(&browser).len(), 4);
+
+        // entries expand to their lines
+        press(&mut browser, "G");
+        press_code(&mut browser, KeyCode::Right);
+        press(&mut browser, "j");
+        press_code(&mut browser, KeyCode::Right);
+        assert_eq!(
+            &rows(&browser)[3..],
+            ["  version", "    version", "      \"1.0\""]
+        );
+    }
+
+    #[test]
+    fn filter_and_search_show_only_the_matching_entries() {
+        let mut browser = browser();
+        press(&mut browser, "f");
+        assert_eq!(browser.filter, Some(Category::Missing));
+        assert_eq!(
+            rows(&browser),
+            vec![
+                "compare.json",
+                "  user",
+                "    name",
+                "      user.name",
+                "    email",
+                "      user.email",
+                "  items",
+                "    [1]",
+                "      items[1]",
+            ]
+        );
+        press(&mut browser, "fF");
+        assert_eq!(browser.filter, Some(Category::Missing));
+
+        press_code(&mut browser, KeyCode::Esc);
+        press(&mut browser, "/email");
+        // the query only applies once it's entered
+        assert_eq!(browser.query, "");
+        press_code(&mut browser, KeyCode::Enter);
+        assert_eq!(
+            rows(&browser),
+            vec!["compare.json", "  user", "    email", "      user.email"]
+        );
+
+        // escape clears the search, and quits once nothing is left to clear
+        assert!(press_code(&mut browser, KeyCode::Esc));
+        assert_eq!(browser.query, "");
+        assert!(!press_code(&mut browser, KeyCode::Esc));
+        assert!(!press(&mut browser, "q"));
+    }
+}